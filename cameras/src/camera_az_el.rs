@@ -73,13 +73,25 @@ impl Default for AzElCamera {
     }
 }
 
+impl AzElCamera {
+    // Camera transform for the current focus, angles and radius
+    pub fn transform(&self) -> Transform {
+        let rotation = az_el_rotation(self.azimuth, self.elevation, &self.up_direction);
+        Transform {
+            translation: az_el_translation(self.focus, rotation, self.radius),
+            rotation,
+            ..default()
+        }
+    }
+}
+
 // Main function handling camera movement, such as rotation, panning, and zoom
 pub fn az_el_camera(
     windows: Query<&mut Window>,
     mut cursor_moved: EventReader<CursorMoved>, // Tracks mouse movement
     mut ev_scroll: EventReader<MouseWheel>, // Tracks mouse scroll
     input_mouse: Res<Input<MouseButton>>, // Tracks mouse button presses
-    mut query: Query<(&mut AzElCamera, &mut Transform, &Projection)>,
+    mut query: Query<(&mut AzElCamera, &mut Transform, &Projection, &Camera)>,
     pointer_over_ui: Res<PointerOverUi>, // Tracks if pointer is over UI
     mut last_position: Local<Vec2>, // Tracks last known cursor position
 ) {
@@ -127,7 +139,18 @@ pub fn az_el_camera(
     }
 
     // update cameras
-    for (mut az_el, mut transform, projection) in query.iter_mut() {
+    for (mut az_el, mut transform, projection, camera) in query.iter_mut() {
+        // a camera drawn in part of the window (split screen) only follows the mouse over
+        // its own viewport
+        if camera.viewport.is_some() {
+            let over_viewport = camera
+                .logical_viewport_rect()
+                .map_or(false, |rect| rect.contains(current_position));
+            if !over_viewport {
+                continue;
+            }
+        }
+
         let mut any_changes = false; // Tracks if updates are made to the camera
 
         // Handle rotation based on cursor movement
@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::{prelude::*, render::view::screenshot::ScreenshotManager, window::PrimaryWindow};
+use bevy_integrator::SimTime;
+
+use crate::control::CameraKeyBindings;
+
+// Settings for single-frame screenshots.
+// - directory: where the PNG files are written (created on first capture)
+// - label: extra context placed in the file name (e.g. the current weather),
+//   other crates can keep this up to date since the cameras crate doesn't know about them
+#[derive(Resource)]
+pub struct ScreenshotSettings {
+    pub directory: PathBuf,
+    pub label: String,
+}
+
+impl Default for ScreenshotSettings {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("screenshots"),
+            label: String::new(),
+        }
+    }
+}
+
+// Press 'F12' (CameraKeyBindings::screenshot) to save a screenshot of the primary window.
+// The capture itself is handled by Bevy's ScreenshotManager, which encodes and writes
+// the image on the async compute task pool, so the main schedule is never blocked.
+pub fn screenshot_system(
+    input: Res<Input<KeyCode>>,
+    keys: Res<CameraKeyBindings>,
+    main_window: Query<Entity, With<PrimaryWindow>>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    settings: Res<ScreenshotSettings>,
+    sim_time: Option<Res<SimTime>>,
+) {
+    if !input.just_pressed(keys.screenshot) {
+        return;
+    }
+
+    let Ok(window) = main_window.get_single() else { return; };
+
+    if let Err(e) = std::fs::create_dir_all(&settings.directory) {
+        eprintln!("Failed to create screenshot directory: {}", e);
+        return;
+    }
+
+    // wall clock timestamp keeps file names unique, sim time lines the image up with the logs
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or(0);
+    let time = sim_time.map(|time| time.time()).unwrap_or(0.);
+
+    let mut file_name = format!("screenshot_{}", timestamp);
+    if !settings.label.is_empty() {
+        file_name += &format!("_{}", settings.label);
+    }
+    file_name += &format!("_t{:.3}s.png", time);
+
+    let path = settings.directory.join(file_name);
+    if let Err(e) = screenshot_manager.save_screenshot_to_disk(window, &path) {
+        eprintln!("Failed to request screenshot: {}", e);
+    }
+}
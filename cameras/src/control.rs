@@ -1,4 +1,8 @@
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    render::camera::Viewport,
+    window::{PrimaryWindow, WindowResized},
+};
 
 use crate::camera_az_el::AzElCamera;
 
@@ -6,6 +10,39 @@ use crate::camera_az_el::AzElCamera;
 #[derive(Component)]
 pub struct FirstPersonCamera;
 
+// Keys used by the camera systems (see camera_parent_system, camera_toggle_system,
+// split_screen_system and capture::screenshot_system)
+#[derive(Resource, Clone, Debug)]
+pub struct CameraKeyBindings {
+    pub cycle_parent: KeyCode,
+    pub toggle_camera: KeyCode,
+    pub split_screen: KeyCode,
+    pub screenshot: KeyCode,
+}
+
+impl Default for CameraKeyBindings {
+    fn default() -> Self {
+        Self {
+            cycle_parent: KeyCode::C,
+            toggle_camera: KeyCode::V,
+            split_screen: KeyCode::F6,
+            screenshot: KeyCode::F12,
+        }
+    }
+}
+
+impl CameraKeyBindings {
+    // (action, key) for every binding
+    pub fn entries(&self) -> Vec<(&'static str, KeyCode)> {
+        vec![
+            ("Cycle camera parent", self.cycle_parent),
+            ("Toggle camera", self.toggle_camera),
+            ("Split screen", self.split_screen),
+            ("Screenshot", self.screenshot),
+        ]
+    }
+}
+
 #[derive(Resource)]
 pub struct CameraParentList {
     pub list: Vec<Entity>,
@@ -18,6 +55,7 @@ pub fn camera_parent_system(
     mut query: Query<Entity, With<AzElCamera>>,
     focused_windows: Query<(Entity, &Window)>,
     input: Res<Input<KeyCode>>,
+    keys: Res<CameraKeyBindings>,
 ) {
     for (_window, focus) in focused_windows.iter() {
         if !focus.focused {
@@ -28,7 +66,7 @@ pub fn camera_parent_system(
             continue;
         }
 
-        if input.just_pressed(KeyCode::C) {
+        if input.just_pressed(keys.cycle_parent) {
             parent_list.active = (parent_list.active + 1) % parent_list.list.len();
         }
 
@@ -50,11 +88,18 @@ pub fn camera_parent_system(
 
 pub fn camera_toggle_system(
     input: Res<Input<KeyCode>>,
+    keys: Res<CameraKeyBindings>,
+    split_screen: Option<Res<SplitScreen>>,
     mut orbit_query: Query<&mut Camera, (With<AzElCamera>, Without<FirstPersonCamera>)>,
     mut fp_query: Query<&mut Camera, (With<FirstPersonCamera>, Without<AzElCamera>)>,
 ) {
-    // Press 'V' to toggle
-    if input.just_pressed(KeyCode::V) {
+    // both cameras are shown side by side, there is nothing to toggle
+    if split_screen.map_or(false, |split_screen| split_screen.enabled) {
+        return;
+    }
+
+    // Press 'V' (CameraKeyBindings::toggle_camera) to toggle
+    if input.just_pressed(keys.toggle_camera) {
         // Toggle the first-person camera
         if let Ok(mut fp_cam) = fp_query.get_single_mut() {
             fp_cam.is_active = !fp_cam.is_active;
@@ -65,3 +110,72 @@ pub fn camera_toggle_system(
         }
     }
 }
+
+// Orbit camera (left) and first-person camera (right) side by side, each in its own
+// viewport of the primary window. Toggled with 'F6' (CameraKeyBindings::split_screen).
+// The mouse only orbits the orbit camera while the cursor is over its half (see az_el_camera),
+// and the UI is drawn once, over the orbit view.
+// - enabled: show both cameras
+// - first_person: which camera was showing before the split, restored when it ends
+#[derive(Resource, Default)]
+pub struct SplitScreen {
+    pub enabled: bool,
+    first_person: bool,
+}
+
+pub fn split_screen_system(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    keys: Res<CameraKeyBindings>,
+    mut split_screen: ResMut<SplitScreen>,
+    mut resized: EventReader<WindowResized>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut orbit_query: Query<&mut Camera, (With<AzElCamera>, Without<FirstPersonCamera>)>,
+    mut fp_query: Query<(Entity, &mut Camera), (With<FirstPersonCamera>, Without<AzElCamera>)>,
+) {
+    let toggled = input.just_pressed(keys.split_screen);
+    // viewports are in physical pixels, so they have to follow the window size
+    let resized = resized.iter().count() > 0;
+    if !toggled && !(resized && split_screen.enabled) {
+        return;
+    }
+    let (Ok(mut orbit_cam), Ok((fp_entity, mut fp_cam))) =
+        (orbit_query.get_single_mut(), fp_query.get_single_mut())
+    else {
+        return;
+    };
+
+    if toggled {
+        split_screen.enabled = !split_screen.enabled;
+        if split_screen.enabled {
+            split_screen.first_person = fp_cam.is_active;
+        }
+        orbit_cam.is_active = split_screen.enabled || !split_screen.first_person;
+        fp_cam.is_active = split_screen.enabled || split_screen.first_person;
+        // one camera per window region, drawn in a fixed order
+        orbit_cam.order = 0;
+        fp_cam.order = 1;
+        commands.entity(fp_entity).insert(UiCameraConfig {
+            show_ui: !split_screen.enabled,
+        });
+    }
+
+    if !split_screen.enabled {
+        orbit_cam.viewport = None;
+        fp_cam.viewport = None;
+        return;
+    }
+    let Ok(window) = windows.get_single() else { return; };
+    let size = UVec2::new(window.physical_width(), window.physical_height());
+    let half = UVec2::new(size.x / 2, size.y);
+    orbit_cam.viewport = Some(Viewport {
+        physical_position: UVec2::ZERO,
+        physical_size: half.max(UVec2::ONE),
+        ..default()
+    });
+    fp_cam.viewport = Some(Viewport {
+        physical_position: UVec2::new(half.x, 0),
+        physical_size: UVec2::new(size.x - half.x, size.y).max(UVec2::ONE),
+        ..default()
+    });
+}
@@ -1,2 +1,3 @@
 pub mod camera_az_el;
+pub mod capture;
 pub mod control;
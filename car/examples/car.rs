@@ -4,18 +4,30 @@ use bevy_integrator::{SimTime, Solver};
 use rigid_body::plugin::RigidBodyPlugin;
 
 use car::{
-    build::{build_car, car_startup_system},
+    audio::CarAudioPlugin,
+    build::{build_car_with, car_startup_system, VehicleConfig},
     environment::build_environment,
+    ghost::{ghost_playback_system, ghost_record_system, ghost_setup, GhostCar},
     setup::{camera_setup, simulation_setup},
-    line_draw::{line_draw_system, LineDrawState},
+    line_draw::{line_draw_system, LineDrawState, LinePathFile, LineStyle, PathLengthUnit},
+    lap_timer::{lap_timer_system, LapTimer},
+    skid_marks::{skid_mark_system, SkidMarks},
+    terrain_picking::{terrain_pick_system, TerrainPicker},
+    trajectory::{trajectory_export_system, trajectory_record_system, TrajectoryRecorder},
+    menu::{
+        help_setup, help_toggle_system, menu_button_system, menu_label_system, menu_setup,
+        menu_toggle_system, update_help_system, HelpState, MenuState,
+    },
 
     ui::*,
+    minimap::*,
     weather::*,
     logger::*,
     external_control::*,
 };
 
 fn main() {
+    let vehicle_config = VehicleConfig::default();
     App::new()
         .add_plugins((
             RigidBodyPlugin {
@@ -24,32 +36,99 @@ fn main() {
                 simulation_setup: vec![simulation_setup],
                 environment_setup: vec![camera_setup],
                 name: "car_demo".to_string(),
+                headless: false,
         }, HanabiPlugin, 
         ExternalControlPlugin,
+        CarAudioPlugin,
     ))
-        .insert_resource(build_car())
+        .insert_resource(build_car_with(&vehicle_config))
+        .insert_resource(vehicle_config)
         .insert_resource(Weather::Sunny)
+        .insert_resource(RainIntensity::default())
+        .insert_resource(ParticleBudget::default())
+        .insert_resource(RainGround::default())
+        .insert_resource(SurfaceWetness::default())
+        .insert_resource(TimeOfDay::default())
         .insert_resource(ClearColor(Color::rgb(0.1, 0.1, 0.1)))
-        .insert_resource(InputLogger::new("car_inputs.log".to_string()))
+        .insert_resource(
+            InputLogger::new("car_inputs.log".to_string()).with_rotation(10_000_000, 3),
+        )
         .insert_resource(LineDrawState::default())
+        .insert_resource(LinePathFile::default())
+        .insert_resource(LineStyle::default())
+        .insert_resource(TerrainPicker::default())
+        .insert_resource(TrajectoryRecorder::default())
+        .insert_resource(PathLengthUnit::default())
+        .insert_resource(LapTimer::default())
+        .insert_resource(GhostCar::default())
+        .insert_resource(SkidMarks::default())
+        .insert_resource(SpeedUnit::default())
+        .insert_resource(MenuState::default())
+        .insert_resource(HelpState::default())
+        .insert_resource(TelemetryGraph::default())
+        .insert_resource(Minimap::default())
         .add_systems(Startup, (
             car_startup_system,
             build_environment,
             setup_lighting_system,
             setup_rain_system,
             hud_setup,
+            menu_setup,
+            help_setup,
+            telemetry_graph_setup,
+            steering_widget_setup,
+            minimap_setup,
+            ghost_setup,
         ))
         .add_systems(Update, (
             update_speedometer_system,
             update_rpm_system,
+            update_gear_system,
+            update_fuel_system,
+            update_g_force_system,
+            update_tire_temperature_system,
+            update_tire_wear_system,
             update_controls_system,
             cycle_weather_system,
             update_environment_system,
             toggle_rain_system,
+            wet_surface_system,
             update_weather_system,
+            screenshot_label_system,
             update_line_color_system,
+            update_path_length_system,
             line_draw_system,
+            terrain_pick_system,
             input_logger_system,
+            update_suspension_system,
         ))
+        .add_systems(Update, (
+            lap_timer_system,
+            update_lap_timer_system,
+            update_out_of_bounds_system,
+            skid_mark_system,
+            update_sim_speed_system,
+            update_control_source_system,
+            speed_unit_toggle_system,
+            menu_toggle_system,
+            menu_button_system,
+            menu_label_system,
+            help_toggle_system,
+            update_help_system,
+            update_telemetry_graph_system,
+            update_steering_widget_system,
+            rain_intensity_system,
+            time_of_day_system,
+            minimap_terrain_system,
+            minimap_path_system,
+            minimap_car_system,
+        ))
+        .add_systems(Update, (ghost_record_system, ghost_playback_system).chain())
+        .add_systems(Update, (camera_logger_system, camera_replay_system))
+        .add_systems(
+            FixedUpdate,
+            trajectory_record_system.run_if(resource_changed::<SimTime>()),
+        )
+        .add_systems(Last, (input_logger_exit_system, trajectory_export_system))
         .run();
 }
\ No newline at end of file
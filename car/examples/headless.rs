@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+use bevy_integrator::{SimTime, Solver};
+use rigid_body::plugin::RigidBodyPlugin;
+
+use car::{
+    autonomous_control::{AutonomousController, PidTerms, Waypoints},
+    build::{build_car_with, car_startup_system, VehicleConfig},
+    control::ControlSource,
+    environment::environment_terrain,
+    setup::simulation_setup,
+    trajectory::{trajectory_export_system, trajectory_record_system, TrajectoryRecorder},
+    vehicle_state::VehicleState,
+};
+
+// Runs the car without a window or renderer (CI, batches of autonomous runs on a server):
+// the autonomous controller drives circles on the flat ground next to the terrain for a
+// fixed number of physics steps, and the car state and the autonomous controller's PID
+// terms are printed every LOG_INTERVAL steps.
+// The path is written to headless_trajectory.csv / .geojson at the end of the run.
+//
+//     cargo run --release --example headless -- [steps]
+//
+// The terrain is only used for collisions, nothing is rendered (so there are no cones).
+
+const DT: f64 = 0.002;
+const DEFAULT_STEPS: usize = 10_000;
+const LOG_INTERVAL: usize = 250;
+
+fn main() {
+    let steps = std::env::args()
+        .nth(1)
+        .and_then(|steps| steps.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_STEPS);
+    // the app exits on the first step past the end time
+    let end_time = (steps as f64 - 0.5) * DT;
+
+    let vehicle_config = VehicleConfig::default();
+    App::new()
+        .add_plugins(RigidBodyPlugin {
+            time: SimTime::new(DT, 0.0, Some(end_time)),
+            solver: Solver::RK4,
+            simulation_setup: vec![simulation_setup],
+            environment_setup: vec![],
+            name: "car_headless".to_string(),
+            headless: true,
+        })
+        .insert_resource(build_car_with(&vehicle_config))
+        .insert_resource(vehicle_config)
+        .insert_resource(environment_terrain())
+        .insert_resource(ControlSource::Autonomous)
+        .insert_resource(TrajectoryRecorder::new("headless_trajectory".to_string()))
+        .insert_resource(Waypoints {
+            points: circle(Vec2::new(-25., 0.), 20., 24),
+            loop_path: true,
+            ..default()
+        })
+        .add_systems(Startup, car_startup_system)
+        .add_systems(
+            FixedUpdate,
+            (state_log_system, trajectory_record_system).run_if(resource_changed::<SimTime>()),
+        )
+        .add_systems(Last, trajectory_export_system)
+        .run();
+}
+
+// Clockwise circle of waypoints, starting on the +x side of the center
+fn circle(center: Vec2, radius: f32, points: usize) -> Vec<Vec3> {
+    (0..points)
+        .map(|i| {
+            let angle = -(i as f32) * TAU / points as f32;
+            (center + radius * Vec2::new(angle.cos(), angle.sin())).extend(0.)
+        })
+        .collect()
+}
+
+fn state_log_system(
+    sim_time: Res<SimTime>,
+    state: Res<VehicleState>,
+    waypoints: Res<Waypoints>,
+    source: Res<ControlSource>,
+    controller: Res<AutonomousController>,
+) {
+    if sim_time.index % LOG_INTERVAL != 0 {
+        return;
+    }
+    println!(
+        "step {:6}  t {:7.3} s  position ({:7.2}, {:7.2}, {:5.2})  speed {:5.2} m/s  heading {:6.1} deg  waypoint {}",
+        sim_time.index,
+        sim_time.time(),
+        state.position.x,
+        state.position.y,
+        state.position.z,
+        state.speed,
+        state.heading.to_degrees(),
+        waypoints.target,
+    );
+    if *source == ControlSource::Autonomous {
+        println!(
+            "    velocity {}    heading {}",
+            format_terms(&controller.velocity.terms()),
+            format_terms(&controller.heading.terms()),
+        );
+    }
+}
+
+fn format_terms(terms: &PidTerms) -> String {
+    format!(
+        "P {:+.3} I {:+.3} D {:+.3} FF {:+.3} -> {:+.3}",
+        terms.proportional, terms.integral, terms.derivative, terms.feed_forward, terms.output
+    )
+}
@@ -0,0 +1,203 @@
+use bevy::{
+    audio::{AddAudioSource, Decodable, Source},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::Duration,
+};
+use rigid_body::joint::Joint;
+
+use crate::{
+    control::CarControl,
+    physics::{average_wheel_rpm, WheelJoint},
+    tire::TireContact,
+};
+
+// Engine and tire sounds
+// - enabled: all car sounds on / off
+// - engine_volume: engine volume at full throttle (half of it when coasting)
+// - idle_pitch: engine pitch with the wheels stopped
+// - rpm_per_pitch: wheel RPM that raises the pitch by 1 (an octave above idle at idle_pitch 1)
+// - max_pitch: upper limit of the engine pitch
+// - screech_volume: tire screech volume at slip_full
+// - slip_start / slip_full: tire slip where the screech starts / is loudest
+#[derive(Resource)]
+pub struct AudioSettings {
+    pub enabled: bool,
+    pub engine_volume: f32,
+    pub idle_pitch: f32,
+    pub rpm_per_pitch: f32,
+    pub max_pitch: f32,
+    pub screech_volume: f32,
+    pub slip_start: f64,
+    pub slip_full: f64,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            engine_volume: 0.4,
+            idle_pitch: 1.,
+            rpm_per_pitch: 600.,
+            max_pitch: 5.,
+            screech_volume: 0.3,
+            slip_start: 1.,
+            slip_full: 3.,
+        }
+    }
+}
+
+// Sounds generated on the fly, so the car needs no audio files
+#[derive(TypePath, TypeUuid, Clone, Copy, Debug, PartialEq, Eq)]
+#[uuid = "5d0b3c0e-8f0e-4c57-9a59-2f6b1f0c8d41"]
+pub enum SynthSound {
+    Engine,    // low hum with a few harmonics; the pitch follows the playback speed
+    TireNoise, // low-passed noise for the screech
+}
+
+const SAMPLE_RATE: u32 = 44_100;
+const ENGINE_FREQUENCY: f32 = 45.; // Hz at playback speed 1
+
+pub struct SynthDecoder {
+    sound: SynthSound,
+    phase: f32, // engine cycle position, 0..1
+    noise_state: u32, // xorshift state
+    filtered: f32, // low-passed noise
+}
+
+impl Iterator for SynthDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = match self.sound {
+            SynthSound::Engine => {
+                self.phase = (self.phase + ENGINE_FREQUENCY / SAMPLE_RATE as f32) % 1.;
+                let angle = std::f32::consts::TAU * self.phase;
+                0.6 * angle.sin() + 0.3 * (2. * angle).sin() + 0.1 * (3. * angle).sin()
+            }
+            SynthSound::TireNoise => {
+                self.noise_state ^= self.noise_state << 13;
+                self.noise_state ^= self.noise_state >> 17;
+                self.noise_state ^= self.noise_state << 5;
+                let white = self.noise_state as f32 / u32::MAX as f32 * 2. - 1.;
+                self.filtered += 0.2 * (white - self.filtered);
+                self.filtered
+            }
+        };
+        Some(sample)
+    }
+}
+
+impl Source for SynthDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    // endless, the sink decides when to stop
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl Decodable for SynthSound {
+    type DecoderItem = f32;
+    type Decoder = SynthDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        SynthDecoder {
+            sound: *self,
+            phase: 0.,
+            noise_state: 0x9e37_79b9,
+            filtered: 0.,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct EngineSound;
+
+#[derive(Component)]
+pub struct TireScreechSound;
+
+pub fn audio_setup(mut commands: Commands, mut sounds: ResMut<Assets<SynthSound>>) {
+    commands.spawn((
+        AudioSourceBundle {
+            source: sounds.add(SynthSound::Engine),
+            settings: PlaybackSettings::LOOP,
+        },
+        EngineSound,
+    ));
+    commands.spawn((
+        AudioSourceBundle {
+            source: sounds.add(SynthSound::TireNoise),
+            settings: PlaybackSettings::LOOP,
+        },
+        TireScreechSound,
+    ));
+}
+
+// Engine pitch from the driven wheel RPM (the same value as the HUD readout),
+// louder with throttle. The AudioSink only exists once playback has started.
+pub fn engine_audio_system(
+    settings: Res<AudioSettings>,
+    control: Res<CarControl>,
+    wheels: Query<(&Joint, &WheelJoint)>,
+    sinks: Query<&AudioSink, With<EngineSound>>,
+) {
+    let wheels: Vec<_> = wheels.iter().collect();
+    let rpm = average_wheel_rpm(&wheels).abs() as f32;
+    let pitch = (settings.idle_pitch + rpm / settings.rpm_per_pitch).min(settings.max_pitch);
+    let volume = settings.engine_volume * (0.5 + 0.5 * control.throttle.clamp(0., 1.));
+    for sink in sinks.iter() {
+        set_playing(sink, settings.enabled);
+        sink.set_speed(pitch);
+        sink.set_volume(volume);
+    }
+}
+
+// Tire screech, silent until the most slipping tire passes slip_start
+pub fn tire_screech_system(
+    settings: Res<AudioSettings>,
+    tires: Query<&TireContact>,
+    sinks: Query<&AudioSink, With<TireScreechSound>>,
+) {
+    let slip = tires
+        .iter()
+        .filter(|tire| tire.position.is_some())
+        .map(|tire| tire.slip)
+        .fold(0., f64::max);
+    let strength = ((slip - settings.slip_start) / (settings.slip_full - settings.slip_start))
+        .clamp(0., 1.) as f32;
+    for sink in sinks.iter() {
+        set_playing(sink, settings.enabled && strength > 0.);
+        sink.set_volume(settings.screech_volume * strength);
+    }
+}
+
+fn set_playing(sink: &AudioSink, playing: bool) {
+    if playing && sink.is_paused() {
+        sink.play();
+    } else if !playing && !sink.is_paused() {
+        sink.pause();
+    }
+}
+
+// Adds the synthesized engine and tire sounds
+pub struct CarAudioPlugin;
+
+impl Plugin for CarAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<SynthSound>()
+            .init_resource::<AudioSettings>()
+            .add_systems(Startup, audio_setup)
+            .add_systems(Update, (engine_audio_system, tire_screech_system));
+    }
+}
@@ -0,0 +1,1114 @@
+use bevy::prelude::*;
+use bevy_integrator::SimTime;
+use grid_terrain::GridTerrain;
+
+use crate::{
+    control::{CarControl, ControlSource},
+    interpolate::CatmullRom,
+    key_bindings::KeyBindings,
+    line_draw::LineDrawState,
+    obstacle::Obstacle,
+    vehicle_state::VehicleState,
+};
+
+// Steering law used by the autonomous controller
+// - PurePursuit: steer toward a look-ahead point on the waypoint path
+// - AvoidObstacles: pure pursuit plus a repulsive steering bias away from obstacles ahead
+// - Stanley: steer to line up with the nearest path segment and close the cross-track
+//   error (StanleyController)
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlMode {
+    #[default]
+    PurePursuit,
+    AvoidObstacles,
+    Stanley,
+}
+
+// Path for the autonomous controller, in absolute coordinates (z is ignored)
+// - points: waypoints in driving order
+// - target: index of the waypoint currently being approached
+// - cruise_speed: speed held between waypoints (m/s)
+// - loop_path: start over at the first waypoint after the last one
+#[derive(Resource, Clone, Debug)]
+pub struct Waypoints {
+    pub points: Vec<Vec3>,
+    pub target: usize,
+    pub cruise_speed: f32,
+    pub loop_path: bool,
+}
+
+impl Default for Waypoints {
+    fn default() -> Self {
+        Self {
+            points: Vec::new(),
+            target: 0,
+            cruise_speed: 8.0,
+            loop_path: false,
+        }
+    }
+}
+
+// The three PID controllers share their gains:
+// - kp, ki, kd: proportional, integral and derivative gains
+// - max_integral: bound on the accumulated error
+// - back_calculation: anti-windup rate (1/s), how fast the integral is unwound while the
+//   output is saturated (0 leaves only the max_integral clamp)
+// - derivative_cutoff: cutoff frequency (Hz) of the first-order low-pass filter on the
+//   derivative, which otherwise amplifies noise in the measurement (infinite or 0 = unfiltered)
+
+// Slows the car down on the approach to the last waypoint.
+// Output is a target speed (m/s) proportional to the remaining distance.
+#[derive(Clone, Debug)]
+pub struct PositionController {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub max_integral: f32,
+    pub back_calculation: f32,
+    pub derivative_cutoff: f32,
+    state: PidState,
+}
+
+// Tracks a target speed. Output is a throttle (positive) / brake (negative) command in [-1, 1].
+// - feed_forward: throttle per m/s of target speed added to the PID output, roughly what
+//   holding that speed takes, so the integral doesn't have to build it up (0 = pure PID)
+#[derive(Clone, Debug)]
+pub struct VelocityController {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub max_integral: f32,
+    pub back_calculation: f32,
+    pub derivative_cutoff: f32,
+    pub feed_forward: f32,
+    state: PidState,
+}
+
+// Tracks a target heading. Output is a steering command in [-1, 1] (positive steers left).
+// - yaw_rate_feed_forward: steering per rad/s of target yaw rate added to the PID output,
+//   so the car steers into a curve before a heading error builds up (0 = off). It has to
+//   roughly match the car's steering (about 0.9 for the default car): with it on, the
+//   pure-pursuit target no longer leads into curves, so too little of it runs wide.
+#[derive(Clone, Debug)]
+pub struct HeadingController {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+    pub max_integral: f32,
+    pub back_calculation: f32,
+    pub derivative_cutoff: f32,
+    pub yaw_rate_feed_forward: f32,
+    state: PidState,
+}
+
+// Breakdown of a PID controller's last output, for logging and tuning
+// - error: the error it was computed from
+// - proportional, integral, derivative, feed_forward: contribution of each term
+// - accumulated_error: the integral state (integral = ki * accumulated_error)
+// - output: the saturated sum of the terms
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PidTerms {
+    pub error: f32,
+    pub proportional: f32,
+    pub integral: f32,
+    pub derivative: f32,
+    pub feed_forward: f32,
+    pub accumulated_error: f32,
+    pub output: f32,
+}
+
+// Memory of a PID controller
+// - integral: accumulated error
+// - last_error: error of the last step, for the derivative (None after a reset)
+// - derivative: filtered derivative of the error
+// - terms: breakdown of the last output
+// - transfer: output to continue from on the next step (see `transfer`)
+#[derive(Clone, Debug, Default)]
+struct PidState {
+    integral: f32,
+    last_error: Option<f32>,
+    derivative: f32,
+    terms: PidTerms,
+    transfer: Option<f32>,
+}
+
+impl PidState {
+    // Shared PID update, `feed_forward` is added to the PID terms.
+    // The output is saturated to (min_output, max_output) and the integral is
+    // corrected by back calculation: while the output is saturated, the integral is pulled
+    // back at `back_calculation` (1/s) times the amount cut off, so it doesn't wind up
+    // (and overshoot) while the output can't follow, e.g. with the car against a wall.
+    // `max_integral` still bounds it.
+    fn step(
+        &mut self,
+        (kp, ki, kd, max_integral, back_calculation, derivative_cutoff): (f32, f32, f32, f32, f32, f32),
+        (min_output, max_output): (f32, f32),
+        feed_forward: f32,
+        error: f32,
+        dt: f32,
+    ) -> f32 {
+        // bumpless transfer: pick the integral that gives the transferred output
+        if let Some(output) = self.transfer.take() {
+            if ki != 0. {
+                self.integral = ((output - feed_forward - kp * error) / ki)
+                    .clamp(-max_integral, max_integral);
+            }
+            self.last_error = None;
+        }
+
+        self.integral = (self.integral + error * dt).clamp(-max_integral, max_integral);
+        self.derivative = match self.last_error {
+            Some(last_error) if dt > 0. => {
+                let raw = (error - last_error) / dt;
+                self.derivative + low_pass_factor(derivative_cutoff, dt) * (raw - self.derivative)
+            }
+            _ => 0.,
+        };
+        self.last_error = Some(error);
+
+        let (proportional, integral, derivative) =
+            (kp * error, ki * self.integral, kd * self.derivative);
+        let output = feed_forward + proportional + integral + derivative;
+        let saturated = output.clamp(min_output, max_output);
+        if ki != 0. {
+            let correction = back_calculation * (saturated - output) * dt / ki;
+            self.integral = (self.integral + correction).clamp(-max_integral, max_integral);
+        }
+        self.terms = PidTerms {
+            error,
+            proportional,
+            integral,
+            derivative,
+            feed_forward,
+            accumulated_error: self.integral,
+            output: saturated,
+        };
+        saturated
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    // Bumpless transfer: the next step starts from `output` instead of jumping to whatever
+    // the stale integral and derivative give. The integral is set so the next output
+    // matches (when there is an integral gain) and the derivative starts over.
+    fn transfer(&mut self, output: f32) {
+        self.transfer = Some(output);
+    }
+}
+
+impl Default for PositionController {
+    fn default() -> Self {
+        Self {
+            kp: 0.5,
+            ki: 0.0,
+            kd: 0.0,
+            max_integral: 10.0,
+            back_calculation: 1.0,
+            derivative_cutoff: f32::INFINITY,
+            state: PidState::default(),
+        }
+    }
+}
+
+impl PositionController {
+    // Breakdown of the last output
+    pub fn terms(&self) -> PidTerms {
+        self.state.terms
+    }
+
+    fn gains(&self) -> (f32, f32, f32, f32, f32, f32) {
+        let (kp, ki, kd) = (self.kp, self.ki, self.kd);
+        (kp, ki, kd, self.max_integral, self.back_calculation, self.derivative_cutoff)
+    }
+
+    // distance: remaining distance to the goal (m), returns a target speed (m/s)
+    pub fn compute(&mut self, distance: f32, dt: f32) -> f32 {
+        let gains = self.gains();
+        self.state.step(gains, (0., f32::INFINITY), 0., distance, dt)
+    }
+
+    pub fn reset(&mut self) {
+        self.state.reset();
+    }
+
+    // Continue smoothly from the last output (see PidState::transfer)
+    pub fn transfer(&mut self) {
+        self.state.transfer(self.state.terms.output);
+    }
+}
+
+impl Default for VelocityController {
+    fn default() -> Self {
+        Self {
+            kp: 0.3,
+            ki: 0.05,
+            kd: 0.0,
+            max_integral: 10.0,
+            back_calculation: 1.0,
+            derivative_cutoff: f32::INFINITY,
+            feed_forward: 0.04,
+            state: PidState::default(),
+        }
+    }
+}
+
+impl VelocityController {
+    // Breakdown of the last output
+    pub fn terms(&self) -> PidTerms {
+        self.state.terms
+    }
+
+    fn gains(&self) -> (f32, f32, f32, f32, f32, f32) {
+        let (kp, ki, kd) = (self.kp, self.ki, self.kd);
+        (kp, ki, kd, self.max_integral, self.back_calculation, self.derivative_cutoff)
+    }
+
+    pub fn compute(&mut self, target_speed: f32, speed: f32, dt: f32) -> f32 {
+        let gains = self.gains();
+        let error = target_speed - speed;
+        let feed_forward = self.feed_forward * target_speed;
+        self.state.step(gains, (-1., 1.), feed_forward, error, dt)
+    }
+
+    pub fn reset(&mut self) {
+        self.state.reset();
+    }
+
+    // Continue smoothly from `drive` (throttle - brake), see PidState::transfer
+    pub fn transfer(&mut self, drive: f32) {
+        self.state.transfer(drive);
+    }
+}
+
+impl Default for HeadingController {
+    fn default() -> Self {
+        Self {
+            kp: 1.5,
+            ki: 0.0,
+            kd: 0.1,
+            max_integral: 1.0,
+            back_calculation: 1.0,
+            derivative_cutoff: f32::INFINITY,
+            yaw_rate_feed_forward: 0.0,
+            state: PidState::default(),
+        }
+    }
+}
+
+impl HeadingController {
+    // Breakdown of the last output
+    pub fn terms(&self) -> PidTerms {
+        self.state.terms
+    }
+
+    fn gains(&self) -> (f32, f32, f32, f32, f32, f32) {
+        let (kp, ki, kd) = (self.kp, self.ki, self.kd);
+        (kp, ki, kd, self.max_integral, self.back_calculation, self.derivative_cutoff)
+    }
+
+    // Headings in radians; the error is wrapped so the car always turns the short way.
+    // target_yaw_rate: how fast the target heading turns (rad/s, positive to the left)
+    pub fn compute(&mut self, target_heading: f32, target_yaw_rate: f32, heading: f32, dt: f32) -> f32 {
+        let gains = self.gains();
+        let error = wrap_angle(target_heading - heading);
+        let feed_forward = self.yaw_rate_feed_forward * target_yaw_rate;
+        self.state.step(gains, (-1., 1.), feed_forward, error, dt)
+    }
+
+    pub fn reset(&mut self) {
+        self.state.reset();
+    }
+
+    // Continue smoothly from `steering`, see PidState::transfer
+    pub fn transfer(&mut self, steering: f32) {
+        self.state.transfer(steering);
+    }
+}
+
+// Share of the change applied per step by a first-order low-pass filter with a cutoff
+// of `cutoff` Hz (1 passes the input through)
+fn low_pass_factor(cutoff: f32, dt: f32) -> f32 {
+    if !cutoff.is_finite() || cutoff <= 0. {
+        return 1.;
+    }
+    let time_constant = 1. / (std::f32::consts::TAU * cutoff);
+    dt / (dt + time_constant)
+}
+
+// Wraps an angle into [-pi, pi]
+pub fn wrap_angle(angle: f32) -> f32 {
+    let wrapped = (angle + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU);
+    wrapped - std::f32::consts::PI
+}
+
+// Repulsive steering bias for AvoidObstacles
+// - range: obstacles further away than this are ignored (m)
+// - cone_half_angle: obstacles outside this angle from the heading are ignored (rad)
+// - gain: steering offset for an obstacle touching the car, falls off linearly to zero at range
+#[derive(Clone, Debug)]
+pub struct ObstacleAvoidance {
+    pub range: f32,
+    pub cone_half_angle: f32,
+    pub gain: f32,
+}
+
+impl Default for ObstacleAvoidance {
+    fn default() -> Self {
+        Self {
+            range: 15.0,
+            cone_half_angle: 30_f32.to_radians(),
+            gain: 0.8,
+        }
+    }
+}
+
+impl ObstacleAvoidance {
+    // Steering offset (positive steers left) pushing the car away from the obstacles
+    // in the cone ahead. Obstacles on the left push right and vice versa; an obstacle
+    // dead ahead pushes left. The result is not clamped, the caller adds it to the
+    // pure-pursuit command and clamps the sum.
+    pub fn steering_bias(&self, position: Vec2, heading: f32, obstacles: &[Vec2]) -> f32 {
+        let forward = Vec2::new(heading.cos(), heading.sin());
+        let mut bias = 0.;
+        for obstacle in obstacles {
+            let offset = *obstacle - position;
+            let distance = offset.length();
+            if distance >= self.range || distance < 1e-3 {
+                continue;
+            }
+            let lateral = forward.perp_dot(offset); // positive on the left
+            let angle = lateral.atan2(forward.dot(offset));
+            if angle.abs() > self.cone_half_angle {
+                continue;
+            }
+            let side = if lateral > 0. { -1. } else { 1. };
+            bias += side * self.gain * (1. - distance / self.range);
+        }
+        bias
+    }
+}
+
+// Stanley steering law: wheel angle = heading error + atan(gain * cross_track_error /
+// (softening + speed)), with the errors measured to the nearest point of the path.
+// - gain: how hard the cross-track error is closed (1/s)
+// - softening: speed added to the car's speed (m/s), keeps the cross-track term
+//   finite at standstill
+// - max_steer_angle: wheel angle of a full steering command (rad), about what the default
+//   car's curvature steering turns the wheels to
+#[derive(Clone, Debug)]
+pub struct StanleyController {
+    pub gain: f32,
+    pub softening: f32,
+    pub max_steer_angle: f32,
+}
+
+impl Default for StanleyController {
+    fn default() -> Self {
+        Self {
+            gain: 2.5,
+            softening: 1.0,
+            max_steer_angle: 0.45,
+        }
+    }
+}
+
+impl StanleyController {
+    // heading_error: path heading minus car heading (rad)
+    // cross_track_error: distance to the path (m), positive when the path is on the left
+    // Returns the wheel angle (rad, positive steers left)
+    pub fn steer_angle(&self, heading_error: f32, cross_track_error: f32, speed: f32) -> f32 {
+        let speed = speed.max(0.) + self.softening.max(f32::EPSILON);
+        wrap_angle(heading_error) + (self.gain * cross_track_error / speed).atan()
+    }
+
+    // Steering command in [-1, 1] toward the path segment through `path_point` heading
+    // `path_heading`
+    pub fn compute(&self, path_point: Vec2, path_heading: f32, position: Vec2, heading: f32, speed: f32) -> f32 {
+        let path_direction = Vec2::new(path_heading.cos(), path_heading.sin());
+        let cross_track_error = path_direction.perp_dot(path_point - position);
+        let angle = self.steer_angle(path_heading - heading, cross_track_error, speed);
+        (angle / self.max_steer_angle).clamp(-1., 1.)
+    }
+}
+
+// State of the autonomous controller
+// - look_ahead_min: pure-pursuit look-ahead distance at standstill (m)
+// - look_ahead_gain: extra look-ahead per m/s of speed (s)
+// - arrival_radius: a waypoint counts as reached inside this distance (m)
+// - grade_feed_forward: throttle added per unit of sin(grade) along the heading, so the
+//   car doesn't wait for the velocity error to build up on hills (brakes downhill, 0 = off)
+#[derive(Resource, Clone, Debug)]
+pub struct AutonomousController {
+    pub position: PositionController,
+    pub velocity: VelocityController,
+    pub heading: HeadingController,
+    pub stanley: StanleyController,
+    pub avoidance: ObstacleAvoidance,
+    pub look_ahead_min: f32,
+    pub look_ahead_gain: f32,
+    pub arrival_radius: f32,
+    pub grade_feed_forward: f32,
+}
+
+impl Default for AutonomousController {
+    fn default() -> Self {
+        Self {
+            position: PositionController::default(),
+            velocity: VelocityController::default(),
+            heading: HeadingController::default(),
+            stanley: StanleyController::default(),
+            avoidance: ObstacleAvoidance::default(),
+            look_ahead_min: 4.0,
+            look_ahead_gain: 0.5,
+            arrival_radius: 2.0,
+            grade_feed_forward: 1.0,
+        }
+    }
+}
+
+impl AutonomousController {
+    pub fn reset(&mut self) {
+        self.position.reset();
+        self.velocity.reset();
+        self.heading.reset();
+    }
+
+    // Bumpless transfer: the controllers continue from the car's current commands
+    pub fn transfer(&mut self, control: &CarControl) {
+        self.position.transfer();
+        self.velocity.transfer(control.throttle - control.brake);
+        self.heading.transfer(control.steering);
+    }
+}
+
+// Grade (rad) along `heading` for the terrain slope angles along x and y (see
+// GridTerrain::slope_at), positive uphill
+pub fn grade_along((slope_x, slope_y): (f64, f64), heading: f32) -> f32 {
+    let heading = heading as f64;
+    (heading.cos() * slope_x.tan() + heading.sin() * slope_y.tan()).atan() as f32
+}
+
+// Signed curvature (1/m, positive turning left) of the waypoint path at waypoint `index`:
+// the turn between the segments into and out of it over their average length.
+// 0 at the ends of an open path.
+fn path_curvature(waypoints: &Waypoints, index: usize) -> f32 {
+    let points = &waypoints.points;
+    let count = points.len();
+    if count < 3 || index >= count {
+        return 0.;
+    }
+    let (previous, next) = match (index, waypoints.loop_path) {
+        (0, true) => (count - 1, 1),
+        (i, true) if i == count - 1 => (i - 1, 0),
+        (0, false) => return 0.,
+        (i, false) if i == count - 1 => return 0.,
+        (i, _) => (i - 1, i + 1),
+    };
+    let incoming = (points[index] - points[previous]).truncate();
+    let outgoing = (points[next] - points[index]).truncate();
+    let length = 0.5 * (incoming.length() + outgoing.length());
+    if length < 1e-6 {
+        return 0.;
+    }
+    incoming.perp_dot(outgoing).atan2(incoming.dot(outgoing)) / length
+}
+
+// Point of the waypoint path closest to `position` and the heading of the segment it is
+// on (a lone waypoint is headed toward)
+fn nearest_path_point(waypoints: &Waypoints, position: Vec2) -> Option<(Vec2, f32)> {
+    let points: Vec<Vec2> = waypoints.points.iter().map(|point| point.truncate()).collect();
+    if let [point] = points.as_slice() {
+        let offset = *point - position;
+        return Some((*point, offset.y.atan2(offset.x)));
+    }
+    let closing = match (waypoints.loop_path, points.first(), points.last()) {
+        (true, Some(first), Some(last)) => Some([*last, *first]),
+        _ => None,
+    };
+    points
+        .windows(2)
+        .map(|pair| [pair[0], pair[1]])
+        .chain(closing)
+        .filter(|[start, end]| start.distance_squared(*end) > f32::EPSILON)
+        .map(|[start, end]| {
+            let segment = end - start;
+            let along = ((position - start).dot(segment) / segment.length_squared()).clamp(0., 1.);
+            (start + segment * along, segment.y.atan2(segment.x))
+        })
+        .min_by(|(a, _), (b, _)| {
+            a.distance_squared(position)
+                .total_cmp(&b.distance_squared(position))
+        })
+}
+
+// First point on the path from the target waypoint on that is at least `look_ahead`
+// away from the car, or the last waypoint if the whole remaining path is closer
+fn look_ahead_point(waypoints: &Waypoints, position: Vec2, look_ahead: f32) -> Option<Vec2> {
+    let remaining = waypoints.points.get(waypoints.target..)?;
+    remaining
+        .iter()
+        .map(|point| point.truncate())
+        .find(|point| point.distance(position) >= look_ahead)
+        .or_else(|| remaining.last().map(|point| point.truncate()))
+}
+
+// Drives the car along the waypoints: pure pursuit picks a target heading toward the
+// look-ahead point, the heading controller turns it into steering (or the Stanley law
+// steers toward the nearest point of the path in ControlMode::Stanley), and the position and
+// velocity controllers set throttle and brake, with a feed-forward for the grade of the
+// terrain under the car. Runs once per physics step.
+pub fn autonomous_control_system(
+    obstacles: Query<&Transform, With<Obstacle>>,
+    sim_time: Res<SimTime>,
+    vehicle_state: Res<VehicleState>,
+    terrain: Option<Res<GridTerrain>>,
+    mode: Res<ControlMode>,
+    mut waypoints: ResMut<Waypoints>,
+    mut controller: ResMut<AutonomousController>,
+    mut control: ResMut<CarControl>,
+) {
+    let dt = sim_time.dt as f32;
+    let position = vehicle_state.position.truncate();
+    let heading = vehicle_state.heading;
+
+    // advance past the waypoints already reached
+    while let Some(point) = waypoints.points.get(waypoints.target) {
+        if point.truncate().distance(position) > controller.arrival_radius {
+            break;
+        }
+        waypoints.target += 1;
+        if waypoints.target == waypoints.points.len() && waypoints.loop_path {
+            waypoints.target = 0;
+            break;
+        }
+    }
+
+    let look_ahead = controller.look_ahead_min + controller.look_ahead_gain * vehicle_state.speed;
+    let Some(target_point) = look_ahead_point(&waypoints, position, look_ahead) else {
+        // no path left: stop
+        control.throttle = 0.;
+        control.brake = 1.;
+        control.steering = 0.;
+        return;
+    };
+
+    let nearest = match *mode {
+        ControlMode::Stanley => nearest_path_point(&waypoints, position),
+        _ => None,
+    };
+    let mut steering = if let Some((path_point, path_heading)) = nearest {
+        let stanley = &controller.stanley;
+        stanley.compute(path_point, path_heading, position, heading, vehicle_state.speed)
+    } else {
+        let offset = target_point - position;
+        let mut target_heading = offset.y.atan2(offset.x);
+        // yaw rate the path asks for at the current speed
+        let curvature = path_curvature(&waypoints, waypoints.target);
+        let target_yaw_rate = curvature * vehicle_state.speed;
+        if controller.heading.yaw_rate_feed_forward != 0. {
+            // on a curve the look-ahead point is already turned into it by half the arc up
+            // to it; the feed-forward steers for the curve instead, so only the offset from
+            // the path is left for the heading controller
+            target_heading -= (0.5 * curvature * offset.length()).clamp(-1., 1.).asin();
+        }
+        controller.heading.compute(target_heading, target_yaw_rate, heading, dt)
+    };
+    if *mode == ControlMode::AvoidObstacles {
+        let obstacle_positions: Vec<Vec2> = obstacles
+            .iter()
+            .map(|transform| transform.translation.truncate())
+            .collect();
+        steering += controller
+            .avoidance
+            .steering_bias(position, heading, &obstacle_positions);
+    }
+
+    // slow down toward the end of an open path
+    let mut target_speed = waypoints.cruise_speed;
+    if !waypoints.loop_path {
+        if let Some(goal) = waypoints.points.last() {
+            let distance = goal.truncate().distance(position);
+            target_speed = target_speed.min(controller.position.compute(distance, dt));
+        }
+    }
+    let mut drive = controller.velocity.compute(target_speed, vehicle_state.speed, dt);
+    // feed forward against gravity on grades
+    let slope = terrain.and_then(|terrain| terrain.slope_at(position.x as f64, position.y as f64));
+    if let Some(slope) = slope {
+        drive = (drive + controller.grade_feed_forward * grade_along(slope, heading).sin()).clamp(-1., 1.);
+    }
+
+    control.steering = steering.clamp(-1., 1.);
+    control.throttle = drive.max(0.);
+    control.brake = (-drive).max(0.);
+    control.reverse = false;
+}
+
+// Starts the controllers fresh whenever the autonomous controller takes over the car,
+// continuing from the car's current throttle/brake and steering so the handover doesn't
+// jerk it. Switching ControlMode while driving autonomously is bumpless too: the
+// controllers drop their derivative memory and pick up from the current commands.
+pub fn autonomy_start_system(
+    source: Res<ControlSource>,
+    mode: Res<ControlMode>,
+    control: Res<CarControl>,
+    mut controller: ResMut<AutonomousController>,
+    mut waypoints: ResMut<Waypoints>,
+) {
+    if *source != ControlSource::Autonomous {
+        return;
+    }
+    if source.is_changed() {
+        controller.reset();
+        controller.transfer(&control);
+        waypoints.target = 0;
+        if waypoints.points.is_empty() {
+            println!("Autonomous control with no path: press U to follow the drawn line");
+        }
+    } else if mode.is_changed() {
+        controller.transfer(&control);
+    }
+}
+
+// Waypoints per line-draw segment after smoothing
+const DRAWN_PATH_SAMPLES_PER_SEGMENT: usize = 8;
+
+// Press 'U' (KeyBindings::follow_drawn_path) to use the points clicked with the
+// line-draw tool as the autonomous controller's path.
+// A closed drawn path is always followed as a loop.
+pub fn follow_drawn_path_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    line_draw_state: Option<Res<LineDrawState>>,
+    mut waypoints: ResMut<Waypoints>,
+) {
+    if !keyboard_input.just_pressed(keys.follow_drawn_path) {
+        return;
+    }
+    let Some(line_draw_state) = line_draw_state else {
+        return;
+    };
+    if line_draw_state.closed {
+        waypoints.loop_path = true;
+    }
+    // smooth the clicked points so the car doesn't corner at each one
+    let spline = if waypoints.loop_path {
+        CatmullRom::closed(line_draw_state.loop_points().to_vec())
+    } else {
+        CatmullRom::new(line_draw_state.points.clone())
+    };
+    waypoints.points = spline.resample(DRAWN_PATH_SAMPLES_PER_SEGMENT);
+    waypoints.target = 0;
+    println!(
+        "Following drawn path: {} points smoothed to {} waypoints",
+        line_draw_state.loop_points().len(),
+        waypoints.points.len()
+    );
+}
+
+// Adds the autonomous controller and its path. The controller only drives the car
+// while ControlSource::Autonomous is selected.
+pub struct AutonomousPlugin;
+
+impl Plugin for AutonomousPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ControlMode>()
+            .init_resource::<Waypoints>()
+            .init_resource::<AutonomousController>()
+            .add_systems(Update, (autonomy_start_system, follow_drawn_path_system))
+            // once per physics step, like the other controllers
+            .add_systems(
+                FixedUpdate,
+                autonomous_control_system
+                    .run_if(resource_changed::<SimTime>()
+                        .and_then(resource_equals(ControlSource::Autonomous))),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The app with AutonomousPlugin as setup wires it, around a car moved by a kinematic
+    // bicycle model instead of the physics
+    fn autonomy_app(dt: f64) -> App {
+        let mut app = App::new();
+        app.add_plugins(AutonomousPlugin)
+            .init_resource::<ControlSource>()
+            .init_resource::<CarControl>()
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<VehicleState>()
+            .insert_resource(SimTime::new(dt, 0., None));
+        app
+    }
+
+    // Moves the car one step: throttle / brake accelerate it (m/s^2) and full steering
+    // turns the front wheels 0.5 rad on a 2.5 m wheelbase
+    fn drive(vehicle_state: &mut VehicleState, control: &CarControl, dt: f32) {
+        let acceleration = 4. * control.throttle - 8. * control.brake;
+        vehicle_state.speed = (vehicle_state.speed + acceleration * dt).max(0.);
+        vehicle_state.heading += vehicle_state.speed * (0.5 * control.steering).tan() / 2.5 * dt;
+        let direction = Vec3::new(vehicle_state.heading.cos(), vehicle_state.heading.sin(), 0.);
+        vehicle_state.velocity = vehicle_state.speed * direction;
+        vehicle_state.position += vehicle_state.velocity * dt;
+    }
+
+    #[test]
+    fn enabling_autonomy_drives_the_car_to_the_goal() {
+        let dt = 0.01;
+        let mut app = autonomy_app(dt);
+        let goal = Vec3::new(30., 20., 0.);
+        app.world.resource_mut::<Waypoints>().points = vec![Vec3::new(15., 5., 0.), goal];
+
+        // with the user in control the controller leaves the car alone
+        for _ in 0..100 {
+            app.update();
+            app.world.resource_mut::<SimTime>().index += 1;
+            app.world.run_schedule(FixedUpdate);
+        }
+        assert_eq!(app.world.resource::<CarControl>().throttle, 0.);
+
+        *app.world.resource_mut::<ControlSource>() = ControlSource::Autonomous;
+        for _ in 0..(30. / dt) as usize {
+            app.update();
+            app.world.resource_mut::<SimTime>().index += 1;
+            app.world.run_schedule(FixedUpdate);
+
+            app.world.resource_scope(|world, control: Mut<CarControl>| {
+                drive(&mut world.resource_mut::<VehicleState>(), &control, dt as f32);
+            });
+        }
+
+        // the car drove the 36 m to the goal and stopped about the arrival radius short of it
+        let vehicle_state = app.world.resource::<VehicleState>();
+        let arrival_radius = app.world.resource::<AutonomousController>().arrival_radius;
+        let distance = vehicle_state.position.distance(goal);
+        assert!(distance < arrival_radius + 0.5, "stopped {} m from the goal", distance);
+        assert!(vehicle_state.speed < 0.1, "still moving at {} m/s", vehicle_state.speed);
+    }
+
+    #[test]
+    fn obstacle_avoidance_steers_away_from_each_side() {
+        let avoidance = ObstacleAvoidance::default();
+        let position = Vec2::new(1., 2.);
+        // facing +y: left is -x, right is +x
+        let heading = std::f32::consts::FRAC_PI_2;
+        // 6 m ahead and 1 m to the side, well inside the 30 degree cone
+        let left = position + Vec2::new(-1., 6.);
+        let right = position + Vec2::new(1., 6.);
+        let expected = avoidance.gain * (1. - left.distance(position) / avoidance.range);
+
+        let from_left = avoidance.steering_bias(position, heading, &[left]);
+        let from_right = avoidance.steering_bias(position, heading, &[right]);
+        assert!((from_left + expected).abs() < 1e-6, "{}", from_left);
+        assert!((from_right - expected).abs() < 1e-6, "{}", from_right);
+
+        // closer obstacles push harder, and both sides at once cancel out
+        let closer_left = position + Vec2::new(-0.5, 3.);
+        let closer = avoidance.steering_bias(position, heading, &[closer_left]);
+        assert!(closer < from_left);
+        assert!(avoidance.steering_bias(position, heading, &[left, right]).abs() < 1e-6);
+
+        // out of range, outside the cone or behind the car: ignored
+        for ignored in [Vec2::new(-1., 20.), Vec2::new(-6., 2.), Vec2::new(0., -5.)] {
+            assert_eq!(avoidance.steering_bias(position, heading, &[position + ignored]), 0.);
+        }
+    }
+
+    #[test]
+    fn pid_output_saturates_at_its_bounds() {
+        let mut velocity = VelocityController::default();
+        assert_eq!(velocity.compute(100., 0., 0.01), 1.);
+        assert_eq!(velocity.compute(0., 100., 0.01), -1.);
+        let terms = velocity.terms();
+        // the terms add up to more than the saturated output
+        let sum = terms.feed_forward + terms.proportional + terms.integral + terms.derivative;
+        assert!(sum < -1. && terms.output == -1.);
+
+        // the position controller only clamps below (no negative target speed)
+        let mut position = PositionController::default();
+        assert_eq!(position.compute(-5., 0.01), 0.);
+        assert!(position.compute(1000., 0.01) > 100.);
+    }
+
+    // A velocity controller with a loose max_integral, held still against a 10 m/s target
+    // for 20 s
+    fn wound_up_controller(back_calculation: f32) -> VelocityController {
+        let mut velocity = VelocityController {
+            max_integral: 100.,
+            back_calculation,
+            ..default()
+        };
+        for _ in 0..2000 {
+            velocity.compute(10., 0., 0.01);
+        }
+        velocity
+    }
+
+    // How long the throttle stays wide open once the car of `wound_up_controller` is
+    // let go and reaches the target speed
+    fn saturated_time_after_windup(back_calculation: f32) -> f32 {
+        let dt = 0.01;
+        let mut velocity = wound_up_controller(back_calculation);
+        let mut time = 0.;
+        while velocity.compute(10., 10., dt) >= 1. && time < 60. {
+            time += dt;
+        }
+        time
+    }
+
+    #[test]
+    fn back_calculation_stops_integral_windup() {
+        let unwound = saturated_time_after_windup(1.);
+        let wound_up = saturated_time_after_windup(0.);
+        // without back calculation the integral sits at max_integral and holds the
+        // throttle wide open long after the car reaches the target speed
+        assert!(wound_up > 1., "{} s", wound_up);
+        assert!(unwound < 0.1, "{} s", unwound);
+
+        // the integral settles well inside the clamp, where back calculation unwinds it as
+        // fast as the error winds it up: (sum - 1) * back_calculation = ki * error
+        let velocity = wound_up_controller(1.);
+        let terms = velocity.terms();
+        assert!(terms.accumulated_error.abs() < 0.5 * velocity.max_integral);
+        let sum = terms.feed_forward + terms.proportional + terms.integral;
+        let excess = velocity.ki * terms.error / velocity.back_calculation;
+        assert!((sum - 1. - excess).abs() < 0.01, "{}", sum);
+    }
+
+    #[test]
+    fn transfer_continues_from_the_current_command() {
+        let mut velocity = VelocityController::default();
+        for _ in 0..100 {
+            velocity.compute(10., 2., 0.01);
+        }
+        // the user was holding the target speed on a little throttle when the
+        // controller took over
+        velocity.transfer(0.3);
+        let output = velocity.compute(5., 5., 0.01);
+        assert!((output - 0.3).abs() < 0.01, "{}", output);
+    }
+
+    // Speed after 30 s of a car whose drive accelerates it 5 m/s^2 per unit and whose
+    // drag slows it 0.2 1/s, so holding a speed takes 0.04 drive per m/s (the default
+    // feed-forward)
+    fn cruise_speed(velocity: &mut VelocityController, target_speed: f32) -> f32 {
+        let dt = 0.01;
+        let mut speed = 0.;
+        for _ in 0..3000 {
+            let drive = velocity.compute(target_speed, speed, dt);
+            speed += (5. * drive - 0.2 * speed) * dt;
+        }
+        speed
+    }
+
+    #[test]
+    fn velocity_feed_forward_holds_the_target_without_an_integral() {
+        // at the target speed the output is the feed-forward alone
+        let mut velocity = VelocityController::default();
+        assert!((velocity.compute(10., 10., 0.01) - 0.4).abs() < 1e-6);
+        assert!((velocity.terms().feed_forward - 0.4).abs() < 1e-6);
+
+        // a proportional-only controller settles short of the target without it
+        let proportional = VelocityController {
+            ki: 0.,
+            ..default()
+        };
+        let with = cruise_speed(&mut proportional.clone(), 10.);
+        let without = cruise_speed(
+            &mut VelocityController {
+                feed_forward: 0.,
+                ..proportional
+            },
+            10.,
+        );
+        assert!((with - 10.).abs() < 0.05, "{} m/s", with);
+        assert!(without < 9., "{} m/s", without);
+    }
+
+    #[test]
+    fn derivative_responds_to_a_step_and_filtering_smooths_it() {
+        let dt = 0.01;
+        let heading = |derivative_cutoff| HeadingController {
+            kp: 0.,
+            kd: 0.01,
+            derivative_cutoff,
+            ..default()
+        };
+
+        // unfiltered: a 0.1 rad step in the error gives kd * 0.1 / dt on that step only
+        let mut unfiltered = heading(f32::INFINITY);
+        unfiltered.compute(0., 0., 0., dt);
+        assert_eq!(unfiltered.terms().derivative, 0.);
+        unfiltered.compute(0.1, 0., 0., dt);
+        assert!((unfiltered.terms().derivative - 0.1).abs() < 1e-5);
+        unfiltered.compute(0.1, 0., 0., dt);
+        assert!(unfiltered.terms().derivative.abs() < 1e-6);
+
+        // filtered at 5 Hz: the spike is cut to a fraction of it and then decays
+        let share = dt / (dt + 1. / (std::f32::consts::TAU * 5.));
+        let mut filtered = heading(5.);
+        filtered.compute(0., 0., 0., dt);
+        filtered.compute(0.1, 0., 0., dt);
+        let first = filtered.terms().derivative;
+        assert!((first - share * 0.1).abs() < 1e-5, "{}", first);
+        filtered.compute(0.1, 0., 0., dt);
+        let second = filtered.terms().derivative;
+        assert!((second - (1. - share) * first).abs() < 1e-5, "{}", second);
+
+        // the first step after a reset has no derivative kick
+        filtered.reset();
+        filtered.compute(0.5, 0., 0., dt);
+        assert_eq!(filtered.terms().derivative, 0.);
+    }
+
+    // Heading error after 10 s of following a target heading turning at 0.5 rad/s, on a
+    // car whose yaw rate is 1.1 rad/s per unit of steering
+    fn ramp_tracking_error(heading: &mut HeadingController) -> f32 {
+        let (dt, rate) = (0.01, 0.5);
+        let mut car_heading = 0.;
+        let mut target = 0.;
+        for _ in 0..1000 {
+            target += rate * dt;
+            let steering = heading.compute(target, rate, car_heading, dt);
+            car_heading += 1.1 * steering * dt;
+        }
+        wrap_angle(target - car_heading)
+    }
+
+    #[test]
+    fn yaw_rate_feed_forward_tracks_a_turning_target() {
+        let without = ramp_tracking_error(&mut HeadingController::default());
+        let with = ramp_tracking_error(&mut HeadingController {
+            yaw_rate_feed_forward: 1. / 1.1,
+            ..default()
+        });
+        // proportional only, the car lags by rate / (1.1 * kp)
+        assert!((without - 0.5 / (1.1 * 1.5)).abs() < 0.01, "{} rad", without);
+        assert!(with.abs() < 0.005, "{} rad", with);
+
+        // the feed-forward is its own term, and off when the target doesn't turn
+        let mut heading = HeadingController {
+            yaw_rate_feed_forward: 0.9,
+            ..default()
+        };
+        heading.compute(0., 0.5, 0., 0.01);
+        assert!((heading.terms().feed_forward - 0.45).abs() < 1e-6);
+        heading.compute(0., 0., 0., 0.01);
+        assert_eq!(heading.terms().feed_forward, 0.);
+    }
+
+    #[test]
+    fn stanley_steers_toward_the_path() {
+        let stanley = StanleyController::default();
+        assert_eq!(stanley.steer_angle(0., 0., 5.), 0.);
+        assert_eq!(stanley.steer_angle(0., 0., 0.), 0.);
+
+        // the path heading to the left or lying to the left steers left, and vice versa
+        for speed in [0., 5., 20.] {
+            assert!(stanley.steer_angle(0.2, 0., speed) > 0.);
+            assert!(stanley.steer_angle(-0.2, 0., speed) < 0.);
+            assert!(stanley.steer_angle(0., 1., speed) > 0.);
+            assert!(stanley.steer_angle(0., -1., speed) < 0.);
+        }
+        assert!((stanley.steer_angle(0.2, 0., 5.) - 0.2).abs() < 1e-6);
+        let cross_track = (2.5_f32 * 1. / (5. + 1.)).atan();
+        assert!((stanley.steer_angle(0., 1., 5.) - cross_track).abs() < 1e-6);
+
+        // compute: a path along +x, 1 m to the car's left
+        let command = stanley.compute(Vec2::new(3., 1.), 0., Vec2::ZERO, 0., 5.);
+        assert!((command - cross_track / stanley.max_steer_angle).abs() < 1e-6);
+        let command = stanley.compute(Vec2::new(3., -1.), 0., Vec2::ZERO, 0., 5.);
+        assert!((command + cross_track / stanley.max_steer_angle).abs() < 1e-6);
+    }
+
+
+    // Largest distance from a 20 m circle of waypoints over a lap, after the first few
+    // seconds, for the car starting on the circle at cruise speed
+    fn circle_cross_track_error(yaw_rate_feed_forward: f32) -> f32 {
+        let (dt, radius) = (0.01, 20.);
+        let mut app = autonomy_app(dt);
+        let points = (0..40)
+            .map(|i| Vec2::from_angle(i as f32 * std::f32::consts::TAU / 40.).extend(0.) * radius)
+            .collect();
+        *app.world.resource_mut::<Waypoints>() = Waypoints {
+            points,
+            loop_path: true,
+            ..default()
+        };
+        app.world.resource_mut::<AutonomousController>().heading.yaw_rate_feed_forward =
+            yaw_rate_feed_forward;
+        let cruise_speed = app.world.resource::<Waypoints>().cruise_speed;
+        *app.world.resource_mut::<VehicleState>() = VehicleState {
+            position: Vec3::new(radius, 0., 0.),
+            speed: cruise_speed,
+            heading: std::f32::consts::FRAC_PI_2,
+            velocity: Vec3::new(0., cruise_speed, 0.),
+            ..default()
+        };
+        *app.world.resource_mut::<ControlSource>() = ControlSource::Autonomous;
+
+        let mut max_error: f32 = 0.;
+        for step in 0..(20. / dt) as usize {
+            app.update();
+            app.world.resource_mut::<SimTime>().index += 1;
+            app.world.run_schedule(FixedUpdate);
+            app.world.resource_scope(|world, control: Mut<CarControl>| {
+                drive(&mut world.resource_mut::<VehicleState>(), &control, dt as f32);
+            });
+            if step as f64 * dt > 4. {
+                let distance = app.world.resource::<VehicleState>().position.truncate().length();
+                max_error = max_error.max((distance - radius).abs());
+            }
+        }
+        max_error
+    }
+
+    #[test]
+    fn yaw_rate_feed_forward_follows_a_circle_closer() {
+        // steering per rad/s of yaw rate of the kinematic car at cruise speed
+        let cruise_speed = Waypoints::default().cruise_speed;
+        let feed_forward = 2.5 / (0.5 * cruise_speed);
+        let without = circle_cross_track_error(0.);
+        let with = circle_cross_track_error(feed_forward);
+        // pure pursuit alone cuts inside the circle, the feed-forward keeps to it
+        assert!(without > 0.5, "{} m", without);
+        assert!(with < 0.1, "{} m", with);
+    }
+
+
+    #[test]
+    fn filtered_derivative_is_less_noisy() {
+        let dt = 0.01;
+        // variance of the derivative term over 5 s of a slow sine heading error with
+        // ±0.01 rad of alternating measurement noise on top
+        let derivative_variance = |derivative_cutoff| {
+            let mut heading = HeadingController {
+                kp: 0.,
+                kd: 0.01,
+                derivative_cutoff,
+                ..default()
+            };
+            let derivatives: Vec<f32> = (0..500)
+                .map(|step| {
+                    let t = step as f32 * dt;
+                    let noise = if step % 2 == 0 { 0.01 } else { -0.01 };
+                    let error = 0.2 * (std::f32::consts::PI * t).sin() + noise;
+                    heading.compute(error, 0., 0., dt);
+                    heading.terms().derivative
+                })
+                .skip(50)
+                .collect();
+            let mean = derivatives.iter().sum::<f32>() / derivatives.len() as f32;
+            derivatives.iter().map(|d| (d - mean).powi(2)).sum::<f32>() / derivatives.len() as f32
+        };
+
+        let unfiltered = derivative_variance(f32::INFINITY);
+        let filtered = derivative_variance(5.);
+        assert!(filtered < 0.1 * unfiltered, "{} >= {}", filtered, unfiltered);
+    }
+}
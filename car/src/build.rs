@@ -8,11 +8,12 @@ use rigid_body::{
 };
 
 use crate::{
+    headlights::spawn_headlights,
     physics::{
         BrakeWheel, DriveType, DrivenWheelLookup, SteeringCurvature, SteeringType,
-        SuspensionComponent,
+        SuspensionComponent, WheelJoint,
     },
-    tire::PointTire,
+    tire::{PointTire, TireContact, TireTemperature},
 };
 
 #[derive(Resource)]
@@ -22,15 +23,64 @@ pub struct CarDefinition {
     wheel: Wheel,
     drives: Vec<DriveType>,
     brake: Brake,
+    trailer: Option<Trailer>,
+}
+
+impl CarDefinition {
+    // Tows `trailer` behind the car
+    pub fn with_trailer(mut self, trailer: Trailer) -> Self {
+        self.trailer = Some(trailer);
+        self
+    }
 }
 
 const CHASSIS_MASS: f64 = 1000.;
 const SUSPENSION_MASS: f64 = 20.;
 const GRAVITY: f64 = 9.81;
 
+// Core vehicle properties used by build_car_with. The defaults are the standard car.
+// - mass: chassis mass (kg)
+// - wheelbase: distance between the front and rear axles (m)
+// - track_width: distance between the left and right wheels (m)
+// - cg_height: height of the center of mass above the center of the chassis box (m)
+// - spring_rate: suspension stiffness at each corner (N/m)
+// - damper_rate: suspension damping at each corner (N s/m)
+// The spring and damper rates can also be changed while driving (vehicle_config_system);
+// the rest only takes effect when the car is built.
+#[derive(Resource, Clone, Debug)]
+pub struct VehicleConfig {
+    pub mass: f64,
+    pub wheelbase: f64,
+    pub track_width: f64,
+    pub cg_height: f64,
+    pub spring_rate: f64,
+    pub damper_rate: f64,
+}
+
+impl Default for VehicleConfig {
+    fn default() -> Self {
+        let mass = CHASSIS_MASS;
+        // 0.1 m static deflection, 25% of critical damping on a quarter of the car
+        let spring_rate = mass * (GRAVITY / 4.) / 0.1;
+        Self {
+            mass,
+            wheelbase: 2.5,
+            track_width: 1.5,
+            cg_height: 0.,
+            spring_rate,
+            damper_rate: 0.25 * 2. * (spring_rate * (mass / 4.)).sqrt(),
+        }
+    }
+}
+
+// The standard car
 pub fn build_car() -> CarDefinition {
+    build_car_with(&VehicleConfig::default())
+}
+
+pub fn build_car_with(config: &VehicleConfig) -> CarDefinition {
     // Chassis
-    let mass = 1000.;
+    let mass = config.mass;
     let dimensions = [3.0_f64, 1.2, 0.4]; // shape of rectangular chassis
     let moi = [
         dimensions[1].powi(2) + dimensions[2].powi(2),
@@ -41,7 +91,7 @@ pub fn build_car() -> CarDefinition {
 
     let chassis = Chassis {
         mass,
-        cg_position: [0., 0., 0.],
+        cg_position: [0., 0., config.cg_height],
         moi,
         dimensions,
         position: [0., 0., 0.],
@@ -53,17 +103,19 @@ pub fn build_car() -> CarDefinition {
     // Suspension
     let suspension_mass = 20.;
     let suspension_size = 0.025_f64;
-    let suspension_stiffness = mass * (GRAVITY / 4.) / 0.1;
-    let suspension_damping = 0.25 * 2. * (suspension_stiffness * (1000. / 4.) as f64).sqrt();
+    let suspension_stiffness = config.spring_rate;
+    let suspension_damping = config.damper_rate;
     let suspension_preload = mass * (GRAVITY / 4.);
     let suspension_moi = (2. / 3.) * suspension_mass * suspension_size.powi(2);
 
     let suspension_names = ["fl", "fr", "rl", "rr"].map(|name| name.to_string());
+    let x = 0.5 * config.wheelbase;
+    let y = 0.5 * config.track_width;
     let suspension_locations = [
-        [1.25, 0.75, -0.2],
-        [1.25, -0.75, -0.2],
-        [-1.25, 0.75, -0.2],
-        [-1.25, -0.75, -0.2],
+        [x, y, -0.2],
+        [x, -y, -0.2],
+        [-x, y, -0.2],
+        [-x, -y, -0.2],
     ];
 
     let suspension: Vec<Suspension> = suspension_locations
@@ -119,6 +171,7 @@ pub fn build_car() -> CarDefinition {
     let brake = Brake {
         front_torque: 800.,
         rear_torque: 400.,
+        handbrake_torque: 3000., // strong enough to lock the rear wheels
     };
 
     CarDefinition {
@@ -127,6 +180,108 @@ pub fn build_car() -> CarDefinition {
         wheel,
         drives,
         brake,
+        trailer: None,
+    }
+}
+
+// A single-axle trailer hitched to the car body. The hitch lets the trailer yaw and
+// pitch relative to the car (it rolls with the car); the trailer wheels are free
+// rolling and unbraked.
+// - mass: trailer body mass (kg)
+// - dimensions: trailer body box (x length, y width, z height)
+// - hitch: hitch point on the car body (chassis coordinates)
+// - hitch_to_axle: distance from the hitch back to the trailer axle (m)
+// - track: distance between the trailer wheels (m)
+// - tongue_load: fraction of the trailer weight carried by the hitch
+#[derive(Clone)]
+pub struct Trailer {
+    pub mass: f64,
+    pub dimensions: [f64; 3],
+    pub hitch: [f64; 3],
+    pub hitch_to_axle: f64,
+    pub track: f64,
+    pub tongue_load: f64,
+}
+
+// A 300 kg box trailer hitched behind the default chassis
+pub fn build_trailer() -> Trailer {
+    Trailer {
+        mass: 300.,
+        dimensions: [2.0, 1.4, 0.5],
+        hitch: [-1.7, 0., -0.1],
+        hitch_to_axle: 2.2,
+        track: 1.5,
+        tongue_load: 0.1,
+    }
+}
+
+impl Trailer {
+    // Spawns the hitch joints, the trailer body and its two wheels under `chassis_id`
+    // (the car body). Returns the trailer body joint.
+    pub fn build(&self, commands: &mut Commands, chassis_id: Entity, wheel: &Wheel) -> Entity {
+        // hitch yaw, at the hitch point on the car
+        let yaw = Joint::rz(
+            "trailer_hitch_yaw".to_string(),
+            Inertia::zero(),
+            Xform::new(
+                Vector::new(self.hitch[0], self.hitch[1], self.hitch[2]),
+                Matrix::identity(),
+            ),
+        );
+        let mut yaw_e = commands.spawn((yaw,));
+        yaw_e.set_parent(chassis_id);
+        let yaw_id = yaw_e.id();
+
+        // hitch pitch, this is the trailer body
+        // (the center of mass sits ahead of the axle so the hitch carries the tongue load)
+        let body_center = [-self.hitch_to_axle, 0., 0.];
+        let cg = Vector::new(-(1. - self.tongue_load) * self.hitch_to_axle, 0., 0.);
+        let [length, width, height] = self.dimensions;
+        let moi = [
+            width.powi(2) + height.powi(2),
+            height.powi(2) + length.powi(2),
+            length.powi(2) + width.powi(2),
+        ]
+        .map(|x| self.mass * (1. / 12.) * x);
+        let inertia = Inertia::new(
+            self.mass,
+            cg,
+            Matrix::from_diagonal(&Vector::new(moi[0], moi[1], moi[2])),
+        );
+        let body = Joint::ry("trailer_hitch_pitch".to_string(), inertia, Xform::identity());
+        let mut body_e = commands.spawn((
+            body,
+            MeshDef {
+                mesh_type: MeshTypeDef::Box {
+                    dimensions: [length as f32, width as f32, height as f32],
+                },
+                transform: TransformDef::from_position(body_center),
+                color: Color::rgb(0.3, 0.3, 0.35),
+            },
+        ));
+        body_e.set_parent(yaw_id);
+        let body_id = body_e.id();
+
+        // axle suspension, sprung like the car's with the trailer's axle load
+        let corner_load = (1. - self.tongue_load) * self.mass * GRAVITY / 2.;
+        let stiffness = corner_load / 0.1;
+        let mass = SUSPENSION_MASS;
+        let size = 0.025_f64;
+        for (name, side) in [("trailer_l", 1.), ("trailer_r", -1.)] {
+            let suspension = Suspension {
+                name: name.to_string(),
+                mass,
+                steering: SteeringType::None,
+                stiffness,
+                damping: 0.25 * 2. * (stiffness * corner_load / GRAVITY).sqrt(),
+                preload: corner_load,
+                moi: (2. / 3.) * mass * size.powi(2),
+                location: [-self.hitch_to_axle, side * 0.5 * self.track, -0.1],
+            };
+            let id_susp = suspension.build(commands, body_id, &suspension.location);
+            wheel.build(commands, &suspension.name, id_susp, DriveType::None, None, 0.);
+        }
+        body_id
     }
 }
 
@@ -164,6 +319,23 @@ pub fn build_wheel() -> Wheel {
     }
 }
 
+// Marks the car's own corner suspensions (not the trailer's), the ones VehicleConfig tunes
+#[derive(Component)]
+pub struct CarSuspension;
+
+// Applies changed VehicleConfig spring and damper rates to the car's suspensions
+pub fn vehicle_config_system(
+    config: Res<VehicleConfig>,
+    mut suspensions: Query<&mut SuspensionComponent, With<CarSuspension>>,
+) {
+    if !config.is_changed() || config.is_added() {
+        return;
+    }
+    for mut suspension in suspensions.iter_mut() {
+        suspension.set_rates(config.spring_rate, config.damper_rate);
+    }
+}
+
 pub fn car_startup_system(mut commands: Commands, car: ResMut<CarDefinition>) {
     let base = Joint::base(Motion::new([0., 0., 9.81], [0., 0., 0.]));
     let base_id = commands.spawn((base, Base)).id();
@@ -177,7 +349,7 @@ pub fn car_startup_system(mut commands: Commands, car: ResMut<CarDefinition>) {
     /*
     List of cameras that follow objects based on chassis coordiantes.
     */
-    let camera_parent_list = vec![
+    let mut camera_parent_list = vec![
         chassis_ids[5], // follow x, y and z and yaw of chassis
         // chassis_ids[0], // only follow x of chassis (why would you do that?)
         chassis_ids[1], // follow x and y of chassis
@@ -187,11 +359,6 @@ pub fn car_startup_system(mut commands: Commands, car: ResMut<CarDefinition>) {
                         // chassis_ids[4],
     ];
 
-    commands.insert_resource(CameraParentList {
-        list: camera_parent_list,
-        active: 0, // start with following x, y, z and yaw of chassis
-    });
-
     commands
     .spawn(Camera3dBundle {
         transform: Transform::from_translation(Vec3::new(1.0, 0.0, 0.5))
@@ -210,17 +377,36 @@ pub fn car_startup_system(mut commands: Commands, car: ResMut<CarDefinition>) {
     // Add a simple marker so we can query for this camera
     .insert(FirstPersonCamera);
 
+    spawn_headlights(&mut commands, chassis_id, car.chassis.dimensions);
+
+    // the trailer body is the last camera parent, so the cameras can ride along with it
+    if let Some(trailer) = &car.trailer {
+        let trailer_id = trailer.build(&mut commands, chassis_id, &car.wheel);
+        camera_parent_list.push(trailer_id);
+    }
+    commands.insert_resource(CameraParentList {
+        list: camera_parent_list,
+        active: 0, // start with following x, y, z and yaw of chassis
+    });
+
     for (ind, susp) in car.suspension.iter().enumerate() {
         let braked_wheel = if ind < 2 {
             Some(BrakeWheel {
                 max_torque: car.brake.front_torque,
+                handbrake_torque: 0.,
+                rolling_radius: car.wheel.rolling_radius,
+                handbrake_hold: None,
             })
         } else {
             Some(BrakeWheel {
                 max_torque: car.brake.rear_torque,
+                handbrake_torque: car.brake.handbrake_torque,
+                rolling_radius: car.wheel.rolling_radius,
+                handbrake_hold: None,
             })
         };
         let id_susp = susp.build(&mut commands, chassis_id, &susp.location);
+        commands.entity(id_susp).insert(CarSuspension);
         let _wheel_id = car.wheel.build(
             &mut commands,
             &susp.name,
@@ -446,6 +632,9 @@ impl Wheel {
                 transform: TransformDef::Identity,
                 color: Color::rgb(0.5, 0.5, 1.0),
             },
+            WheelJoint {
+                driven: !matches!(driven_wheel, DriveType::None),
+            },
         ));
 
         // add driven and braked components
@@ -468,22 +657,26 @@ impl Wheel {
         let wheel_id = wheel_e.id();
 
         // add tire contact model
-        commands.spawn(PointTire::new(
-            wheel_id,
-            parent_id,
-            self.stiffness,
-            self.damping,
-            self.coefficient_of_friction,
-            self.normalized_slip_stiffness,
-            // self.rolling_resistance,
-            self.rolling_radius,
-            self.low_speed,
-            self.radius,
-            self.width,
-            self.filter_time,
-            5,
-            51,
-            0.01,
+        commands.spawn((
+            PointTire::new(
+                wheel_id,
+                parent_id,
+                self.stiffness,
+                self.damping,
+                self.coefficient_of_friction,
+                self.normalized_slip_stiffness,
+                // self.rolling_resistance,
+                self.rolling_radius,
+                self.low_speed,
+                self.radius,
+                self.width,
+                self.filter_time,
+                5,
+                51,
+                0.01,
+            ),
+            TireTemperature::default(),
+            TireContact::default(),
         ));
         wheel_id
     }
@@ -492,4 +685,5 @@ impl Wheel {
 pub struct Brake {
     front_torque: f64,
     rear_torque: f64,
+    handbrake_torque: f64,
 }
\ No newline at end of file
@@ -0,0 +1,141 @@
+use bevy::{prelude::*, render::camera::Projection};
+use cameras::camera_az_el::AzElCamera;
+use grid_terrain::GridTerrain;
+use rigid_body::sva::Vector;
+
+use crate::{key_bindings::KeyBindings, vehicle_state::VehicleState};
+
+// Extra room around the terrain when fitting it in view
+const FIT_MARGIN: f32 = 1.1;
+
+// Orbit camera shortcuts, the camera keeps its azimuth and elevation:
+// - 'F3' (KeyBindings::focus_car): focus on the car where it is now
+// - 'F4' (KeyBindings::fit_terrain): focus on the middle of the terrain and back off
+//   until the whole grid fits in view
+// The orbit camera rides along with its parent (see CameraParentList), so the focus is
+// set in the parent's coordinates. The camera jumps to the new view.
+pub fn camera_focus_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    vehicle_state: Res<VehicleState>,
+    terrain: Option<Res<GridTerrain>>,
+    mut cameras: Query<(&mut AzElCamera, &mut Transform, &Projection, Option<&Parent>)>,
+    parents: Query<&GlobalTransform>,
+) {
+    let focus_car = keyboard_input.just_pressed(keys.focus_car);
+    let fit_terrain = keyboard_input.just_pressed(keys.fit_terrain);
+    if !focus_car && !fit_terrain {
+        return;
+    }
+
+    for (mut camera, mut transform, projection, parent) in cameras.iter_mut() {
+        // world point -> camera parent coordinates
+        let to_parent = parent
+            .and_then(|parent| parents.get(parent.get()).ok())
+            .map_or(Mat4::IDENTITY, |parent| parent.compute_matrix().inverse());
+
+        if focus_car {
+            camera.focus = to_parent.transform_point3(vehicle_state.position);
+        } else if let Some(terrain) = terrain.as_ref() {
+            let (min, max) = terrain.extents();
+            let min = Vec3::new(min.x as f32, min.y as f32, min.z as f32);
+            let max = Vec3::new(max.x as f32, max.y as f32, max.z as f32);
+            let half_diagonal = 0.5 * (max - min).length();
+            let fov = match projection {
+                Projection::Perspective(perspective) => {
+                    perspective.fov.min(perspective.fov * perspective.aspect_ratio)
+                }
+                Projection::Orthographic(_) => std::f32::consts::FRAC_PI_4,
+            };
+            camera.focus = to_parent.transform_point3(0.5 * (min + max));
+            camera.radius = FIT_MARGIN * half_diagonal / (0.5 * fov).sin();
+        }
+        *transform = camera.transform();
+    }
+}
+
+// Keeps the orbit camera from dipping below the terrain: the camera is pulled in along
+// the line to its focus until it is above the surface. Only the view is clamped, the
+// zoom (AzElCamera::radius) is kept, so the camera moves back out once the terrain is
+// out of the way. Toggled with 'F2' (KeyBindings::camera_above_terrain).
+// - enabled: clamp the camera (off gives free movement, e.g. to look under the car)
+// - clearance: distance kept between the camera and the surface (m)
+// - ray_step: step along the line from the focus while searching for the surface (m)
+#[derive(Resource)]
+pub struct CameraTerrainConstraint {
+    pub enabled: bool,
+    pub clearance: f32,
+    pub ray_step: f64,
+}
+
+impl Default for CameraTerrainConstraint {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            clearance: 0.2,
+            ray_step: 0.25,
+        }
+    }
+}
+
+// Runs after the camera has been moved for the frame (az_el_camera, camera_focus_system).
+// Without a terrain there is nothing to clamp against; outside the grid (or with an
+// empty grid) the terrain is the flat ground.
+pub fn camera_terrain_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    mut constraint: ResMut<CameraTerrainConstraint>,
+    terrain: Option<Res<GridTerrain>>,
+    mut cameras: Query<(&AzElCamera, &mut Transform, Option<&Parent>)>,
+    parents: Query<&GlobalTransform>,
+) {
+    let toggled = keyboard_input.just_pressed(keys.camera_above_terrain);
+    if toggled {
+        constraint.enabled = !constraint.enabled;
+        println!(
+            "Camera above terrain: {}",
+            if constraint.enabled { "on" } else { "off" }
+        );
+    }
+
+    for (camera, mut transform, parent) in cameras.iter_mut() {
+        let view = camera.transform();
+        let (Some(terrain), true) = (terrain.as_ref(), constraint.enabled) else {
+            // back to the unclamped view when switched off
+            if toggled {
+                transform.translation = view.translation;
+            }
+            continue;
+        };
+
+        // camera parent coordinates -> world
+        let to_world = parent
+            .and_then(|parent| parents.get(parent.get()).ok())
+            .map_or(Mat4::IDENTITY, |parent| parent.compute_matrix());
+        let focus = to_world.transform_point3(camera.focus);
+        let eye = to_world.transform_point3(view.translation);
+        let world_radius = focus.distance(eye);
+
+        let distance = terrain
+            .raycast(
+                to_vector(focus),
+                to_vector(eye - focus),
+                world_radius as f64,
+                constraint.ray_step,
+            )
+            // a focus inside the terrain (Some(0)) can't be helped, leave the camera
+            .filter(|distance| *distance > 0.);
+        transform.translation = match distance {
+            Some(distance) => {
+                let scale = camera.radius / world_radius.max(f32::EPSILON);
+                let radius = ((distance as f32 - constraint.clearance) * scale).max(0.05);
+                camera.focus + view.rotation * Vec3::new(0., 0., radius)
+            }
+            None => view.translation,
+        };
+    }
+}
+
+fn to_vector(v: Vec3) -> Vector {
+    Vector::new(v.x as f64, v.y as f64, v.z as f64)
+}
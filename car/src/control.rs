@@ -1,72 +1,365 @@
-use bevy::prelude::*;
+use bevy::{
+    input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest},
+    prelude::*,
+};
+use bevy_integrator::SimControl;
+use std::time::Duration;
+
+use crate::{
+    key_bindings::KeyBindings,
+    tire::{TerrainImpact, TerrainImpactThresholds, TireContact},
+    vehicle_state::VehicleState,
+};
 
 #[derive(Resource, Default)]
 pub struct CarControl {
     pub throttle: f32,
     pub steering: f32,
     pub brake: f32,
+    pub handbrake: f32, // locks the rear wheels, independent of the brake
+    pub reverse: bool,  // throttle drives the car backwards
+}
+
+// Which input drives the car. Only the active source writes CarControl, so keyboard,
+// WebSocket clients and the autonomous controller never fight over it:
+// - User: keyboard and gamepad (user_control_system)
+// - External: WebSocket clients (update_from_external_controls)
+// - Autonomous: the waypoint follower (autonomous_control_system)
+// Each writer is registered with run_if(resource_equals(<its source>)).
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlSource {
+    #[default]
+    User,
+    External,
+    Autonomous,
+}
+
+impl ControlSource {
+    pub fn next(self) -> Self {
+        match self {
+            ControlSource::User => ControlSource::External,
+            ControlSource::External => ControlSource::Autonomous,
+            ControlSource::Autonomous => ControlSource::User,
+        }
+    }
+}
+
+// Steering feel
+// - max_steering: largest steering command, as a fraction of the full lock of the steered
+//   wheels (Steering::max_angle / SteeringCurvature::max_curvature); also limits
+//   commands from external and autonomous control
+// - turn_in_rate: keyboard steering change per second while A / D is held
+// - return_rate: steering change per second back toward center once A / D is released
+// - speed_assist: reduce the steering gain with speed (toggled with 'H')
+// - assist_start_speed / assist_full_speed: speeds (m/s) where the gain starts to drop /
+//   reaches assist_min_gain
+// - assist_min_gain: steering gain at and above assist_full_speed
+#[derive(Resource, Clone, Debug)]
+pub struct SteeringConfig {
+    pub max_steering: f32,
+    pub turn_in_rate: f32,
+    pub return_rate: f32,
+    pub speed_assist: bool,
+    pub assist_start_speed: f32,
+    pub assist_full_speed: f32,
+    pub assist_min_gain: f32,
+}
+
+impl Default for SteeringConfig {
+    fn default() -> Self {
+        Self {
+            max_steering: 1.0,
+            turn_in_rate: 1.8,
+            return_rate: 5.4,
+            speed_assist: false,
+            assist_start_speed: 5.0,
+            assist_full_speed: 30.0,
+            assist_min_gain: 0.35,
+        }
+    }
+}
+
+impl SteeringConfig {
+    // Steering command limited to the configured lock
+    pub fn limit(&self, steering: f32) -> f32 {
+        steering.clamp(-self.max_steering, self.max_steering)
+    }
+
+    // Steering gain at a vehicle speed (m/s): 1 at low speed, falling linearly to
+    // assist_min_gain between the assist speeds (always 1 with the assist off)
+    pub fn assist_factor(&self, speed: f32) -> f32 {
+        if !self.speed_assist {
+            return 1.;
+        }
+        let range = (self.assist_full_speed - self.assist_start_speed).max(f32::EPSILON);
+        let t = ((speed - self.assist_start_speed) / range).clamp(0., 1.);
+        1. - t * (1. - self.assist_min_gain)
+    }
+
+    // Steering command applied to the wheels: limited to the lock, then scaled by the assist
+    pub fn effective(&self, steering: f32, speed: f32) -> f32 {
+        self.limit(steering) * self.assist_factor(speed)
+    }
+}
+
+// Press 'H' (KeyBindings::toggle_steering_assist) to toggle speed-sensitive steering
+pub fn steering_assist_toggle_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    mut config: ResMut<SteeringConfig>,
+) {
+    if keyboard_input.just_pressed(keys.toggle_steering_assist) {
+        config.speed_assist = !config.speed_assist;
+        println!(
+            "Steering assist {}",
+            if config.speed_assist { "enabled" } else { "disabled" }
+        );
+    }
+}
+
+// Below this speed (m/s) the car counts as stopped for switching between drive and reverse
+const REVERSE_SWITCH_SPEED: f32 = 0.5;
+
+// Simulation speed relative to real time (1 = real time, 0.5 = half speed, ...)
+// It scales the frame time fed to the fixed timestep, so more or fewer physics
+// steps run per frame; the physics step itself (SimTime::dt) is unchanged.
+#[derive(Resource, Debug, Clone)]
+pub struct TimeScale {
+    pub scale: f32,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self { scale: 1.0 }
+    }
+}
+
+// Fast-forward is limited by how many physics steps a frame can afford
+const MIN_TIME_SCALE: f32 = 0.125;
+const MAX_TIME_SCALE: f32 = 8.0;
+
+// Press '-' / '=' (KeyBindings::slower / faster) to halve / double the simulation speed
+pub fn time_scale_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    mut time_scale: ResMut<TimeScale>,
+    mut time: ResMut<Time>,
+) {
+    if keyboard_input.just_pressed(keys.slower) {
+        time_scale.scale /= 2.0;
+    }
+    if keyboard_input.just_pressed(keys.faster) {
+        time_scale.scale *= 2.0;
+    }
+    if time_scale.is_changed() {
+        time_scale.scale = time_scale.scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+        time.set_relative_speed(time_scale.scale);
+        println!("Simulation speed: {}x", time_scale.scale);
+    }
+}
+
+// Press 'K' (KeyBindings::pause) to pause / resume the simulation,
+// and '.' (KeyBindings::step) to advance one physics step while paused
+pub fn sim_control_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    mut sim_control: ResMut<SimControl>,
+) {
+    if keyboard_input.just_pressed(keys.pause) {
+        sim_control.paused = !sim_control.paused;
+        println!("Simulation {}", if sim_control.paused { "paused" } else { "resumed" });
+    }
+    if sim_control.paused && keyboard_input.just_pressed(keys.step) {
+        sim_control.step_requested = true;
+    }
+}
+
+// Response of one gamepad axis
+// - deadzone: deflection (0..1) ignored around rest, to hide stick drift
+// - exponent: response curve past the deadzone (1 = linear, above 1 = finer control near center)
+#[derive(Clone, Copy, Debug)]
+pub struct AxisTuning {
+    pub deadzone: f32,
+    pub exponent: f32,
+}
+
+impl Default for AxisTuning {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.01,
+            exponent: 1.0,
+        }
+    }
+}
+
+impl AxisTuning {
+    // Maps a raw axis value (-1..1) to a control value (-1..1): zero inside the deadzone,
+    // then rescaled so the curve starts at zero at the deadzone and reaches 1 at full deflection
+    pub fn apply(&self, value: f32) -> f32 {
+        let deflection = value.abs();
+        if deflection <= self.deadzone {
+            return 0.;
+        }
+        let scaled = ((deflection - self.deadzone) / (1. - self.deadzone)).min(1.);
+        scaled.powf(self.exponent).copysign(value)
+    }
+}
+
+// Deadzones and response curves of the gamepad inputs.
+// The right stick uses the throttle tuning pushed forward and the brake tuning pulled back.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct GamepadTuning {
+    pub throttle: AxisTuning,
+    pub brake: AxisTuning,
+    pub steering: AxisTuning,
+}
+
+// Press 'O' (KeyBindings::cycle_control_source) to hand the car to the next control source
+// (User -> External -> Autonomous -> User). The controls are released on every switch so
+// the new source starts from a coasting car with centered steering.
+pub fn control_source_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    mut source: ResMut<ControlSource>,
+    mut control: ResMut<CarControl>,
+) {
+    if keyboard_input.just_pressed(keys.cycle_control_source) {
+        switch_control_source(&mut source, &mut control);
+    }
+}
+
+// Hands the car to the next control source and releases the controls
+pub fn switch_control_source(source: &mut ControlSource, control: &mut CarControl) {
+    *source = source.next();
+    control.throttle = 0.;
+    control.brake = 0.;
+    control.steering = 0.;
+    control.reverse = false;
+    println!("Control source: {:?}", *source);
 }
 
 pub fn user_control_system(
     keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
     gamepads: Res<Gamepads>,
     button_axes: Res<Axis<GamepadButton>>,
     axes: Res<Axis<GamepadAxis>>,
+    tuning: Res<GamepadTuning>,
+    steering_config: Res<SteeringConfig>,
     mut control: ResMut<CarControl>,
+    vehicle_state: Res<VehicleState>,
+    time: Res<Time>,
 ) {
+    let mut gamepad_handbrake: f32 = 0.0;
+
     // gamepad controls
     for gamepad in gamepads.iter() {
         // trigger controls
-        let throttle = button_axes
-            .get(GamepadButton::new(
-                gamepad,
-                GamepadButtonType::RightTrigger2,
-            ))
-            .unwrap();
+        let throttle = tuning.throttle.apply(
+            button_axes
+                .get(GamepadButton::new(
+                    gamepad,
+                    GamepadButtonType::RightTrigger2,
+                ))
+                .unwrap(),
+        );
 
-        if throttle > 0.01 {
+        if throttle > 0. {
             control.throttle = throttle;
         }
 
-        let brake = button_axes
-            .get(GamepadButton::new(gamepad, GamepadButtonType::LeftTrigger2))
-            .unwrap();
+        let brake = tuning.brake.apply(
+            button_axes
+                .get(GamepadButton::new(gamepad, GamepadButtonType::LeftTrigger2))
+                .unwrap(),
+        );
 
-        if brake > 0.01 {
+        if brake > 0. {
             control.brake = brake;
         }
 
+        // handbrake on the south face button (A / Cross)
+        let handbrake = button_axes
+            .get(GamepadButton::new(gamepad, GamepadButtonType::South))
+            .unwrap_or(0.0);
+        gamepad_handbrake = gamepad_handbrake.max(handbrake);
+
         // right stick throttle/brake
         let throttle_brake = axes
             .get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickY))
             .unwrap();
-        if throttle_brake > 0.01 {
-            control.throttle = throttle_brake;
-        }
-        if throttle_brake < -0.01 {
-            control.brake = -throttle_brake;
+        if throttle_brake > 0. {
+            let throttle = tuning.throttle.apply(throttle_brake);
+            if throttle > 0. {
+                control.throttle = throttle;
+            }
+        } else {
+            let brake = -tuning.brake.apply(throttle_brake);
+            if brake > 0. {
+                control.brake = brake;
+            }
         }
 
         // left stick steering
-        let steering = -axes
-            .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
-            .unwrap();
-        if steering.abs() > 0.01 {
+        let steering = -tuning.steering.apply(
+            axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+                .unwrap(),
+        );
+        if steering != 0. {
             control.steering = steering;
         }
     }
 
-    // Keyboard controls - these are rate controlled to make them feel more natural.
-    // When a key is pressed, the control value is increased at a constant rate.
-    // When a key is released, the control value is decreased at a constant rate.
-    // The control value is clamped between 0 and const MAX_SPEED for throttle and brake, 
-    // and between -1 and 1 for steering.
-    
+    // Drive / Reverse
+    // With the car stopped and the brake (S) held, pressing throttle (W) switches direction
+    if keyboard_input.just_pressed(keys.throttle)
+        && keyboard_input.pressed(keys.brake)
+        && vehicle_state.speed < REVERSE_SWITCH_SPEED
+    {
+        control.reverse = !control.reverse;
+        println!("Shifted to {}", if control.reverse { "reverse" } else { "drive" });
+    }
+
+    // Handbrake - Spacebar
+    // Not rate controlled, it is either pulled or released
+    let keyboard_handbrake = if keyboard_input.pressed(keys.handbrake) { 1.0 } else { 0.0 };
+    control.handbrake = gamepad_handbrake.max(keyboard_handbrake);
+
+    let held = HeldKeys {
+        throttle: keyboard_input.pressed(keys.throttle),
+        brake: keyboard_input.pressed(keys.brake),
+        steer_left: keyboard_input.pressed(keys.steer_left),
+        steer_right: keyboard_input.pressed(keys.steer_right),
+    };
+    ramp_keyboard_controls(&mut control, held, &steering_config, time.delta_seconds());
+}
+
+// Keyboard keys held this frame
+#[derive(Clone, Copy, Default)]
+pub struct HeldKeys {
+    pub throttle: bool,
+    pub brake: bool,
+    pub steer_left: bool,
+    pub steer_right: bool,
+}
+
+// Keyboard controls - these are rate controlled to make them feel more natural.
+// When a key is pressed, the control value is increased at a constant rate.
+// When a key is released, the control value is decreased at a constant rate.
+// The control value is clamped between 0 and const MAX_SPEED for throttle and brake, 
+// and between -1 and 1 for steering.
+// The response times are the seconds to go from 0 to full, whatever the frame rate.
+pub fn ramp_keyboard_controls(
+    control: &mut CarControl,
+    held: HeldKeys,
+    steering_config: &SteeringConfig,
+    dt: f32,
+) {
     let acceleration_response_time = 0.01;
     let brake_response_time = 0.2;
 
-    let accel_const: f32 =  1. / (acceleration_response_time * 60.);
-    let brake_const: f32 =  1. / (brake_response_time * 60.);
+    let accel_const: f32 = dt / acceleration_response_time;
+    let brake_const: f32 = dt / brake_response_time;
     
     // Acceleration
 
@@ -74,7 +367,7 @@ pub fn user_control_system(
     const MAX_SPEED: f32 = 1.0;                                 // Maximum throttle value
 
     // Forward Acceleration - Key W
-    if keyboard_input.pressed(KeyCode::W) {
+    if held.throttle {
         // Clamp acceleration at top speed (chooses min of max_speed and curr speed)
         control.throttle += accel_const;
         control.throttle = control.throttle.min(MAX_SPEED);
@@ -86,7 +379,7 @@ pub fn user_control_system(
         
 
     // Brake Control - Key S
-    if keyboard_input.pressed(KeyCode::S) {
+    if held.brake {
         control.brake += brake_const;
         control.brake = control.brake.min(MAX_SPEED * 10.0);    // Braking is greater than max speed for use of quick braking
     } else {
@@ -96,32 +389,32 @@ pub fn user_control_system(
 
 
     // Steering
-    // gradual adjustment controls
-    const MAX_STEERING: f32 = 1.0;                 // Max steering angle constant (Affects animation and physics)
-    let steer_increment = 0.03;             // Adjust this value for faster/slower steering response
-    let return_to_zero_increment = 0.09;    // Controls how fast the car will return to 0 or no steering after 'a' and 'd' are released
+    // gradual adjustment controls (see SteeringConfig), scaled by the frame time
+    let max_steering = steering_config.max_steering;
+    let steer_increment = steering_config.turn_in_rate * dt;
+    let return_to_zero_increment = steering_config.return_rate * dt;
     let mut steer_active = false;
 
 
     // Steer Left - Key A
-    if keyboard_input.pressed(KeyCode::A) {
+    if held.steer_left {
         steer_active = true;
-        if control.steering < MAX_STEERING {
+        if control.steering < max_steering {
             control.steering += steer_increment;
-            if control.steering > MAX_STEERING {
-                control.steering = MAX_STEERING;    // Clamp to max
+            if control.steering > max_steering {
+                control.steering = max_steering;    // Clamp to max
             }
         }
     }
 
 
     // Steer Right - Key D
-    if keyboard_input.pressed(KeyCode::D) {
+    if held.steer_right {
         steer_active = true;
-        if control.steering > -MAX_STEERING {
+        if control.steering > -max_steering {
             control.steering -= steer_increment;
-            if control.steering < -MAX_STEERING {
-                control.steering = -MAX_STEERING;   // Clamp to min
+            if control.steering < -max_steering {
+                control.steering = -max_steering;   // Clamp to min
             }
         }
     }
@@ -138,10 +431,245 @@ pub fn user_control_system(
         }
         
         // Clamp to ensure it stays within bounds
-        if control.steering > MAX_STEERING {
-            control.steering = MAX_STEERING;
-        } else if control.steering < -MAX_STEERING {
-            control.steering = -MAX_STEERING;
+        if control.steering > max_steering {
+            control.steering = max_steering;
+        } else if control.steering < -max_steering {
+            control.steering = -max_steering;
         }
     }
-}
\ No newline at end of file
+}
+
+// Gamepad rumble from the tires
+// - enabled: rumble on / off
+// - slip_start / slip_full: tire slip where the sliding rumble (weak motor) starts / is strongest
+// - impact_duration: length of the bump pulse on the strong motor (s)
+// (what counts as a bump is set by TerrainImpactThresholds)
+#[derive(Resource)]
+pub struct HapticsEnabled {
+    pub enabled: bool,
+    pub slip_start: f64,
+    pub slip_full: f64,
+    pub impact_duration: f32,
+}
+
+impl Default for HapticsEnabled {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            slip_start: 1.0,
+            slip_full: 3.0,
+            impact_duration: 0.15,
+        }
+    }
+}
+
+// How often the sliding rumble is renewed (s); each request lasts this long so they don't stack
+const SLIP_RUMBLE_PERIOD: f32 = 0.1;
+
+// Rumbles every connected gamepad: the weak motor while the tires slide past their grip
+// limit, and a pulse on the strong motor for each TerrainImpact, scaled by how deep the
+// tire sank in. Does nothing without a gamepad.
+pub fn rumble_system(
+    gamepads: Res<Gamepads>,
+    haptics: Res<HapticsEnabled>,
+    thresholds: Res<TerrainImpactThresholds>,
+    time: Res<Time>,
+    tires: Query<&TireContact>,
+    mut impacts: EventReader<TerrainImpact>,
+    mut rumble_requests: EventWriter<GamepadRumbleRequest>,
+    mut slip_timer: Local<f32>,
+) {
+    if !haptics.enabled || gamepads.iter().next().is_none() {
+        impacts.clear();
+        return;
+    }
+
+    let slip = tires.iter().map(|tire| tire.slip).fold(0., f64::max);
+
+    for impact in impacts.iter() {
+        let strength = (0.5 * impact.magnitude / thresholds.penetration).clamp(0.25, 1.) as f32;
+        for gamepad in gamepads.iter() {
+            rumble_requests.send(GamepadRumbleRequest::Add {
+                duration: Duration::from_secs_f32(haptics.impact_duration),
+                intensity: GamepadRumbleIntensity::strong_motor(strength),
+                gamepad,
+            });
+        }
+    }
+
+    *slip_timer -= time.delta_seconds();
+    if *slip_timer > 0. {
+        return;
+    }
+    *slip_timer = SLIP_RUMBLE_PERIOD;
+    let strength = ((slip - haptics.slip_start) / (haptics.slip_full - haptics.slip_start))
+        .clamp(0., 1.) as f32;
+    if strength > 0. {
+        for gamepad in gamepads.iter() {
+            rumble_requests.send(GamepadRumbleRequest::Add {
+                duration: Duration::from_secs_f32(SLIP_RUMBLE_PERIOD),
+                intensity: GamepadRumbleIntensity::weak_motor(strength),
+                gamepad,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Hold the keys for each frame in `dts`
+    fn run(control: &mut CarControl, held: HeldKeys, dts: &[f32]) {
+        let config = SteeringConfig::default();
+        for &dt in dts {
+            ramp_keyboard_controls(control, held, &config, dt);
+        }
+    }
+
+    #[test]
+    fn keyboard_ramps_do_not_depend_on_frame_rate() {
+        let steady = [0.25 / 15.; 15];
+        let uneven = [0.1, 0.05, 0.07, 0.03];
+        let press = HeldKeys {
+            brake: true,
+            steer_left: true,
+            ..default()
+        };
+        let release = HeldKeys::default();
+
+        let mut a = CarControl::default();
+        let mut b = CarControl::default();
+        run(&mut a, press, &steady);
+        run(&mut b, press, &uneven);
+        assert!((a.steering - 0.45).abs() < 1e-5, "steering {}", a.steering);
+        assert!((a.steering - b.steering).abs() < 1e-5);
+        assert!((a.brake - 1.25).abs() < 1e-5, "brake {}", a.brake);
+        assert!((a.brake - b.brake).abs() < 1e-5);
+
+        // let go for 0.05 s: steering returns toward center at the same rate
+        run(&mut a, release, &[0.05 / 3.; 3]);
+        run(&mut b, release, &[0.02, 0.03]);
+        assert!((a.steering - 0.18).abs() < 1e-5, "steering {}", a.steering);
+        assert!((a.steering - b.steering).abs() < 1e-5);
+        assert!((a.brake - b.brake).abs() < 1e-5);
+    }
+
+    #[test]
+    fn axis_tuning_deadzone_expo_and_bounds() {
+        let tuning = AxisTuning {
+            deadzone: 0.1,
+            exponent: 2.0,
+        };
+        // nothing inside the deadzone, either way
+        for value in [0., 0.05, -0.05, 0.1, -0.1] {
+            assert_eq!(tuning.apply(value), 0.);
+        }
+        // past it the curve starts from zero, is squared and keeps the sign
+        assert!((tuning.apply(0.55) - 0.25).abs() < 1e-6);
+        assert!((tuning.apply(-0.55) + 0.25).abs() < 1e-6);
+        assert!(tuning.apply(0.11) > 0. && tuning.apply(0.11) < 1e-3);
+        // full deflection (and raw values past it) stay within -1..1
+        for value in [1., 1.2, -1., -1.2] {
+            assert!((tuning.apply(value).abs() - 1.).abs() < 1e-6);
+        }
+        for i in -20..=20 {
+            let value = i as f32 * 0.06;
+            assert!(tuning.apply(value).abs() <= 1.);
+        }
+
+        // linear with no deadzone passes values through
+        let linear = AxisTuning {
+            deadzone: 0.,
+            exponent: 1.,
+        };
+        for value in [-1., -0.3, 0.2, 1.] {
+            assert!((linear.apply(value) - value).abs() < 1e-6);
+        }
+    }
+
+    // The three CarControl writers with the run conditions they are registered with.
+    // Every source asks for different controls:
+    // - User: W, A and Space held over a 20 ms frame
+    // - External: a client sending throttle 0.3, brake 0.2, steering -0.4
+    // - Autonomous: no path to follow, so it stops the car
+    // The autonomous controller runs first so a later writer running by mistake shows.
+    fn control_app(source: ControlSource) -> App {
+        use crate::autonomous_control::{
+            autonomous_control_system, AutonomousController, ControlMode, Waypoints,
+        };
+        use crate::external_control::{update_from_external_controls, ExternalControls};
+        use bevy_integrator::SimTime;
+
+        let mut app = App::new();
+        app.insert_resource(source)
+            .init_resource::<CarControl>()
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<Gamepads>()
+            .init_resource::<Axis<GamepadButton>>()
+            .init_resource::<Axis<GamepadAxis>>()
+            .init_resource::<GamepadTuning>()
+            .init_resource::<SteeringConfig>()
+            .init_resource::<VehicleState>()
+            .init_resource::<Time>()
+            .init_resource::<ExternalControls>()
+            .insert_resource(SimTime::new(0.002, 0., None))
+            .init_resource::<ControlMode>()
+            .init_resource::<Waypoints>()
+            .init_resource::<AutonomousController>()
+            .add_systems(
+                Update,
+                (
+                    autonomous_control_system.run_if(
+                        resource_changed::<SimTime>()
+                            .and_then(resource_equals(ControlSource::Autonomous)),
+                    ),
+                    user_control_system.run_if(resource_equals(ControlSource::User)),
+                    update_from_external_controls
+                        .run_if(resource_equals(ControlSource::External)),
+                )
+                    .chain(),
+            );
+
+        let mut keyboard = app.world.resource_mut::<Input<KeyCode>>();
+        for key in [KeyCode::W, KeyCode::A, KeyCode::Space] {
+            keyboard.press(key);
+        }
+        let mut time = app.world.resource_mut::<Time>();
+        let start = time.startup();
+        time.update_with_instant(start);
+        time.update_with_instant(start + Duration::from_millis(20));
+        assert!(app.world.resource::<ExternalControls>().submit(0, 0.3, 0.2, -0.4));
+        app
+    }
+
+    fn controls(app: &App) -> (f32, f32, f32, f32) {
+        let control = app.world.resource::<CarControl>();
+        (control.throttle, control.brake, control.steering, control.handbrake)
+    }
+
+    fn assert_controls(actual: (f32, f32, f32, f32), expected: (f32, f32, f32, f32)) {
+        let [a, e] = [actual, expected].map(|(t, b, s, h)| [t, b, s, h]);
+        for (actual_value, expected_value) in a.iter().zip(e) {
+            let close = (actual_value - expected_value).abs() < 1e-5;
+            assert!(close, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn only_the_active_source_writes_car_control() {
+        let mut app = control_app(ControlSource::User);
+        app.update();
+        // full throttle after 20 ms, 20 ms of steering at 1.8 / s and the handbrake pulled
+        assert_controls(controls(&app), (1., 0., 0.036, 1.));
+
+        let mut app = control_app(ControlSource::External);
+        app.update();
+        assert_controls(controls(&app), (0.3, 0.2, -0.4, 0.));
+
+        let mut app = control_app(ControlSource::Autonomous);
+        app.update();
+        assert_controls(controls(&app), (0., 1., 0., 0.));
+    }
+}
@@ -1,10 +1,45 @@
 use bevy::prelude::*;
 
+use crate::obstacle::spawn_cone_row;
 use grid_terrain::{
     examples::{steps, table_top, wave},
     GridTerrain,
 };
 
+// Offsets used wherever something is placed on or tested against the terrain surface,
+// kept together so every code path agrees on where "on the surface" is
+// - surface_offset: height above the surface for drawn lines and skid marks (m),
+//   enough to keep them from z-fighting with the terrain mesh
+// - penetration_allowance: tire contact depth that is ignored before the tire pushes
+//   back (m); a little allowance keeps resting tires from chattering on seams
+// - ray_step: step along a ray while searching for the terrain surface (m)
+// - snap_above / snap_below: how far above / below a point the surface is searched
+//   for when snapping it to the terrain (m)
+#[derive(Resource, Clone, Debug)]
+pub struct ContactConfig {
+    pub surface_offset: f32,
+    pub penetration_allowance: f64,
+    pub ray_step: f32,
+    pub snap_above: f32,
+    pub snap_below: f32,
+}
+
+impl Default for ContactConfig {
+    fn default() -> Self {
+        Self {
+            surface_offset: 0.01,
+            penetration_allowance: 0.,
+            ray_step: 0.05,
+            snap_above: 2.0,
+            snap_below: 8.0,
+        }
+    }
+}
+
+// Height of the flat track around the terrain pieces (m). The ground is a Plane at this
+// height for both the collisions and the drawn ground, so tires touch it where it is seen.
+pub const TRACK_HEIGHT: f64 = 0.;
+
 pub fn build_environment(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -12,6 +47,27 @@ pub fn build_environment(
 ) {
     // Ambient and directional lighting setup has been moved to `weather.rs`
 
+    let mut grid_terrain = environment_terrain();
+    let empty_parent = commands.spawn(SpatialBundle::default()).id();
+
+    grid_terrain.build_meshes(&mut commands, &mut meshes, &mut materials, empty_parent);
+    commands.insert_resource(grid_terrain);
+
+    // a row of cones on the flat ground behind the start
+    spawn_cone_row(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        Vec3::new(-40., 20., TRACK_HEIGHT as f32),
+        Vec3::X,
+        8,
+        3.,
+    );
+}
+
+// The terrain of build_environment without any meshes, so it can be used for collisions
+// where nothing is rendered (see the headless example)
+pub fn environment_terrain() -> GridTerrain {
     let size = 20.0;
 
     let height = 2.;
@@ -27,9 +83,5 @@ pub fn build_environment(
     elements.extend(wave_elements);
     elements.extend(step_elements);
 
-    let grid_terrain = GridTerrain::new(elements, [size, size]);
-    let empty_parent = commands.spawn(SpatialBundle::default()).id();
-
-    grid_terrain.build_meshes(&mut commands, &mut meshes, &mut materials, empty_parent);
-    commands.insert_resource(grid_terrain);
+    GridTerrain::new(elements, [size, size]).with_ground_height(TRACK_HEIGHT)
 }
\ No newline at end of file
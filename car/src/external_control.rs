@@ -1,13 +1,15 @@
 use bevy::prelude::*;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
 use tokio::runtime::Runtime;
 use tokio::task;
 use tokio_tungstenite::accept_async;
-use futures_util::stream::StreamExt;
-use futures_util::SinkExt; 
+use futures_util::stream::{Stream, StreamExt};
+use futures_util::{Sink, SinkExt};
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::WebSocketStream;
@@ -15,14 +17,136 @@ use tokio_tungstenite::MaybeTlsStream;
 use tokio::sync::Mutex as AsyncMutex;
 use tokio::net::TcpStream;
 
-use crate::control::CarControl;
+use crate::control::{CarControl, ControlSource};
 use crate::control::user_control_system;
 
+// Connection heartbeat (see handle_websocket_connection)
+// - interval: time between pings
+// - timeout: silence (no pong or data) after which the client is dropped
+#[derive(Clone, Copy, Debug)]
+struct Heartbeat {
+    interval: Duration,
+    timeout: Duration,
+}
+
+const HEARTBEAT: Heartbeat = Heartbeat {
+    interval: Duration::from_secs(2),
+    timeout: Duration::from_secs(6),
+};
+
+// Which connected client may drive the car
+// - LastWriterWins: every client's message is applied, the latest one wins
+// - PrimaryClientOnly: only the longest-connected client is applied, the others are ignored
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClientPolicy {
+    #[default]
+    LastWriterWins,
+    PrimaryClientOnly,
+}
+
+// Connected WebSocket clients by ID (IDs count up from 0 in connection order)
+#[derive(Default)]
+pub struct ExternalClients {
+    pub clients: BTreeMap<u64, SocketAddr>,
+    pub policy: ClientPolicy,
+    next_id: u64,
+    driver: Option<u64>,  // client whose controls were applied last
+}
+
+impl ExternalClients {
+    // Registers a new client and returns its ID
+    fn connect(&mut self, address: SocketAddr) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.clients.insert(id, address);
+        id
+    }
+
+    // Removes a client and returns its address
+    fn disconnect(&mut self, id: u64) -> Option<SocketAddr> {
+        self.clients.remove(&id)
+    }
+
+    // The longest-connected client still connected
+    pub fn primary(&self) -> Option<u64> {
+        self.clients.keys().next().copied()
+    }
+
+    // True if messages from client `id` should be applied under the current policy
+    fn accepts(&self, id: u64) -> bool {
+        match self.policy {
+            ClientPolicy::LastWriterWins => true,
+            ClientPolicy::PrimaryClientOnly => self.primary() == Some(id),
+        }
+    }
+}
+
 // Define a Resource to hold external data
-#[derive(Resource)]
+// (clones share the same control values and clients, the server tasks each hold one)
+#[derive(Resource, Clone)]
 pub struct ExternalControls {
     control: Arc<Mutex<CarControl>>,
     last_update: Arc<Mutex<Instant>>, // Track the last time external input was received
+    clients: Arc<Mutex<ExternalClients>>,
+}
+
+impl Default for ExternalControls {
+    fn default() -> Self {
+        Self {
+            control: Arc::new(Mutex::new(CarControl::default())),
+            last_update: Arc::new(Mutex::new(Instant::now())),
+            clients: Arc::new(Mutex::new(ExternalClients::default())),
+        }
+    }
+}
+
+impl ExternalControls {
+    // Takes control values sent by client `id`, unless the client policy ignores that
+    // client. Returns true if the values were taken.
+    pub fn submit(&self, id: u64, throttle: f32, brake: f32, steering: f32) -> bool {
+        {
+            let mut clients = self.clients.lock().unwrap();
+            if !clients.accepts(id) {
+                return false;
+            }
+            clients.driver = Some(id);
+        }
+        if let Ok(mut car_control) = self.control.lock() {
+            car_control.throttle = throttle;
+            car_control.brake = brake;
+            car_control.steering = steering;
+            *self.last_update.lock().unwrap() = Instant::now();
+        }
+        true
+    }
+
+    // Drops client `id` and returns its address. If it was driving, the controls are
+    // released (throttle, brake and steering back to zero) so the car coasts instead of
+    // holding the last command of a client that is gone.
+    fn disconnect(&self, id: u64) -> Option<SocketAddr> {
+        let mut clients = self.clients.lock().unwrap();
+        if clients.driver == Some(id) {
+            clients.driver = None;
+            if let Ok(mut car_control) = self.control.lock() {
+                car_control.throttle = 0.;
+                car_control.brake = 0.;
+                car_control.steering = 0.;
+                *self.last_update.lock().unwrap() = Instant::now();
+            }
+        }
+        clients.disconnect(id)
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.clients.lock().map_or(0, |clients| clients.clients.len())
+    }
+
+    pub fn set_policy(&self, policy: ClientPolicy) {
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.policy = policy;
+            println!("External client policy: {:?}", policy);
+        }
+    }
 }
 
 pub struct ExternalControlPlugin;
@@ -32,29 +156,32 @@ impl Plugin for ExternalControlPlugin {
         // Create a single Tokio runtime here
         let rt = Runtime::new().expect("Failed to create Tokio runtime");
 
-        // Shared CarControl and last_update
-        let control = Arc::new(Mutex::new(CarControl::default()));
-        let last_update = Arc::new(Mutex::new(Instant::now()));
+        // Shared CarControl, last_update and clients
+        let external_controls = ExternalControls::default();
 
         // Clone these for server thread
-        let websocket_control = control.clone();
-        let websocket_last_update = last_update.clone();
+        let websocket_controls = external_controls.clone();
 
         // Spawn a thread that uses THIS runtime
         thread::spawn(move || {
             rt.block_on(async move {
-                start_websocket_server(websocket_control, websocket_last_update).await;
+                start_websocket_server(websocket_controls).await;
             });
         });
 
         // Insert resources into Bevy
-        app.insert_resource(ExternalControls { control, last_update })
-           .add_systems(Update, update_from_external_controls.after(user_control_system),);
+        app.insert_resource(external_controls)
+           .add_systems(
+               Update,
+               update_from_external_controls
+                   .after(user_control_system)
+                   .run_if(resource_equals(ControlSource::External)),
+           );
     }
 }
 
 // Receive WebSocket messages and update CarControl
-fn update_from_external_controls(
+pub fn update_from_external_controls(
     external_controls: Res<ExternalControls>,
     mut car_control: ResMut<CarControl>,
 ) {
@@ -85,23 +212,30 @@ fn update_from_external_controls(
 }
 
 // WebSocket Server to listen for external inputs
-async fn start_websocket_server(
-    control: Arc<Mutex<CarControl>>,
-    last_update: Arc<Mutex<Instant>>,
-) {
+async fn start_websocket_server(external_controls: ExternalControls) {
     let listener = TcpListener::bind("127.0.0.1:8080")
         .await
         .expect("Failed to bind WebSocket server on :8080");
 
     println!("Bevy WebSocket server listening on ws://127.0.0.1:8080");
 
-    while let Ok((stream, _)) = listener.accept().await {
-        println!("New WebSocket connection established.");
+    while let Ok((stream, address)) = listener.accept().await {
         match accept_async(stream).await {
             Ok(ws_stream) => {
-                let c = control.clone();
-                let lu = last_update.clone();
-                task::spawn(handle_websocket_connection(ws_stream, c, lu));
+                let (id, count) = {
+                    let mut clients = external_controls.clients.lock().unwrap();
+                    let id = clients.connect(address);
+                    (id, clients.clients.len())
+                };
+                println!("WebSocket client {} connected from {} ({} connected)", id, address, count);
+                let (sink, stream) = ws_stream.split();
+                task::spawn(handle_websocket_connection(
+                    sink,
+                    stream,
+                    id,
+                    external_controls.clone(),
+                    HEARTBEAT,
+                ));
             }
             Err(e) => {
                 eprintln!("Failed to accept WebSocket connection: {}", e);
@@ -111,35 +245,72 @@ async fn start_websocket_server(
 }
 
 // Handle incoming WebSocket messages
-async fn handle_websocket_connection(
-    mut ws_stream: tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
-    control: Arc<Mutex<CarControl>>,
-    last_update: Arc<Mutex<Instant>>,
-) {
-    println!("Listening for incoming WebSocket messages...");
+// (the two halves of the split connection, see start_websocket_server)
+async fn handle_websocket_connection<Si, St, E>(
+    mut ws_sink: Si,
+    mut ws_stream: St,
+    id: u64,
+    external_controls: ExternalControls,
+    heartbeat: Heartbeat,
+) where
+    Si: Sink<Message> + Unpin,
+    St: Stream<Item = Result<Message, E>> + Unpin,
+{
+    println!("Listening for incoming WebSocket messages from client {}...", id);
 
-    while let Some(Ok(msg)) = ws_stream.next().await {
-        println!("Received WebSocket message: {:?}", msg);
+    // Heartbeat: ping the client regularly and drop it once nothing (pong or data)
+    // has arrived for heartbeat.timeout, so a half-closed connection can't hang forever
+    let timeout = heartbeat.timeout;
+    let mut heartbeat = tokio::time::interval(heartbeat.interval);
+    let mut last_seen = Instant::now();
+
+    loop {
+        let msg = tokio::select! {
+            msg = ws_stream.next() => match msg {
+                Some(Ok(msg)) => msg,
+                _ => break,
+            },
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > timeout {
+                    println!("WebSocket client {} stopped responding", id);
+                    break;
+                }
+                if ws_sink.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+        };
+        last_seen = Instant::now();
+
+        match msg {
+            Message::Pong(_) | Message::Ping(_) => continue,
+            Message::Close(_) => break,
+            _ => {}
+        }
+        println!("Received WebSocket message from client {}: {:?}", id, msg);
 
         if let Message::Text(text) = msg {
             if let Some((throttle, brake, steering)) = parse_control_data(&text) {
+                if !external_controls.submit(id, throttle, brake, steering) {
+                    println!("Ignoring client {}: only the primary client may drive", id);
+                    continue;
+                }
                 println!(
-                    "Parsed control data - Throttle: {:.2}, Brake: {:.2}, Steering: {:.2}",
-                    throttle, brake, steering
+                    "Parsed control data from client {} - Throttle: {:.2}, Brake: {:.2}, Steering: {:.2}",
+                    id, throttle, brake, steering
                 );
-                if let Ok(mut car_control) = control.lock() {
-                    car_control.throttle = throttle;
-                    car_control.brake = brake;
-                    car_control.steering = steering;
-                    *last_update.lock().unwrap() = Instant::now();
-                }
             } else {
                 println!("Failed to parse control data from: {}", text);
             }
         }
     }
 
-    println!("WebSocket connection closed.");
+    let address = external_controls.disconnect(id);
+    match address {
+        Some(address) => println!("WebSocket client {} ({}) disconnected", id, address),
+        None => println!("WebSocket client {} disconnected", id),
+    }
 }
 
 
@@ -157,3 +328,85 @@ fn parse_control_data(msg: &str) -> Option<(f32, f32, f32)> {
 
     Some((throttle, brake, steering))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{sink, stream};
+
+    const FAST_HEARTBEAT: Heartbeat = Heartbeat {
+        interval: Duration::from_millis(10),
+        timeout: Duration::from_millis(50),
+    };
+
+    fn connect(external_controls: &ExternalControls) -> u64 {
+        let address = "127.0.0.1:9000".parse().unwrap();
+        external_controls.clients.lock().unwrap().connect(address)
+    }
+
+    // Runs a connection whose client sends `messages` and then goes silent without
+    // closing it. Returns once the handler has dropped the client.
+    async fn run_silent_client(
+        external_controls: &ExternalControls,
+        id: u64,
+        messages: Vec<&str>,
+    ) {
+        let messages = messages
+            .into_iter()
+            .map(|text| Ok::<_, ()>(Message::Text(text.to_string())));
+        let stream = stream::iter(messages).chain(stream::pending());
+        let connection = handle_websocket_connection(
+            sink::drain(),
+            stream,
+            id,
+            external_controls.clone(),
+            FAST_HEARTBEAT,
+        );
+        tokio::time::timeout(Duration::from_secs(5), connection)
+            .await
+            .expect("the silent client was never dropped");
+    }
+
+    fn shared_controls(external_controls: &ExternalControls) -> (f32, f32, f32) {
+        let control = external_controls.control.lock().unwrap();
+        (control.throttle, control.brake, control.steering)
+    }
+
+    #[tokio::test]
+    async fn silent_client_times_out_and_releases_control() {
+        let external_controls = ExternalControls::default();
+        let id = connect(&external_controls);
+
+        let started = Instant::now();
+        run_silent_client(&external_controls, id, vec!["0.8 0.1 -0.3"]).await;
+        assert!(started.elapsed() >= FAST_HEARTBEAT.timeout);
+        assert_eq!(external_controls.client_count(), 0);
+        assert_eq!(shared_controls(&external_controls), (0., 0., 0.));
+
+        // the release reaches the car, which was still on the client's last command
+        let mut app = App::new();
+        app.insert_resource(external_controls)
+            .insert_resource(CarControl {
+                throttle: 0.8,
+                brake: 0.1,
+                steering: -0.3,
+                ..default()
+            })
+            .add_systems(Update, update_from_external_controls);
+        app.update();
+        let control = app.world.resource::<CarControl>();
+        assert_eq!((control.throttle, control.brake, control.steering), (0., 0., 0.));
+    }
+
+    #[tokio::test]
+    async fn a_silent_passenger_leaves_the_driver_in_control() {
+        let external_controls = ExternalControls::default();
+        let driver = connect(&external_controls);
+        let passenger = connect(&external_controls);
+        assert!(external_controls.submit(driver, 0.5, 0., 0.2));
+
+        run_silent_client(&external_controls, passenger, vec![]).await;
+        assert_eq!(external_controls.client_count(), 1);
+        assert_eq!(shared_controls(&external_controls), (0.5, 0., 0.2));
+    }
+}
@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+use rigid_body::joint::Joint;
+
+use crate::lap_timer::LapTimer;
+
+// Ghost of the best lap, driven alongside the live car to compare laps.
+// The car body pose is recorded during every lap; when a lap is the best so far its
+// recording becomes the ghost, which is replayed against the current lap time.
+// The ghost is only a translucent box, not a rigid body, so it never collides with the car.
+// - enabled: show the ghost
+// - dimensions: size of the ghost box (matches the chassis in build_car)
+// - sample_interval: lap time between recorded poses (s)
+// - best_lap: lap time of the ghost (s)
+#[derive(Resource)]
+pub struct GhostCar {
+    pub enabled: bool,
+    pub dimensions: Vec3,
+    pub sample_interval: f64,
+    pub best_lap: Option<f64>,
+    recording: Vec<(f64, Transform)>, // (lap time, car body pose) of the current lap
+    ghost: Vec<(f64, Transform)>,     // recording of the best lap
+    laps: usize,                      // LapTimer::laps at the last update
+}
+
+impl Default for GhostCar {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dimensions: Vec3::new(3.0, 1.2, 0.4),
+            sample_interval: 0.05,
+            best_lap: None,
+            recording: Vec::new(),
+            ghost: Vec::new(),
+            laps: 0,
+        }
+    }
+}
+
+impl GhostCar {
+    // Ghost pose at `time` into the lap, interpolated between the recorded poses.
+    // None without a ghost or once the ghost has finished its lap.
+    pub fn pose(&self, time: f64) -> Option<Transform> {
+        let index = self.ghost.partition_point(|(t, _)| *t <= time);
+        if index == 0 || index == self.ghost.len() {
+            return None;
+        }
+        let (t0, pose0) = self.ghost[index - 1];
+        let (t1, pose1) = self.ghost[index];
+        let s = ((time - t0) / (t1 - t0)) as f32;
+        Some(Transform {
+            translation: pose0.translation.lerp(pose1.translation, s),
+            rotation: pose0.rotation.slerp(pose1.rotation, s),
+            scale: Vec3::ONE,
+        })
+    }
+}
+
+#[derive(Component)]
+pub struct Ghost;
+
+pub fn ghost_setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    ghost: Res<GhostCar>,
+) {
+    let size = ghost.dimensions;
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Box::new(size.x, size.y, size.z))),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgba(0.3, 0.8, 1.0, 0.35),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        Name::new("Ghost"),
+        Ghost,
+    ));
+}
+
+// Records the car body pose during each lap and keeps the best lap as the ghost.
+// Moving the finish line clears the lap times, and the ghost with them.
+pub fn ghost_record_system(
+    lap_timer: Res<LapTimer>,
+    joints: Query<(&Joint, &GlobalTransform)>,
+    mut ghost: ResMut<GhostCar>,
+) {
+    if lap_timer.laps < ghost.laps || lap_timer.lap_start.is_none() {
+        ghost.ghost.clear();
+        ghost.recording.clear();
+        ghost.best_lap = None;
+        ghost.laps = lap_timer.laps;
+        return;
+    }
+    if lap_timer.laps > ghost.laps {
+        if lap_timer.last_lap.is_some() && lap_timer.last_lap == lap_timer.best_lap {
+            ghost.ghost = std::mem::take(&mut ghost.recording);
+            ghost.best_lap = lap_timer.best_lap;
+        }
+        ghost.recording.clear();
+        ghost.laps = lap_timer.laps;
+    }
+
+    let time = lap_timer.current_lap;
+    let due = ghost
+        .recording
+        .last()
+        .map_or(true, |(last, _)| time - last >= ghost.sample_interval);
+    if !due {
+        return;
+    }
+    // the car body is the roll joint, last in the chassis chain
+    if let Some((_, transform)) = joints.iter().find(|(joint, _)| joint.name == "chassis_rx") {
+        ghost.recording.push((time, transform.compute_transform()));
+    }
+}
+
+// Moves the ghost along the best lap at the current lap time
+pub fn ghost_playback_system(
+    lap_timer: Res<LapTimer>,
+    ghost: Res<GhostCar>,
+    mut query: Query<(&mut Transform, &mut Visibility), With<Ghost>>,
+) {
+    let pose = if ghost.enabled {
+        ghost.pose(lap_timer.current_lap)
+    } else {
+        None
+    };
+    for (mut transform, mut visibility) in query.iter_mut() {
+        match pose {
+            Some(pose) => {
+                *transform = pose;
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
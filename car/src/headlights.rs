@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+
+use crate::{
+    key_bindings::KeyBindings,
+    weather::{TimeOfDay, Weather},
+};
+
+// Headlight setting, cycled with KeyBindings::headlights
+// - Auto: low beam when it's dark (Night weather, rain, or night on the day/night cycle)
+// - Low / High: always on
+// - Off
+#[derive(Resource, Default, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum HeadlightMode {
+    #[default]
+    Auto,
+    Low,
+    High,
+    Off,
+}
+
+impl HeadlightMode {
+    pub fn next(self) -> Self {
+        match self {
+            HeadlightMode::Auto => HeadlightMode::Low,
+            HeadlightMode::Low => HeadlightMode::High,
+            HeadlightMode::High => HeadlightMode::Off,
+            HeadlightMode::Off => HeadlightMode::Auto,
+        }
+    }
+}
+
+// One of the car's headlight spot lights
+#[derive(Component)]
+pub struct Headlight;
+
+// Beam settings: intensity (lumens), range (m), cone outer angle (rad) and
+// how far the beam points down (rad)
+const LOW_BEAM: (f32, f32, f32, f32) = (8000., 40., 0.6, 0.08);
+const HIGH_BEAM: (f32, f32, f32, f32) = (30000., 120., 0.4, 0.02);
+
+// Spawns two headlights at the front corners of the chassis box (chassis coordinates,
+// x forward), parented to the car body. They start off, headlight_system turns them on.
+pub fn spawn_headlights(commands: &mut Commands, chassis_id: Entity, dimensions: [f64; 3]) {
+    let front = 0.5 * dimensions[0] as f32;
+    let side = 0.35 * dimensions[1] as f32;
+    for y in [side, -side] {
+        commands
+            .spawn(SpotLightBundle {
+                spot_light: SpotLight {
+                    color: Color::rgb(1.0, 0.95, 0.85),
+                    intensity: 0.,
+                    shadows_enabled: false,
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(front, y, 0.)),
+                ..default()
+            })
+            .set_parent(chassis_id)
+            .insert(Name::new("Headlight"))
+            .insert(Headlight);
+    }
+}
+
+// Press ';' (KeyBindings::headlights) to cycle auto / low / high / off
+pub fn headlight_toggle_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    mut mode: ResMut<HeadlightMode>,
+) {
+    if keyboard_input.just_pressed(keys.headlights) {
+        *mode = mode.next();
+        println!("Headlights: {:?}", *mode);
+    }
+}
+
+// Sets the beam of every headlight from the mode (and, in Auto, from how dark it is)
+pub fn headlight_system(
+    mode: Res<HeadlightMode>,
+    weather: Option<Res<Weather>>,
+    time_of_day: Option<Res<TimeOfDay>>,
+    mut lights: Query<(&mut SpotLight, &mut Transform), With<Headlight>>,
+) {
+    let dark = weather.map_or(false, |weather| matches!(*weather, Weather::Night | Weather::Rain))
+        || time_of_day.map_or(false, |time_of_day| time_of_day.is_dark());
+    let beam = match *mode {
+        HeadlightMode::Auto if dark => Some(LOW_BEAM),
+        HeadlightMode::Low => Some(LOW_BEAM),
+        HeadlightMode::High => Some(HIGH_BEAM),
+        _ => None,
+    };
+    for (mut light, mut transform) in lights.iter_mut() {
+        match beam {
+            Some((intensity, range, outer_angle, pitch)) => {
+                light.intensity = intensity;
+                light.range = range;
+                light.outer_angle = outer_angle;
+                light.inner_angle = 0.7 * outer_angle;
+                transform.rotation = Transform::IDENTITY
+                    .looking_to(Vec3::new(pitch.cos(), 0., -pitch.sin()), Vec3::Z)
+                    .rotation;
+            }
+            None => light.intensity = 0.,
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use bevy::prelude::Vec3;
+
 #[derive(Debug, Clone)]
 pub struct Interpolator1D {
     x: Vec<f64>,
@@ -45,3 +47,135 @@ fn bin_search(x: &Vec<f64>, target: f64) -> usize {
     }
     i
 }
+
+// Catmull-Rom spline through a list of control points, for smoothing paths.
+// t = i is control point i, so t runs from 0 to len - 1 (to len for a closed loop,
+// which comes back to the first point). The end segments of an open path repeat
+// the end points; a closed path wraps around.
+#[derive(Debug, Clone)]
+pub struct CatmullRom {
+    points: Vec<Vec3>,
+    closed: bool,
+}
+
+impl CatmullRom {
+    pub fn new(points: Vec<Vec3>) -> Self {
+        Self { points, closed: false }
+    }
+
+    pub fn closed(points: Vec<Vec3>) -> Self {
+        Self { points, closed: true }
+    }
+
+    // Largest valid t (number of segments)
+    pub fn max_t(&self) -> f32 {
+        match self.points.len() {
+            0 | 1 => 0.,
+            n if self.closed => n as f32,
+            n => (n - 1) as f32,
+        }
+    }
+
+    // Point on the spline, t is clamped to 0..=max_t
+    pub fn sample(&self, t: f32) -> Vec3 {
+        match self.points.len() {
+            0 => return Vec3::ZERO,
+            1 => return self.points[0],
+            _ => {}
+        }
+        let (p0, p1, p2, p3, u) = self.segment(t);
+        0.5 * (2. * p1
+            + (p2 - p0) * u
+            + (2. * p0 - 5. * p1 + 4. * p2 - p3) * u * u
+            + (3. * p1 - p0 - 3. * p2 + p3) * u * u * u)
+    }
+
+    // Derivative of the spline with respect to t (direction of travel, not normalized)
+    pub fn tangent(&self, t: f32) -> Vec3 {
+        if self.points.len() < 2 {
+            return Vec3::ZERO;
+        }
+        let (p0, p1, p2, p3, u) = self.segment(t);
+        0.5 * ((p2 - p0)
+            + 2. * (2. * p0 - 5. * p1 + 4. * p2 - p3) * u
+            + 3. * (3. * p1 - p0 - 3. * p2 + p3) * u * u)
+    }
+
+    // Points along the whole spline, `per_segment` points per segment (including the
+    // control points, and the last point of an open path)
+    pub fn resample(&self, per_segment: usize) -> Vec<Vec3> {
+        let count = (self.max_t() as usize) * per_segment.max(1);
+        let mut points: Vec<Vec3> = (0..count)
+            .map(|i| self.sample(i as f32 / per_segment.max(1) as f32))
+            .collect();
+        if !self.closed {
+            points.extend(self.points.last());
+        }
+        points
+    }
+
+    // The four control points around t and the position u (0..1) within the segment
+    fn segment(&self, t: f32) -> (Vec3, Vec3, Vec3, Vec3, f32) {
+        let t = t.clamp(0., self.max_t());
+        let i = (t.floor() as usize).min(self.max_t() as usize - 1);
+        let u = t - i as f32;
+        let i = i as isize;
+        (
+            self.control(i - 1),
+            self.control(i),
+            self.control(i + 1),
+            self.control(i + 2),
+            u,
+        )
+    }
+
+    fn control(&self, i: isize) -> Vec3 {
+        let n = self.points.len() as isize;
+        let i = if self.closed {
+            i.rem_euclid(n)
+        } else {
+            i.clamp(0, n - 1)
+        };
+        self.points[i as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn control_points() -> Vec<Vec3> {
+        vec![
+            Vec3::new(0., 0., 0.),
+            Vec3::new(10., 2., 0.5),
+            Vec3::new(14., 12., 1.),
+            Vec3::new(4., 15., 0.),
+            Vec3::new(-3., 6., -0.5),
+        ]
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_its_control_points() {
+        let points = control_points();
+        for spline in [CatmullRom::new(points.clone()), CatmullRom::closed(points.clone())] {
+            for (i, point) in points.iter().enumerate() {
+                let sample = spline.sample(i as f32);
+                assert!(sample.distance(*point) < 1e-5, "t = {}: {:?}", i, sample);
+            }
+        }
+
+        // a closed loop comes back to the first point, an open path stops at the last
+        let closed = CatmullRom::closed(points.clone());
+        assert!(closed.sample(closed.max_t()).distance(points[0]) < 1e-5);
+        let open = CatmullRom::new(points.clone());
+        assert!(open.sample(open.max_t() + 1.).distance(points[4]) < 1e-5);
+
+        // every control point is among the resampled points
+        for spline in [open, closed] {
+            let resampled = spline.resample(8);
+            for point in &points {
+                assert!(resampled.iter().any(|sample| sample.distance(*point) < 1e-5));
+            }
+        }
+    }
+}
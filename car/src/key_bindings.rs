@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+
+// Keyboard keys for every car action, so conflicting keys can be remapped
+// by inserting a different KeyBindings resource instead of editing the systems.
+// The camera keys (parent cycle, camera toggle, screenshot) live in the cameras
+// crate as CameraKeyBindings.
+#[derive(Resource, Clone, Debug)]
+pub struct KeyBindings {
+    // driving
+    pub throttle: KeyCode,
+    pub brake: KeyCode,
+    pub steer_left: KeyCode,
+    pub steer_right: KeyCode,
+    pub handbrake: KeyCode,
+    pub toggle_abs: KeyCode,
+    pub headlights: KeyCode, // auto -> low -> high -> off
+    pub toggle_steering_assist: KeyCode,
+    pub refuel: KeyCode,
+    pub replace_tires: KeyCode,
+    pub reset_car: KeyCode,
+    pub add_spawn_point: KeyCode, // at the last line-draw click
+    pub respawn: KeyCode,
+    // suspension tuning
+    pub stiffer_springs: KeyCode,
+    pub softer_springs: KeyCode,
+    pub more_damping: KeyCode,
+    pub less_damping: KeyCode,
+    // control source / autonomy
+    pub cycle_control_source: KeyCode, // user -> external -> autonomous
+    pub follow_drawn_path: KeyCode, // use the line-draw points as the autonomous path
+    // simulation
+    pub pause: KeyCode,
+    pub step: KeyCode, // advances one physics step while paused
+    pub slower: KeyCode,
+    pub faster: KeyCode,
+    // environment
+    pub cycle_weather: KeyCode,
+    pub rain_heavier: KeyCode, // while it rains
+    pub rain_lighter: KeyCode,
+    pub toggle_time_of_day: KeyCode, // day/night cycle on / off
+    pub scrub_time: KeyCode,         // hold to fast forward, with Shift to rewind
+    // HUD
+    pub toggle_speed_unit: KeyCode, // MPH / KPH
+    pub menu: KeyCode,              // settings menu (Escape quits the app)
+    pub help: KeyCode,              // key binding overlay
+    // orbit camera
+    pub focus_car: KeyCode,
+    pub fit_terrain: KeyCode,
+    pub camera_above_terrain: KeyCode,
+    // terrain editor
+    pub pick_terrain: KeyCode,
+    pub terrain_palette: KeyCode,
+    pub place_terrain: KeyCode,
+    pub remove_terrain: KeyCode,
+    pub rotate_terrain: KeyCode,
+    pub mirror_terrain: KeyCode,
+    pub export_trajectory: KeyCode,
+    // line drawing
+    pub toggle_line_draw: KeyCode,
+    pub line_reset: KeyCode,
+    pub line_undo: KeyCode, // used together with Ctrl
+    pub line_close: KeyCode, // back to the first point
+    pub line_save: KeyCode,
+    pub line_load: KeyCode,
+    pub line_color: KeyCode,
+    pub line_narrower: KeyCode,
+    pub line_wider: KeyCode,
+    pub lane_narrower: KeyCode, // gap between the two lines
+    pub lane_wider: KeyCode,
+    // lap timing
+    pub set_finish_line: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            throttle: KeyCode::W,
+            brake: KeyCode::S,
+            steer_left: KeyCode::A,
+            steer_right: KeyCode::D,
+            handbrake: KeyCode::Space,
+            toggle_abs: KeyCode::B,
+            headlights: KeyCode::Semicolon,
+            toggle_steering_assist: KeyCode::H,
+            refuel: KeyCode::F,
+            replace_tires: KeyCode::G,
+            reset_car: KeyCode::Back,
+            add_spawn_point: KeyCode::J,
+            respawn: KeyCode::N,
+            stiffer_springs: KeyCode::Home,
+            softer_springs: KeyCode::End,
+            more_damping: KeyCode::PageUp,
+            less_damping: KeyCode::PageDown,
+            cycle_control_source: KeyCode::O,
+            follow_drawn_path: KeyCode::U,
+            pause: KeyCode::K,
+            step: KeyCode::Period,
+            slower: KeyCode::Minus,
+            faster: KeyCode::Equals,
+            cycle_weather: KeyCode::P,
+            rain_heavier: KeyCode::E,
+            rain_lighter: KeyCode::Q,
+            toggle_time_of_day: KeyCode::X,
+            scrub_time: KeyCode::Y,
+            toggle_speed_unit: KeyCode::M,
+            menu: KeyCode::Tab,
+            help: KeyCode::F1,
+            focus_car: KeyCode::F3,
+            fit_terrain: KeyCode::F4,
+            camera_above_terrain: KeyCode::F2,
+            pick_terrain: KeyCode::F7,
+            terrain_palette: KeyCode::F8,
+            place_terrain: KeyCode::Insert,
+            remove_terrain: KeyCode::Delete,
+            rotate_terrain: KeyCode::Comma,
+            mirror_terrain: KeyCode::Apostrophe,
+            export_trajectory: KeyCode::F10,
+            toggle_line_draw: KeyCode::T,
+            line_reset: KeyCode::R,
+            line_undo: KeyCode::Z,
+            line_close: KeyCode::Return,
+            line_save: KeyCode::F5,
+            line_load: KeyCode::F9,
+            line_color: KeyCode::I,
+            line_narrower: KeyCode::BracketLeft,
+            line_wider: KeyCode::BracketRight,
+            lane_narrower: KeyCode::Key9,
+            lane_wider: KeyCode::Key0,
+            set_finish_line: KeyCode::L,
+        }
+    }
+}
+
+impl KeyBindings {
+    // (action, key) for every binding, in the order they are listed above
+    pub fn entries(&self) -> Vec<(&'static str, KeyCode)> {
+        vec![
+            ("Throttle", self.throttle),
+            ("Brake / reverse", self.brake),
+            ("Steer left", self.steer_left),
+            ("Steer right", self.steer_right),
+            ("Handbrake", self.handbrake),
+            ("Toggle ABS", self.toggle_abs),
+            ("Headlights", self.headlights),
+            ("Toggle steering assist", self.toggle_steering_assist),
+            ("Refuel", self.refuel),
+            ("Replace tires", self.replace_tires),
+            ("Reset car", self.reset_car),
+            ("Add spawn point", self.add_spawn_point),
+            ("Respawn", self.respawn),
+            ("Stiffer springs", self.stiffer_springs),
+            ("Softer springs", self.softer_springs),
+            ("More damping", self.more_damping),
+            ("Less damping", self.less_damping),
+            ("Cycle control source", self.cycle_control_source),
+            ("Follow drawn path", self.follow_drawn_path),
+            ("Pause", self.pause),
+            ("Step (paused)", self.step),
+            ("Slower", self.slower),
+            ("Faster", self.faster),
+            ("Cycle weather", self.cycle_weather),
+            ("Heavier rain", self.rain_heavier),
+            ("Lighter rain", self.rain_lighter),
+            ("Day/night cycle", self.toggle_time_of_day),
+            ("Scrub time (Shift: back)", self.scrub_time),
+            ("MPH / KPH", self.toggle_speed_unit),
+            ("Settings menu", self.menu),
+            ("Help", self.help),
+            ("Focus camera on car", self.focus_car),
+            ("Fit terrain in view", self.fit_terrain),
+            ("Camera above terrain", self.camera_above_terrain),
+            ("Pick terrain cell", self.pick_terrain),
+            ("Next terrain piece", self.terrain_palette),
+            ("Place terrain piece", self.place_terrain),
+            ("Flatten terrain cell", self.remove_terrain),
+            ("Rotate terrain piece", self.rotate_terrain),
+            ("Mirror terrain piece", self.mirror_terrain),
+            ("Export trajectory", self.export_trajectory),
+            ("Toggle line draw", self.toggle_line_draw),
+            ("Clear line", self.line_reset),
+            ("Undo line point (Ctrl)", self.line_undo),
+            ("Close line loop", self.line_close),
+            ("Save line", self.line_save),
+            ("Load line", self.line_load),
+            ("Line color", self.line_color),
+            ("Line narrower", self.line_narrower),
+            ("Line wider", self.line_wider),
+            ("Lane narrower", self.lane_narrower),
+            ("Lane wider", self.lane_wider),
+            ("Set finish line", self.set_finish_line),
+        ]
+    }
+}
@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use bevy_integrator::SimTime;
+use rigid_body::joint::Joint;
+
+use crate::{key_bindings::KeyBindings, line_draw::LineDrawState};
+
+// Lap timing against a finish line on the ground plane (x, y in absolute coordinates).
+// - finish_start / finish_end: the two ends of the finish line segment
+// - lap_start: sim time of the last forward crossing (None until the first crossing)
+// - last_lap / best_lap: lap times in seconds
+// - laps: number of completed laps
+//
+// "Forward" is the left-hand side of the finish line, looking from finish_start to finish_end.
+// The default line crosses the start straight so driving in +x starts the first lap.
+#[derive(Resource)]
+pub struct LapTimer {
+    pub finish_start: Vec2,
+    pub finish_end: Vec2,
+    pub lap_start: Option<f64>,
+    pub current_lap: f64,
+    pub last_lap: Option<f64>,
+    pub best_lap: Option<f64>,
+    pub laps: usize,
+    previous_position: Option<Vec2>,
+}
+
+impl Default for LapTimer {
+    fn default() -> Self {
+        Self::new(Vec2::new(0., 25.), Vec2::new(0., 15.))
+    }
+}
+
+impl LapTimer {
+    pub fn new(finish_start: Vec2, finish_end: Vec2) -> Self {
+        Self {
+            finish_start,
+            finish_end,
+            lap_start: None,
+            current_lap: 0.,
+            last_lap: None,
+            best_lap: None,
+            laps: 0,
+            previous_position: None,
+        }
+    }
+
+    // Moves the finish line and clears all lap times
+    pub fn set_finish_line(&mut self, finish_start: Vec2, finish_end: Vec2) {
+        *self = Self::new(finish_start, finish_end);
+    }
+
+    // Signed distance-like value: positive on the forward side of the line
+    fn side(&self, point: Vec2) -> f32 {
+        (self.finish_end - self.finish_start).perp_dot(point - self.finish_start)
+    }
+
+    // True if moving from p0 to p1 crosses the finish line segment going forward
+    fn crossed_forward(&self, p0: Vec2, p1: Vec2) -> bool {
+        let s0 = self.side(p0);
+        let s1 = self.side(p1);
+        if !(s0 < 0. && s1 >= 0.) {
+            return false;
+        }
+        // the crossing point must lie between the two ends of the line
+        let crossing = p0 + (p1 - p0) * (s0 / (s0 - s1));
+        let line = self.finish_end - self.finish_start;
+        let along = (crossing - self.finish_start).dot(line) / line.length_squared();
+        (0. ..=1.).contains(&along)
+    }
+
+    // Records a forward crossing at sim time `time`
+    fn complete_lap(&mut self, time: f64) {
+        if let Some(lap_start) = self.lap_start {
+            let lap = time - lap_start;
+            self.laps += 1;
+            self.last_lap = Some(lap);
+            if self.best_lap.map_or(true, |best| lap < best) {
+                self.best_lap = Some(lap);
+            }
+            println!("Lap {}: {:.3} s", self.laps, lap);
+        }
+        self.lap_start = Some(time);
+        self.current_lap = 0.;
+    }
+}
+
+// Half the length of the finish line placed across the start of a closed drawn path (m)
+const LOOP_FINISH_HALF_WIDTH: f32 = 5.0;
+
+// Finish line across the start of a closed path, crossed forward when driving the path
+// in the order it was drawn
+fn loop_finish_line(points: &[Vec3]) -> Option<(Vec2, Vec2)> {
+    let [first, second, ..] = points else { return None; };
+    let left = (second.truncate() - first.truncate()).normalize_or_zero().perp();
+    let start = first.truncate();
+    Some((
+        start + left * LOOP_FINISH_HALF_WIDTH,
+        start - left * LOOP_FINISH_HALF_WIDTH,
+    ))
+}
+
+// Tracks the chassis position and times laps on every forward crossing of the finish line.
+// Press 'L' (KeyBindings::set_finish_line) to move the finish line onto the last two points
+// clicked with the line-draw tool, or across the start of the path once it is closed.
+pub fn lap_timer_system(
+    joints: Query<&Joint>,
+    sim_time: Res<SimTime>,
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    line_draw_state: Res<LineDrawState>,
+    mut lap_timer: ResMut<LapTimer>,
+) {
+    if keyboard_input.just_pressed(keys.set_finish_line) {
+        let loop_line = if line_draw_state.closed {
+            loop_finish_line(line_draw_state.loop_points())
+        } else {
+            None
+        };
+        if let Some((a, b)) = loop_line {
+            lap_timer.set_finish_line(a, b);
+            println!("Finish line set across the start of the loop, from {:?} to {:?}", a, b);
+        } else if let [.., a, b] = line_draw_state.points.as_slice() {
+            lap_timer.set_finish_line(a.truncate(), b.truncate());
+            println!("Finish line set from {:?} to {:?}", a.truncate(), b.truncate());
+        } else {
+            println!("Draw a line (two clicks) to place the finish line");
+        }
+    }
+
+    let mut position = Vec2::ZERO;
+    for joint in joints.iter() {
+        match joint.name.as_str() {
+            "chassis_px" => position.x = joint.q as f32,
+            "chassis_py" => position.y = joint.q as f32,
+            _ => {}
+        }
+    }
+
+    let time = sim_time.time();
+    if let Some(previous_position) = lap_timer.previous_position {
+        if lap_timer.crossed_forward(previous_position, position) {
+            lap_timer.complete_lap(time);
+        }
+    }
+    lap_timer.previous_position = Some(position);
+
+    if let Some(lap_start) = lap_timer.lap_start {
+        lap_timer.current_lap = time - lap_start;
+    }
+}
@@ -1,12 +1,27 @@
+pub mod audio;
+pub mod autonomous_control;
 pub mod build;
+pub mod camera_focus;
 pub mod control;
 pub mod environment;
+pub mod ghost;
+pub mod headlights;
 pub mod interpolate;
+pub mod key_bindings;
+pub mod lap_timer;
+pub mod menu;
+pub mod minimap;
 pub mod mesh;
+pub mod obstacle;
 pub mod physics;
+pub mod reset;
 pub mod setup;
+pub mod skid_marks;
+pub mod terrain_picking;
 pub mod tire;
+pub mod trajectory;
 pub mod ui;
+pub mod vehicle_state;
 pub mod weather;
 pub mod line_draw;
 pub mod logger;             // Added the logger module
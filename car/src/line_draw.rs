@@ -1,14 +1,60 @@
 use bevy::prelude::*;             
 use grid_terrain::GridTerrain; 
+use serde::{Deserialize, Serialize};
+use std::fs;
 
 use cameras::camera_az_el::{AzElCamera, PointerOverUi};
 
+use crate::{environment::ContactConfig, key_bindings::KeyBindings};
+
 // A resource that tracks whether the user is in "line-draw mode" (`enabled`),
 // along with the last 3D point (`last_point`) we clicked.
+// `points` holds every clicked point of the current path and `segments` holds the
+// entities spawned for each click, so the most recent click can be undone.
+// `segment_styles` holds the style each segment was drawn with, segments are redrawn
+// when the corner at one of their ends changes.
+// `path_length` is the length of the path measured along the terrain (in meters).
+// `closed` is set once the path loops back to its first point: a click within
+// `close_distance` (m) of the first point snaps onto it, or 'Enter' (KeyBindings::line_close)
+// draws the last segment back to it. The last point of a closed path is then a copy of the
+// first one (see `loop_points`) and further clicks are ignored until the closing click is
+// undone or the path is cleared.
+// This is the only line-draw state in the workspace, the cameras crate doesn't draw lines.
 #[derive(Resource)]
 pub struct LineDrawState {
+    pub enabled: bool,
     pub last_point: Option<Vec3>,
-    pub color: LineColor, 
+    pub points: Vec<Vec3>,
+    pub segments: Vec<Vec<Entity>>,
+    pub segment_styles: Vec<LineStyle>,
+    pub path_length: f32,
+    pub closed: bool,
+    pub close_distance: f32,
+}
+
+// A path needs this many points before it can be closed into a loop
+const MIN_LOOP_POINTS: usize = 3;
+
+impl LineDrawState {
+    // Points of the path, without the repeated first point when the path is closed
+    pub fn loop_points(&self) -> &[Vec3] {
+        match (self.closed, self.points.split_last()) {
+            (true, Some((_, points))) => points,
+            _ => &self.points,
+        }
+    }
+
+    // True if a click at `point` would close the path
+    fn closes_loop(&self, point: Vec3) -> bool {
+        match self.points.first() {
+            Some(first) => {
+                !self.closed
+                    && self.points.len() >= MIN_LOOP_POINTS
+                    && point.distance(*first) <= self.close_distance
+            }
+            None => false,
+        }
+    }
 }
 
 // Line colors for the line segments
@@ -17,6 +63,7 @@ pub struct LineDrawState {
 #[derive(Clone, Copy)]
 pub enum LineColor {
     White,
+    Yellow,
     Cyan,
     Magenta,
 }
@@ -27,7 +74,8 @@ pub enum LineColor {
 impl LineColor {
     fn next(self) -> Self {
         match self {
-            LineColor::White   => LineColor::Cyan,
+            LineColor::White   => LineColor::Yellow,
+            LineColor::Yellow  => LineColor::Cyan,
             LineColor::Cyan    => LineColor::Magenta,
             LineColor::Magenta => LineColor::White,
         }
@@ -36,22 +84,123 @@ impl LineColor {
     fn to_color(self) -> Color {
         match self {
             LineColor::White => Color::WHITE,
+            LineColor::Yellow  => Color::rgb(1.0, 0.85, 0.0),
             LineColor::Cyan    => Color::rgb(0.0, 1.0, 1.0),
             LineColor::Magenta => Color::rgb(1.0, 0.0, 1.0),
         }
     }
 }
 
+// How new line segments look.
+// - color: the material color of the segments
+// - width: thickness of each line
+// - lane_width: distance between the two parallel lines, centered on the clicked path (m).
+//   It scales with the width when the line is widened or narrowed, and can be changed on its
+//   own with '9' / '0' (KeyBindings::lane_narrower / lane_wider)
+// - max_segment_length: lines are split into pieces no longer than this so they can follow
+//   the terrain, the same density for short and long lines (m)
+// - palette: the palette entry last selected with 'I' (used for the HUD label)
+#[derive(Resource, Clone)]
+pub struct LineStyle {
+    pub color: Color,
+    pub width: f32,
+    pub lane_width: f32,
+    pub max_segment_length: f32,
+    pub palette: LineColor,
+}
+
+const MIN_LINE_WIDTH: f32 = 0.05;
+const MAX_LINE_WIDTH: f32 = 1.0;
+const LINE_WIDTH_STEP: f32 = 0.05;
+
+// Ratio of the parallel line gap to the line width (2.0 gap for the default 0.2 width)
+const LINE_GAP_PER_WIDTH: f32 = 10.0;
+const MIN_LANE_WIDTH: f32 = 0.5;
+const MAX_LANE_WIDTH: f32 = 20.0;
+const LANE_WIDTH_STEP: f32 = 0.25;
+
+// Longest miter at a lane corner, as a multiple of the offset distance. Sharper outer
+// corners are beveled and sharper inner corners are cut short.
+const MITER_LIMIT: f32 = 2.0;
+
+impl Default for LineStyle {
+    fn default() -> Self {
+        let width = 0.2;
+        Self {
+            color: LineColor::White.to_color(), // Starting color for line segments
+            width,
+            lane_width: width * LINE_GAP_PER_WIDTH,
+            max_segment_length: 0.2,
+            palette: LineColor::White,
+        }
+    }
+}
+
+impl LineStyle {
+    // Sets the line width (within its limits) and scales the lane width with it
+    pub fn set_width(&mut self, width: f32) {
+        let width = width.clamp(MIN_LINE_WIDTH, MAX_LINE_WIDTH);
+        self.lane_width =
+            (self.lane_width * width / self.width).clamp(MIN_LANE_WIDTH, MAX_LANE_WIDTH);
+        self.width = width;
+    }
+}
 
 impl Default for LineDrawState {
     fn default() -> Self {
         Self {
+            enabled: true,
             last_point: None,
-            color: LineColor::White, // Starting color for line segments
+            points: Vec::new(),
+            segments: Vec::new(),
+            segment_styles: Vec::new(),
+            path_length: 0.0,
+            closed: false,
+            close_distance: 2.0,
+        }
+    }
+}
+
+// Secondary unit used when reporting the path length (meters are always shown)
+// - label: unit name shown on the HUD
+// - per_meter: how many of this unit make up one meter
+#[derive(Resource)]
+pub struct PathLengthUnit {
+    pub label: String,
+    pub per_meter: f32,
+}
+
+impl Default for PathLengthUnit {
+    fn default() -> Self {
+        Self {
+            label: "ft".to_string(),
+            per_meter: 3.28084,
+        }
+    }
+}
+
+// The file used to save and load the clicked points of a drawn path
+#[derive(Resource)]
+pub struct LinePathFile {
+    pub file_name: String,
+}
+
+impl Default for LinePathFile {
+    fn default() -> Self {
+        Self {
+            file_name: "line_path.json".to_string(),
         }
     }
 }
 
+// On-disk format of a drawn path (Vec3 isn't serializable without bevy's serialize feature)
+#[derive(Serialize, Deserialize)]
+struct SavedPath {
+    points: Vec<[f32; 3]>,
+    #[serde(default)]
+    closed: bool,
+}
+
 // A system that responds to user clicks (left mouse button) when in line-draw mode.
 // 
 // 1) A ray from the camera is casted into the 3D world to find where it hits the terrain.
@@ -62,6 +211,7 @@ pub fn line_draw_system(
     windows: Query<&Window>,                              // Query for the primary window
     mouse: Res<Input<MouseButton>>,                      // Tracks mouse button presses
     keyboard: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,                              // Remappable keys for the line tool
     camera_query: Query<(&Camera, &GlobalTransform), With<AzElCamera>>, 
     mut line_draw_state: ResMut<LineDrawState>,          // Our resource controlling line-draw mode
     mut commands: Commands,                              // For spawning 3D objects
@@ -69,22 +219,109 @@ pub fn line_draw_system(
     mut meshes: ResMut<Assets<Mesh>>,                    // Asset storage for Mesh objects
     mut materials: ResMut<Assets<StandardMaterial>>,      // Asset storage for Material objects
     grid_terrain: Res<GridTerrain>,                      // The terrain resource for collision
+    contact: Res<ContactConfig>,                         // Surface offsets shared with the skid marks
+    path_file: Res<LinePathFile>,                        // Where drawn paths are saved/loaded
+    mut line_style: ResMut<LineStyle>,                   // Color and width of new segments
 ) {
 
-    // Check if user presses 'R' to reset line drawing
-    if keyboard.just_pressed(KeyCode::R) {
-        line_draw_state.last_point = None;
+    // Toggle line-draw mode with 'T'; while it is off, clicks don't place points
+    if keyboard.just_pressed(keys.toggle_line_draw) {
+        line_draw_state.enabled = !line_draw_state.enabled;
+        println!("Line draw {}", if line_draw_state.enabled { "enabled" } else { "disabled" });
+    }
+
+    // Check if user presses 'R' to reset line drawing, removing the drawn segments
+    if keyboard.just_pressed(keys.line_reset) {
+        clear_path(&mut commands, &mut line_draw_state);
+        return;
+    }
+
+    // Close the path with 'Enter': draw the last segment back to the first point
+    if keyboard.just_pressed(keys.line_close) {
+        let first = line_draw_state.points.first().copied();
+        match first {
+            _ if line_draw_state.closed => println!("The path is already closed"),
+            Some(first) if line_draw_state.points.len() >= MIN_LOOP_POINTS => {
+                add_path_point(
+                    first,
+                    &mut line_draw_state,
+                    &line_style,
+                    &mut commands,
+                    &grid_terrain,
+                    &contact,
+                    &mut meshes,
+                    &mut materials,
+                );
+                line_draw_state.closed = true;
+                print_loop_closed(&line_draw_state);
+            }
+            _ => println!("Click at least {} points to close the path", MIN_LOOP_POINTS),
+        }
+        return;
+    }
+
+    // Check if user presses 'Ctrl+Z' to undo the last click
+    let ctrl = keyboard.pressed(KeyCode::ControlLeft) || keyboard.pressed(KeyCode::ControlRight);
+    if ctrl && keyboard.just_pressed(keys.line_undo) {
+        undo_last_click(
+            &mut commands,
+            &mut line_draw_state,
+            &grid_terrain,
+            &contact,
+            &mut meshes,
+            &mut materials,
+        );
         return;
     }
     
+    // Save the clicked points with 'F5', load them back with 'F9'
+    if keyboard.just_pressed(keys.line_save) {
+        if let Err(e) = save_path(&line_draw_state, &path_file.file_name) {
+            println!("Failed to save path to {}: {}", path_file.file_name, e);
+        }
+    }
+    if keyboard.just_pressed(keys.line_load) {
+        if let Err(e) = load_path(
+            &path_file.file_name,
+            &mut line_draw_state,
+            &line_style,
+            &mut commands,
+            &grid_terrain,
+            &contact,
+            &mut meshes,
+            &mut materials,
+        ) {
+            println!("Failed to load path from {}: {}", path_file.file_name, e);
+        }
+        return;
+    }
+
     // Cycle through a few hardcoded colors
-    if keyboard.just_pressed(KeyCode::I) {
-        line_draw_state.color = line_draw_state.color.next();
+    if keyboard.just_pressed(keys.line_color) {
+        line_style.palette = line_style.palette.next();
+        line_style.color = line_style.palette.to_color();
+    }
+
+    // Narrow / widen the line with '[' and ']', the lane widens with it
+    if keyboard.just_pressed(keys.line_narrower) {
+        let width = line_style.width - LINE_WIDTH_STEP;
+        line_style.set_width(width);
+    }
+    if keyboard.just_pressed(keys.line_wider) {
+        let width = line_style.width + LINE_WIDTH_STEP;
+        line_style.set_width(width);
+    }
+    // Narrow / widen the lane alone with '9' and '0'
+    if keyboard.just_pressed(keys.lane_narrower) {
+        line_style.lane_width = (line_style.lane_width - LANE_WIDTH_STEP).max(MIN_LANE_WIDTH);
+    }
+    if keyboard.just_pressed(keys.lane_wider) {
+        line_style.lane_width = (line_style.lane_width + LANE_WIDTH_STEP).min(MAX_LANE_WIDTH);
     }
     
 
-    // If pointer is over UI, do nothing
-    if pointer_over_ui.check() {
+    // If line-draw mode is off or the pointer is over UI, do nothing
+    if !line_draw_state.enabled || pointer_over_ui.check() {
         return;
     }
 
@@ -93,41 +330,254 @@ pub fn line_draw_system(
     // Attempt to get the camera entity (with AzElCamera) and its transform
     let Ok((camera, camera_transform)) = camera_query.get_single() else { return; };
 
-    // On LEFT mouse click, we start a raycast
+    // On RIGHT mouse click, we start a raycast
     if mouse.just_pressed(MouseButton::Right) {
         // Grab the 2D cursor position in window space
         if let Some(cursor_pos) = window.cursor_position() {
             // Convert from 2D cursor position -> a ray (origin, direction) in 3D
             if let Some((origin, dir)) = screen_to_world_ray(cursor_pos, window, camera, camera_transform) {
-                if let Some(world_pos) = raycast_terrain(origin, dir, 200.0, 0.05, &grid_terrain) {
-                    // If we had a previous point, create a line from that old point to the new one
-                    if let Some(prev_point) = line_draw_state.last_point {
-                        let color = line_draw_state.color.to_color();
-                        spawn_line_hugging_terrain(
-                            &mut commands,
-                            prev_point,
-                            world_pos,
-                            &grid_terrain,
-                            &mut meshes,
-                            &mut materials,
-                            color,
-                        );
+                if let Some(mut world_pos) = raycast_terrain(origin, dir, 200.0, &grid_terrain, &contact) {
+                    if line_draw_state.closed {
+                        println!("The path is closed, undo the last click or clear the line to keep drawing");
+                        return;
+                    }
+                    // A click near the first point snaps onto it and closes the loop
+                    let closes = line_draw_state.closes_loop(world_pos);
+                    if closes {
+                        world_pos = line_draw_state.points[0];
+                    }
+                    add_path_point(
+                        world_pos,
+                        &mut line_draw_state,
+                        &line_style,
+                        &mut commands,
+                        &grid_terrain,
+                        &contact,
+                        &mut meshes,
+                        &mut materials,
+                    );
+                    if closes {
+                        line_draw_state.closed = true;
+                        print_loop_closed(&line_draw_state);
                     }
-                    // Store this new point for future line segments
-                    line_draw_state.last_point = Some(world_pos);
                 }
             }
         }
     }
 }
 
+// Adds a point to the path. If there was a previous point, a line from that old point
+// to the new one is drawn and the path length grows by it. The segment before it is
+// redrawn to join the new corner, and so is the first segment when the point closes the loop.
+fn add_path_point(
+    point: Vec3,
+    line_draw_state: &mut LineDrawState,
+    style: &LineStyle,
+    commands: &mut Commands,
+    terrain: &GridTerrain,
+    contact: &ContactConfig,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+) {
+    let prev_point = line_draw_state.last_point;
+    // Store this new point for future line segments
+    line_draw_state.last_point = Some(point);
+    line_draw_state.points.push(point);
+    let Some(prev_point) = prev_point else { return; };
+
+    let new_segment = line_draw_state.points.len() - 2;
+    if new_segment > 0 {
+        redraw_path_segment(new_segment - 1, line_draw_state, commands, terrain, contact, meshes, materials);
+    }
+    draw_path_segment(new_segment, line_draw_state, style, commands, terrain, contact, meshes, materials);
+    if new_segment > 0 && is_loop(&line_draw_state.points) {
+        redraw_path_segment(0, line_draw_state, commands, terrain, contact, meshes, materials);
+    }
+    line_draw_state.path_length +=
+        terrain_path_length(prev_point, point, terrain, contact, style.max_segment_length);
+}
+
+// Draws the two lane lines of the path segment from `points[index]` to `points[index + 1]`,
+// replacing the lines it had if it was drawn before.
+fn draw_path_segment(
+    index: usize,
+    line_draw_state: &mut LineDrawState,
+    style: &LineStyle,
+    commands: &mut Commands,
+    terrain: &GridTerrain,
+    contact: &ContactConfig,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+) {
+    let corners = offset_corners(&line_draw_state.points, 0.5 * style.lane_width);
+    let (left, right) = segment_boundaries(&corners, index);
+    let mut entities = spawn_line_hugging_terrain(commands, &left, terrain, contact, meshes, materials, style);
+    entities.extend(spawn_line_hugging_terrain(commands, &right, terrain, contact, meshes, materials, style));
+
+    if let Some(old_entities) = line_draw_state.segments.get_mut(index) {
+        for entity in std::mem::replace(old_entities, entities) {
+            if let Some(entity_commands) = commands.get_entity(entity) {
+                entity_commands.despawn_recursive();
+            }
+        }
+        line_draw_state.segment_styles[index] = style.clone();
+    } else {
+        line_draw_state.segments.push(entities);
+        line_draw_state.segment_styles.push(style.clone());
+    }
+}
+
+// Draws an existing segment again with the style it was drawn with
+fn redraw_path_segment(
+    index: usize,
+    line_draw_state: &mut LineDrawState,
+    commands: &mut Commands,
+    terrain: &GridTerrain,
+    contact: &ContactConfig,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+) {
+    if let Some(style) = line_draw_state.segment_styles.get(index).cloned() {
+        draw_path_segment(index, line_draw_state, &style, commands, terrain, contact, meshes, materials);
+    }
+}
+
+fn print_loop_closed(line_draw_state: &LineDrawState) {
+    println!(
+        "Loop closed: {} points, {:.1} m",
+        line_draw_state.loop_points().len(),
+        line_draw_state.path_length
+    );
+}
+
+// Removes the most recent click: despawns the segments it created and moves
+// `last_point` back to the click before it.
+// The first click of a path has no segments, undoing it just forgets the point.
+// Undoing the click that closed the path opens it again.
+fn undo_last_click(
+    commands: &mut Commands,
+    line_draw_state: &mut LineDrawState,
+    terrain: &GridTerrain,
+    contact: &ContactConfig,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+) {
+    if line_draw_state.points.is_empty() {
+        println!("Nothing to undo");
+        return;
+    }
+
+    // every click after the first one spawned a group of segments
+    let num_points = line_draw_state.points.len();
+    if num_points > 1 {
+        // measured the same way as when the segment was added
+        let max_segment_length = line_draw_state
+            .segment_styles
+            .last()
+            .map_or(LineStyle::default().max_segment_length, |style| style.max_segment_length);
+        let removed_length = terrain_path_length(
+            line_draw_state.points[num_points - 2],
+            line_draw_state.points[num_points - 1],
+            terrain,
+            contact,
+            max_segment_length,
+        );
+        line_draw_state.path_length = (line_draw_state.path_length - removed_length).max(0.0);
+
+        if let Some(segment_entities) = line_draw_state.segments.pop() {
+            for entity in segment_entities {
+                if let Some(entity_commands) = commands.get_entity(entity) {
+                    entity_commands.despawn_recursive();
+                }
+            }
+        }
+        line_draw_state.segment_styles.pop();
+    }
+
+    let was_loop = is_loop(&line_draw_state.points);
+    line_draw_state.points.pop();
+    line_draw_state.last_point = line_draw_state.points.last().copied();
+    line_draw_state.closed = false;
+
+    // the segment before the removed one (and the first one if the loop was opened)
+    // now end without a corner
+    if let Some(last_segment) = line_draw_state.segments.len().checked_sub(1) {
+        redraw_path_segment(last_segment, line_draw_state, commands, terrain, contact, meshes, materials);
+        if was_loop && last_segment > 0 {
+            redraw_path_segment(0, line_draw_state, commands, terrain, contact, meshes, materials);
+        }
+    }
+}
+
+// Writes the clicked points of the current path to a JSON file.
+// A closed path is saved as a loop (its last point repeats the first one).
+pub fn save_path(line_draw_state: &LineDrawState, file_name: &str) -> std::io::Result<()> {
+    let saved = SavedPath {
+        points: line_draw_state.points.iter().map(|p| p.to_array()).collect(),
+        closed: line_draw_state.closed,
+    };
+    let json = serde_json::to_string_pretty(&saved)?;
+    fs::write(file_name, json)?;
+    println!("Saved {} path points to {}", saved.points.len(), file_name);
+    Ok(())
+}
+
+// Despawns every drawn segment and forgets the path
+fn clear_path(commands: &mut Commands, line_draw_state: &mut LineDrawState) {
+    for segment_entities in line_draw_state.segments.drain(..) {
+        for entity in segment_entities {
+            if let Some(entity_commands) = commands.get_entity(entity) {
+                entity_commands.despawn_recursive();
+            }
+        }
+    }
+    line_draw_state.segment_styles.clear();
+    line_draw_state.last_point = None;
+    line_draw_state.points.clear();
+    line_draw_state.path_length = 0.0;
+    line_draw_state.closed = false;
+}
+
+// Reads clicked points from a JSON file and redraws the path over the terrain.
+// Any segments of the current path are removed first.
+pub fn load_path(
+    file_name: &str,
+    line_draw_state: &mut LineDrawState,
+    style: &LineStyle,
+    commands: &mut Commands,
+    terrain: &GridTerrain,
+    contact: &ContactConfig,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+) -> std::io::Result<()> {
+    let json = fs::read_to_string(file_name)?;
+    let saved: SavedPath = serde_json::from_str(&json)?;
+    let points: Vec<Vec3> = saved.points.iter().map(|p| Vec3::from_array(*p)).collect();
+
+    clear_path(commands, line_draw_state);
+    line_draw_state.last_point = points.last().copied();
+    line_draw_state.points = points;
+    line_draw_state.closed = saved.closed;
+
+    // redraw each pair of clicks
+    for index in 0..line_draw_state.points.len().saturating_sub(1) {
+        draw_path_segment(index, line_draw_state, style, commands, terrain, contact, meshes, materials);
+        let (p1, p2) = (line_draw_state.points[index], line_draw_state.points[index + 1]);
+        line_draw_state.path_length +=
+            terrain_path_length(p1, p2, terrain, contact, style.max_segment_length);
+    }
+
+    println!("Loaded {} path points from {}", line_draw_state.points.len(), file_name);
+    Ok(())
+}
+
 // Converts a 2D screen coordinate into a ray in world space.
 // 
 // - `camera_transform`: The camera's current position & rotation in the world
 // 
 // Returns `None` if the projection matrix cannot be inverted or if we can't determine
 // a valid world position.
-fn screen_to_world_ray(
+pub fn screen_to_world_ray(
     screen_pos: Vec2,
     window: &Window,
     camera: &Camera,
@@ -161,15 +611,15 @@ fn screen_to_world_ray(
 }
 
 // Move along a ray from `origin` in `dir` increments of `step_size`, up to `max_dist`.
-// Calls `terrain.interference_f32(...)` to see if there's a collision at each step.
+// Calls `terrain.interference(...)` to see if there's a collision at each step.
 // If collision is found, we compute the collision point and move it up a little
 // to avoid z-fighting, returning it as our final intersection point.
 fn raycast_terrain(
     origin: Vec3,
     dir: Vec3,
     max_dist: f32,
-    step_size: f32,
     terrain: &GridTerrain,
+    contact: &ContactConfig,
 ) -> Option<Vec3> {
     let mut dist = 0.0;
     while dist < max_dist {
@@ -185,7 +635,7 @@ fn raycast_terrain(
 
         // Ask the terrain if there's a collision at that point
         if let Some(inter) = terrain.interference(test_point_vector) {
-            let offset = 0.01; // nudge above the surface by 1 cm
+            let offset = contact.surface_offset; // nudge above the surface
             let collision_point = test_point
                 + Vec3::new(
                     inter.magnitude as f32 * inter.normal.x as f32,
@@ -204,76 +654,183 @@ fn raycast_terrain(
         }
 
         // Advance the ray by step_size and keep checking
-        dist += step_size;
+        dist += contact.ray_step;
     }
     // No collision found within max_dist
     None
 }
 
-/// Subdivides the line from `p1` to `p2` into segments so each piece can follow
-/// the terrain's ups and downs. Each sub-segment is individually "snapped" onto the terrain
-/// so the entire line sticks to the ground or slopes/bumps.
-fn spawn_line_hugging_terrain(
-    commands: &mut Commands,
-    p1: Vec3,
-    p2: Vec3,
-    terrain: &GridTerrain,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<StandardMaterial>>,
-    color: Color,
-) {
-    // We subdivide into this many small line pieces
-    let total_subdiv = 100;
-    let segment = p2 - p1;
+// True if the path ends where it started (a closed loop)
+fn is_loop(points: &[Vec3]) -> bool {
+    points.len() > 2 && points.first() == points.last()
+}
 
-    // Define how far apart the parallel lines
-    let offset_distance = 2.0; 
+// Left and right lane boundaries of a centerline, `distance` to each side in the ground
+// (x, y) plane. The z of the centerline is kept, the lines are snapped to the terrain
+// when they are drawn.
+// Corners are mitered so both boundaries stay parallel to the centerline through the turn
+// without crossing themselves. Outer corners sharper than MITER_LIMIT get a bevel (two
+// points) and inner ones are cut at the limit. A centerline that ends on its first point
+// is a loop, its start is joined like any other corner.
+pub fn offset_path(points: &[Vec3], distance: f32) -> (Vec<Vec3>, Vec<Vec3>) {
+    let corners = offset_corners(points, distance);
+    let left = corners.iter().flat_map(|corner| corner.left.iter().copied()).collect();
+    let right = corners.iter().flat_map(|corner| corner.right.iter().copied()).collect();
+    (left, right)
+}
 
-    let mut last_left = None;
-    let mut last_right = None;
+// Boundary points at one centerline point, one (miter) or two (bevel) on each side
+struct OffsetCorner {
+    left: Vec<Vec3>,
+    right: Vec<Vec3>,
+}
 
-    for i in 0..=total_subdiv {
-        let t = i as f32 / total_subdiv as f32;
-        let rough = p1 + segment * t;
+fn offset_corners(points: &[Vec3], distance: f32) -> Vec<OffsetCorner> {
+    let direction = |from: Vec3, to: Vec3| (to - from).truncate().normalize_or_zero();
+    let closed = is_loop(points);
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let incoming = match i {
+                0 if closed => Some(direction(points[n - 2], points[0])),
+                0 => None,
+                _ => Some(direction(points[i - 1], points[i])),
+            };
+            let outgoing = match i {
+                _ if i + 1 < n => Some(direction(points[i], points[i + 1])),
+                _ if closed => Some(direction(points[0], points[1])),
+                _ => None,
+            };
+            offset_corner(points[i], incoming, outgoing, distance)
+        })
+        .collect()
+}
 
-        // Compute perpendicular vector based on previous segment to avoid misalignment
-        let segment_dir = (p2 - p1).normalize();
-        let perpendicular = Vec3::new(-segment_dir.y, segment_dir.x, 0.0).normalize();
+fn offset_corner(point: Vec3, incoming: Option<Vec2>, outgoing: Option<Vec2>, distance: f32) -> OffsetCorner {
+    // the path ends and zero length segments just continue the other direction
+    let (d0, d1) = match (incoming, outgoing) {
+        (Some(d0), Some(d1)) if d0 == Vec2::ZERO => (d1, d1),
+        (Some(d0), Some(d1)) if d1 == Vec2::ZERO => (d0, d0),
+        (Some(d0), Some(d1)) => (d0, d1),
+        (Some(d), None) | (None, Some(d)) => (d, d),
+        (None, None) => (Vec2::ZERO, Vec2::ZERO),
+    };
+    // positive when turning left, the left side is then the inside of the corner
+    let turn = d0.perp_dot(d1);
+    OffsetCorner {
+        left: corner_side(point, d0.perp(), d1.perp(), distance, turn > 0.0),
+        right: corner_side(point, -d0.perp(), -d1.perp(), distance, turn < 0.0),
+    }
+}
 
-        let rough_left = rough + perpendicular * (offset_distance * 0.5);
-        let rough_right = rough - perpendicular * (offset_distance * 0.5);
+// Offset points on one side of a corner, `n0` and `n1` are the unit normals towards that
+// side of the incoming and outgoing segments
+fn corner_side(point: Vec3, n0: Vec2, n1: Vec2, distance: f32, inner: bool) -> Vec<Vec3> {
+    let at = |offset: Vec2| point + offset.extend(0.0);
+    let bisector = (n0 + n1).normalize_or_zero();
+    // cosine of half the turn: 1 going straight, 0 for a U-turn
+    let cos_half = bisector.dot(n0);
+    if cos_half * MITER_LIMIT >= 1.0 {
+        vec![at(bisector * (distance / cos_half))]
+    } else if inner {
+        vec![at(bisector * (distance * MITER_LIMIT))]
+    } else {
+        vec![at(n0 * distance), at(n1 * distance)]
+    }
+}
 
-        // Snap both approximate points onto the terrain
-        if let Some(surf_left) = snap_point_to_terrain(rough_left, terrain) {
-            if let Some(prev_left) = last_left {
-                spawn_line_segment(commands, prev_left, surf_left, meshes, materials, color);
+// Left and right boundary of the segment from centerline point `index` to `index + 1`.
+// A bevel belongs to the segment that ends at it.
+fn segment_boundaries(corners: &[OffsetCorner], index: usize) -> (Vec<Vec3>, Vec<Vec3>) {
+    let (start, end) = (&corners[index], &corners[index + 1]);
+    let side = |start: &[Vec3], end: &[Vec3]| start.last().into_iter().chain(end).copied().collect();
+    (side(&start.left, &end.left), side(&start.right, &end.right))
+}
+
+/// Subdivides the polyline `boundary` into pieces of at most `style.max_segment_length`
+/// so each piece can follow the terrain's ups and downs. Each sub-segment is
+/// individually "snapped" onto the terrain so the entire line sticks to the ground or slopes/bumps.
+/// Returns the spawned segment entities.
+fn spawn_line_hugging_terrain(
+    commands: &mut Commands,
+    boundary: &[Vec3],
+    terrain: &GridTerrain,
+    contact: &ContactConfig,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    style: &LineStyle,
+) -> Vec<Entity> {
+    let mut entities = Vec::new();
+    let mut last_surf = None;
+    for (edge, pair) in boundary.windows(2).enumerate() {
+        let (p1, p2) = (pair[0], pair[1]);
+        let pieces = subdivisions((p2 - p1).length(), style.max_segment_length);
+        // the first point of every edge after the first is the end of the edge before it
+        let first = if edge == 0 { 0 } else { 1 };
+        for i in first..=pieces {
+            let rough = p1.lerp(p2, i as f32 / pieces as f32);
+            // Snap the approximate point onto the terrain
+            if let Some(surf) = snap_point_to_terrain(rough, terrain, contact) {
+                if let Some(prev) = last_surf {
+                    entities.extend(spawn_line_segment(commands, prev, surf, meshes, materials, style.color, style.width));
+                }
+                last_surf = Some(surf);
             }
-            last_left = Some(surf_left);
         }
+    }
+    entities
+}
+
+// Number of pieces a line of `length` is split into, none longer than `max_segment_length`
+// (at least one)
+pub fn subdivisions(length: f32, max_segment_length: f32) -> usize {
+    if max_segment_length <= 0.0 {
+        return 1;
+    }
+    ((length / max_segment_length).ceil() as usize).max(1)
+}
 
-        if let Some(surf_right) = snap_point_to_terrain(rough_right, terrain) {
-            if let Some(prev_right) = last_right {
-                spawn_line_segment(commands, prev_right, surf_right, meshes, materials, color);
+// Length of the line from `p1` to `p2` measured along the terrain surface.
+// The centerline is subdivided the same way as the drawn lines and each piece is snapped
+// to the terrain, so hills and steps add to the length.
+fn terrain_path_length(
+    p1: Vec3,
+    p2: Vec3,
+    terrain: &GridTerrain,
+    contact: &ContactConfig,
+    max_segment_length: f32,
+) -> f32 {
+    let segment = p2 - p1;
+    let pieces = subdivisions(segment.length(), max_segment_length);
+    let mut length = 0.0;
+    let mut last_point: Option<Vec3> = None;
+    for i in 0..=pieces {
+        let t = i as f32 / pieces as f32;
+        let rough = p1 + segment * t;
+        if let Some(surf) = snap_point_to_terrain(rough, terrain, contact) {
+            if let Some(prev) = last_point {
+                length += (surf - prev).length();
             }
-            last_right = Some(surf_right);
+            last_point = Some(surf);
         }
     }
+    length
 }
 
 // Given an approximate point in the air (`rough`), cast a small ray downward
 // so we can pin it exactly to the terrain surface. 
 //
-/// This is done by calling `raycast_terrain` from 2 units above to 10 units below.
-fn snap_point_to_terrain(
+/// This is done by calling `raycast_terrain` from `snap_above` above to `snap_below` below.
+pub fn snap_point_to_terrain(
     rough: Vec3,
     terrain: &GridTerrain,
+    contact: &ContactConfig,
 ) -> Option<Vec3> {
-    let above = rough + Vec3::new(0., 0., 2.0);
+    let above = rough + Vec3::new(0., 0., contact.snap_above);
     let dir_down = Vec3::new(0., 0., -1.);
-    let max_dist = 10.0;
-    let step = 0.05;
+    let max_dist = contact.snap_above + contact.snap_below;
     // If there's a collision, we get a snapped point
-    raycast_terrain(above, dir_down, max_dist, step, terrain)
+    raycast_terrain(above, dir_down, max_dist, terrain, contact)
 }
 
 // Spawns a single rectangular "line segment" between p1 and p2.
@@ -284,12 +841,13 @@ fn spawn_line_segment(
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
     color: Color,
-) {
+    thickness: f32,
+) -> Option<Entity> {
     let segment = p2 - p1;
     let length = segment.length();
     // If there's no length, skip
     if length < f32::EPSILON {
-        return;
+        return None;
     }
 
     // Midpoint for translation
@@ -298,18 +856,17 @@ fn spawn_line_segment(
     let angle_xy = segment.y.atan2(segment.x);
     let rotation = Quat::from_rotation_z(angle_xy);
 
-    // A small thickness for the line, and a Z scale of 0.02 for a slightly raised effect
-    let thickness = 0.2; 
+    // The line thickness comes from the style, and a Z scale of 0.02 for a slightly raised effect
     // We use a unit shape::Box, then scale it to length × thickness × 0.02
     let mesh_handle = meshes.add(Mesh::from(shape::Box::new(1.0, 1.0, 0.02)));
 
-    // Simple white material so the line is visible
+    // Simple material in the selected color so the line is visible
     let material_handle = materials.add(StandardMaterial {
         base_color: color,
         ..default()
     });
 
-    commands.spawn(PbrBundle {
+    let entity = commands.spawn(PbrBundle {
         transform: Transform {
             translation: mid,                          // The center in world coords
             rotation,                                  // Rotates box so it lines up with p1->p2
@@ -319,4 +876,180 @@ fn spawn_line_segment(
         material: material_handle,
         ..default()
     });
+    Some(entity.id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::{CommandQueue, SystemState};
+    use std::f32::consts::PI;
+
+    #[test]
+    fn clearing_the_path_despawns_its_segments() {
+        let mut world = World::new();
+        let parent = world.spawn_empty().id();
+        let child = world.spawn_empty().id();
+        world.entity_mut(parent).push_children(&[child]);
+        let other = world.spawn_empty().id();
+
+        let mut state = LineDrawState {
+            last_point: Some(Vec3::X),
+            points: vec![Vec3::ZERO, Vec3::X],
+            segments: vec![vec![parent], vec![other]],
+            segment_styles: vec![LineStyle::default(), LineStyle::default()],
+            path_length: 1.0,
+            closed: true,
+            ..default()
+        };
+
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        clear_path(&mut commands, &mut state);
+        queue.apply(&mut world);
+
+        for entity in [parent, child, other] {
+            assert!(world.get_entity(entity).is_none());
+        }
+        assert!(state.segments.is_empty() && state.segment_styles.is_empty());
+        assert!(state.points.is_empty() && state.last_point.is_none());
+        assert_eq!(state.path_length, 0.0);
+        assert!(!state.closed);
+    }
+
+
+    type LineParams<'w, 's> = (
+        Commands<'w, 's>,
+        ResMut<'w, Assets<Mesh>>,
+        ResMut<'w, Assets<StandardMaterial>>,
+    );
+
+    #[test]
+    fn line_pieces_scale_with_length() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_asset::<Mesh>()
+            .add_asset::<StandardMaterial>();
+        let terrain = GridTerrain::new(vec![], [1., 1.]);
+        let contact = ContactConfig::default();
+        let style = LineStyle::default();
+
+        let mut spawn_line = |length: f32| {
+            let mut state: SystemState<LineParams> = SystemState::new(&mut app.world);
+            let (mut commands, mut meshes, mut materials) = state.get_mut(&mut app.world);
+            let boundary = [Vec3::new(0., 1., 0.), Vec3::new(length, 1., 0.)];
+            let entities = spawn_line_hugging_terrain(
+                &mut commands,
+                &boundary,
+                &terrain,
+                &contact,
+                &mut meshes,
+                &mut materials,
+                &style,
+            );
+            state.apply(&mut app.world);
+            for &entity in &entities {
+                let transform = app.world.get::<Transform>(entity).unwrap();
+                assert!(transform.scale.x <= style.max_segment_length + 1e-4);
+            }
+            entities.len()
+        };
+
+        // the pieces are the same length on both lines, so 50 times as many on the long one
+        let short = spawn_line(1.);
+        let long = spawn_line(50.);
+        assert_eq!(short, subdivisions(1., style.max_segment_length));
+        assert_eq!(short, 5);
+        assert_eq!(long, 50 * short);
+    }
+
+
+    // The line tool with a camera 5 m above the ground. The camera's projection is only
+    // computed by the renderer, so it stays the identity here: clicks cast rays along
+    // the camera's +z, which the camera's half turn about x points at the ground.
+    fn line_draw_app(state: LineDrawState) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_asset::<Mesh>()
+            .add_asset::<StandardMaterial>()
+            .insert_resource(state)
+            .insert_resource(GridTerrain::new(vec![], [1., 1.]))
+            .insert_resource(PointerOverUi::new())
+            .init_resource::<Input<MouseButton>>()
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<ContactConfig>()
+            .init_resource::<LinePathFile>()
+            .init_resource::<LineStyle>()
+            .add_systems(Update, line_draw_system);
+        app.world.spawn(Window::default());
+        app.world.spawn((
+            Camera::default(),
+            GlobalTransform::from(
+                Transform::from_xyz(0., 0., 5.).with_rotation(Quat::from_rotation_x(PI)),
+            ),
+            AzElCamera::default(),
+        ));
+        app
+    }
+
+    // Right click at `cursor` (pixels)
+    fn click(app: &mut App, cursor: Vec2) {
+        let mut windows = app.world.query::<&mut Window>();
+        windows.single_mut(&mut app.world).set_cursor_position(Some(cursor));
+        app.world.resource_mut::<Input<MouseButton>>().press(MouseButton::Right);
+        app.update();
+        let mut mouse = app.world.resource_mut::<Input<MouseButton>>();
+        mouse.release(MouseButton::Right);
+        mouse.clear();
+    }
+
+    fn segment_count(app: &mut App) -> usize {
+        app.world.query::<&Handle<Mesh>>().iter(&app.world).count()
+    }
+
+    #[test]
+    fn clicks_only_draw_while_enabled() {
+        let state = LineDrawState::default();
+        assert!(state.enabled);
+        assert!(state.points.is_empty() && state.segments.is_empty());
+
+        let (center, right) = (Vec2::new(640., 360.), Vec2::new(960., 360.));
+        let mut app = line_draw_app(LineDrawState {
+            enabled: false,
+            ..default()
+        });
+        click(&mut app, center);
+        click(&mut app, right);
+        assert!(app.world.resource::<LineDrawState>().points.is_empty());
+        assert_eq!(segment_count(&mut app), 0);
+
+        // the same clicks with the tool on draw a 2.5 m segment
+        app.world.resource_mut::<LineDrawState>().enabled = true;
+        click(&mut app, center);
+        click(&mut app, right);
+        let state = app.world.resource::<LineDrawState>();
+        assert_eq!(state.points.len(), 2);
+        assert!((state.path_length - 2.5).abs() < 0.05, "{}", state.path_length);
+        assert!(segment_count(&mut app) > 0);
+    }
+
+
+    #[test]
+    fn lane_width_scales_with_the_line_width() {
+        let mut style = LineStyle::default();
+        assert_eq!(style.lane_width, style.width * LINE_GAP_PER_WIDTH);
+
+        style.set_width(0.4);
+        assert!((style.lane_width - 4.0).abs() < 1e-5);
+        // a lane set on its own keeps its own ratio to the width
+        style.lane_width = 3.0;
+        style.set_width(0.2);
+        assert!((style.lane_width - 1.5).abs() < 1e-5);
+        // both stay within their limits
+        style.set_width(10.0);
+        assert_eq!(style.width, MAX_LINE_WIDTH);
+        style.set_width(0.0);
+        assert_eq!((style.width, style.lane_width), (MIN_LINE_WIDTH, MIN_LANE_WIDTH));
+    }
 }
@@ -1,14 +1,38 @@
-use bevy::prelude::*;
-use std::fs::OpenOptions;
-use std::io::Write;
+use bevy::{app::AppExit, prelude::*};
+use bevy_integrator::SimTime;
+use cameras::camera_az_el::AzElCamera;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::time::{Duration, Instant};
 use serde::{Serialize, Deserialize};
 use chrono::Local;
 
 use crate::control::CarControl;
 
+// Logs control presses and releases as JSON lines.
+// Every event carries the simulation time and physics step, so it can be lined up with the
+// simulated state and is the same from run to run; `wall_clock` adds the local date and
+// time as extra metadata.
+// The file stays open behind a buffer that is flushed every `flush_interval` and when the
+// app exits. With `max_file_size` set, a full log file is rotated: it is renamed to
+// `<log_file>.1` (older ones move up to `.2`, `.3`, ...; the oldest past `max_rotated_files`
+// is deleted) and a new file is started.
+// With `record_camera` the orbit camera pose is logged every `camera_interval` of sim time
+// (as "camera" events), so CameraReplay can frame a replay the same way. Off by default
+// to keep logs small.
 #[derive(Resource)]
 pub struct InputLogger {
+    pub wall_clock: bool,
+    pub record_camera: bool,
+    pub camera_interval: f64, // s
+    pub flush_interval: Duration,
+    pub max_file_size: Option<u64>, // bytes
+    pub max_rotated_files: usize,
     log_file: String,
+    writer: Option<BufWriter<File>>,
+    file_size: u64, // bytes in the current file, including buffered ones
+    last_flush: Instant,
+    last_camera_sample: Option<f64>, // sim time
     last_throttle: f32,
     last_brake: f32,
     last_steering: f32,
@@ -19,16 +43,71 @@ pub struct InputLogger {
 
 #[derive(Serialize, Deserialize)]
 struct LogEvent {
-    time: String,
+    sim_time: f64, // s
+    step: usize,   // physics step index
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time: Option<String>, // wall clock, only with InputLogger::wall_clock
     control_type: String,
     event_type: String,  // Added to distinguish press/release
     value: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    camera: Option<CameraPose>, // only on "camera" events
+}
+
+// Orbit camera state (see AzElCamera)
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct CameraPose {
+    pub focus: [f32; 3],
+    pub azimuth: f32,
+    pub elevation: f32,
+    pub radius: f32,
+}
+
+impl CameraPose {
+    pub fn from_camera(camera: &AzElCamera) -> Self {
+        Self {
+            focus: camera.focus.to_array(),
+            azimuth: camera.azimuth,
+            elevation: camera.elevation,
+            radius: camera.radius,
+        }
+    }
+
+    // Linear blend from self (s = 0) to other (s = 1)
+    fn lerp(&self, other: &CameraPose, s: f32) -> Self {
+        let blend = |a: f32, b: f32| a + (b - a) * s;
+        Self {
+            focus: Vec3::from_array(self.focus)
+                .lerp(Vec3::from_array(other.focus), s)
+                .to_array(),
+            azimuth: blend(self.azimuth, other.azimuth),
+            elevation: blend(self.elevation, other.elevation),
+            radius: blend(self.radius, other.radius),
+        }
+    }
+
+    fn apply(&self, camera: &mut AzElCamera) {
+        camera.focus = Vec3::from_array(self.focus);
+        camera.azimuth = self.azimuth;
+        camera.elevation = self.elevation;
+        camera.radius = self.radius;
+    }
 }
 
 impl Default for InputLogger {
     fn default() -> Self {
         Self {
+            wall_clock: true,
+            record_camera: false,
+            camera_interval: 0.1,
+            flush_interval: Duration::from_secs(1),
+            max_file_size: None,
+            max_rotated_files: 3,
             log_file: "car_inputs.log".to_string(),
+            writer: None,
+            file_size: 0,
+            last_flush: Instant::now(),
+            last_camera_sample: None,
             last_throttle: 0.0,
             last_brake: 0.0,
             last_steering: 0.0,
@@ -48,8 +127,77 @@ impl InputLogger {
         }
     }
 
-    pub fn log_event(&self, control_type: &str, event_type: &str, value: f32) {
-        let time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    // Rotates the log file once it reaches `max_file_size` bytes, keeping `max_rotated_files`
+    pub fn with_rotation(mut self, max_file_size: u64, max_rotated_files: usize) -> Self {
+        self.max_file_size = Some(max_file_size);
+        self.max_rotated_files = max_rotated_files;
+        self
+    }
+
+    // Writes the buffered events to the file
+    pub fn flush(&mut self) {
+        if let Some(writer) = self.writer.as_mut() {
+            if let Err(e) = writer.flush() {
+                println!("Failed to flush log file: {}", e);
+            }
+        }
+        self.last_flush = Instant::now();
+    }
+
+    // Opens the log file for appending (once), continuing its size count
+    fn writer(&mut self) -> io::Result<&mut BufWriter<File>> {
+        if self.writer.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.log_file)?;
+            self.file_size = file.metadata()?.len();
+            self.writer = Some(BufWriter::new(file));
+        }
+        Ok(self.writer.as_mut().unwrap())
+    }
+
+    // Closes the current file and shifts it and the older ones up by one number
+    fn rotate(&mut self) -> io::Result<()> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+        }
+        let rotated = |index: usize| format!("{}.{}", self.log_file, index);
+        if self.max_rotated_files == 0 {
+            fs::remove_file(&self.log_file)?;
+        } else {
+            let oldest = rotated(self.max_rotated_files);
+            if fs::metadata(&oldest).is_ok() {
+                fs::remove_file(&oldest)?;
+            }
+            for index in (1..self.max_rotated_files).rev() {
+                if fs::metadata(rotated(index)).is_ok() {
+                    fs::rename(rotated(index), rotated(index + 1))?;
+                }
+            }
+            fs::rename(&self.log_file, rotated(1))?;
+        }
+        self.file_size = 0;
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let length = line.len() as u64 + 1;
+        self.writer()?; // opens the file, so file_size includes what an earlier run wrote
+        if let Some(max_file_size) = self.max_file_size {
+            if self.file_size > 0 && self.file_size + length > max_file_size {
+                self.rotate()?;
+            }
+        }
+        writeln!(self.writer()?, "{}", line)?;
+        self.file_size += length;
+        Ok(())
+    }
+
+    pub fn log_event(&mut self, sim_time: &SimTime, control_type: &str, event_type: &str, value: f32) {
+        let time = self
+            .wall_clock
+            .then(|| Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
         
         // Convert value to percentage and round to 3 decimal places
         let percentage_value = if control_type == "steering" {
@@ -59,21 +207,37 @@ impl InputLogger {
         };
 
         let event = LogEvent {
+            sim_time: sim_time.time(),
+            step: sim_time.index,
             time,
             control_type: control_type.to_string(),
             event_type: event_type.to_string(),
             value: percentage_value,
+            camera: None,
         };
+        self.write_event(&event);
+    }
 
-        if let Ok(event_json) = serde_json::to_string(&event) {
-            if let Ok(mut file) = OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&self.log_file) 
-            {
-                if let Err(e) = writeln!(file, "{}", event_json) {
-                    println!("Failed to write to log file: {}", e);
-                }
+    pub fn log_camera(&mut self, sim_time: &SimTime, camera: &AzElCamera) {
+        let time = self
+            .wall_clock
+            .then(|| Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+        let event = LogEvent {
+            sim_time: sim_time.time(),
+            step: sim_time.index,
+            time,
+            control_type: "camera".to_string(),
+            event_type: "sample".to_string(),
+            value: 0.,
+            camera: Some(CameraPose::from_camera(camera)),
+        };
+        self.write_event(&event);
+    }
+
+    fn write_event(&mut self, event: &LogEvent) {
+        if let Ok(event_json) = serde_json::to_string(event) {
+            if let Err(e) = self.write_line(&event_json) {
+                println!("Failed to write to log file: {}", e);
             }
         }
     }
@@ -82,34 +246,190 @@ impl InputLogger {
 pub fn input_logger_system(
     mut logger: ResMut<InputLogger>,
     control: Res<CarControl>,
+    sim_time: Res<SimTime>,
 ) {
+    let logger = logger.as_mut();
     // Throttle Press and Release
     if control.throttle > 0.01 && !logger.throttle_pressed {
-        logger.log_event("throttle", "press", control.throttle);
+        logger.log_event(&sim_time, "throttle", "press", control.throttle);
         logger.throttle_pressed = true;
     } else if control.throttle <= 0.01 && logger.throttle_pressed {
-        logger.log_event("throttle", "release", logger.last_throttle);
+        logger.log_event(&sim_time, "throttle", "release", logger.last_throttle);
         logger.throttle_pressed = false;
     }
     logger.last_throttle = control.throttle;
 
     // Brake Press and Release
     if control.brake > 0.01 && !logger.brake_pressed {
-        logger.log_event("brake", "press", control.brake);
+        logger.log_event(&sim_time, "brake", "press", control.brake);
         logger.brake_pressed = true;
     } else if control.brake <= 0.01 && logger.brake_pressed {
-        logger.log_event("brake", "release", logger.last_brake);
+        logger.log_event(&sim_time, "brake", "release", logger.last_brake);
         logger.brake_pressed = false;
     }
     logger.last_brake = control.brake;
 
     // Steering Press and Release
     if control.steering.abs() > 0.01 && !logger.steering_pressed {
-        logger.log_event("steering", "press", control.steering);
+        logger.log_event(&sim_time, "steering", "press", control.steering);
         logger.steering_pressed = true;
     } else if control.steering.abs() <= 0.01 && logger.steering_pressed {
-        logger.log_event("steering", "release", logger.last_steering);
+        logger.log_event(&sim_time, "steering", "release", logger.last_steering);
         logger.steering_pressed = false;
     }
     logger.last_steering = control.steering;
-}
\ No newline at end of file
+
+    if logger.last_flush.elapsed() >= logger.flush_interval {
+        logger.flush();
+    }
+}
+
+// Flushes the log when the app is closing (runs in Last, after the exit was requested)
+pub fn input_logger_exit_system(mut logger: ResMut<InputLogger>, exit: EventReader<AppExit>) {
+    if !exit.is_empty() {
+        logger.flush();
+    }
+}
+
+// Logs the orbit camera pose every `camera_interval` of sim time while `record_camera` is on
+pub fn camera_logger_system(
+    mut logger: ResMut<InputLogger>,
+    sim_time: Res<SimTime>,
+    cameras: Query<&AzElCamera>,
+) {
+    if !logger.record_camera {
+        return;
+    }
+    let time = sim_time.time();
+    // a reset sim clock (time going backwards) starts sampling again
+    let due = logger
+        .last_camera_sample
+        .map_or(true, |last| time - last >= logger.camera_interval || time < last);
+    if !due {
+        return;
+    }
+    if let Ok(camera) = cameras.get_single() {
+        logger.log_camera(&sim_time, camera);
+        logger.last_camera_sample = Some(time);
+    }
+}
+
+// Drives the orbit camera from the "camera" events of a log recorded with
+// InputLogger::record_camera, interpolating between samples by sim time
+// - enabled: replay on / off (the mouse controls the camera while off)
+#[derive(Resource)]
+pub struct CameraReplay {
+    pub enabled: bool,
+    poses: Vec<(f64, CameraPose)>, // (sim time, pose), in time order
+}
+
+impl CameraReplay {
+    // Reads the camera samples of a log file; other events are skipped
+    pub fn load(log_file: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(log_file)?;
+        let mut poses: Vec<(f64, CameraPose)> = text
+            .lines()
+            .filter_map(|line| serde_json::from_str::<LogEvent>(line).ok())
+            .filter_map(|event| Some((event.sim_time, event.camera?)))
+            .collect();
+        poses.sort_by(|a, b| a.0.total_cmp(&b.0));
+        println!("Loaded {} camera samples from {}", poses.len(), log_file);
+        Ok(Self {
+            enabled: true,
+            poses,
+        })
+    }
+
+    // Camera pose at sim time `time` (held at the first / last sample outside the log)
+    pub fn pose(&self, time: f64) -> Option<CameraPose> {
+        let index = self.poses.partition_point(|(t, _)| *t <= time);
+        if index == 0 {
+            return self.poses.first().map(|(_, pose)| *pose);
+        }
+        if index == self.poses.len() {
+            return self.poses.last().map(|(_, pose)| *pose);
+        }
+        let (t0, pose0) = &self.poses[index - 1];
+        let (t1, pose1) = &self.poses[index];
+        Some(pose0.lerp(pose1, ((time - t0) / (t1 - t0)) as f32))
+    }
+}
+
+pub fn camera_replay_system(
+    replay: Option<Res<CameraReplay>>,
+    sim_time: Res<SimTime>,
+    mut cameras: Query<(&mut AzElCamera, &mut Transform)>,
+) {
+    let Some(replay) = replay else {
+        return;
+    };
+    if !replay.enabled {
+        return;
+    }
+    let Some(pose) = replay.pose(sim_time.time()) else {
+        return;
+    };
+    for (mut camera, mut transform) in cameras.iter_mut() {
+        pose.apply(&mut camera);
+        *transform = camera.transform();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Values of the events in a log file, oldest first (empty if there is no such file)
+    fn logged_values(file: &str) -> Vec<f32> {
+        fs::read_to_string(file)
+            .unwrap_or_default()
+            .lines()
+            .map(|line| serde_json::from_str::<LogEvent>(line).unwrap().value)
+            .collect()
+    }
+
+    #[test]
+    fn full_log_files_are_rotated() {
+        let directory =
+            std::env::temp_dir().join(format!("logger_rotation_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let log_file = directory.join("inputs.log").to_string_lossy().to_string();
+        let sim_time = SimTime::new(0.002, 0., None);
+
+        // every event is one line of the same length, three fit in a file
+        let line = serde_json::to_string(&LogEvent {
+            sim_time: 0.,
+            step: 0,
+            time: None,
+            control_type: "throttle".to_string(),
+            event_type: "press".to_string(),
+            value: 1.,
+            camera: None,
+        })
+        .unwrap();
+        let mut logger = InputLogger {
+            wall_clock: false,
+            ..InputLogger::new(log_file.clone())
+        }
+        .with_rotation(3 * (line.len() as u64 + 1), 2);
+
+        for i in 0..10 {
+            // logged as a percentage, so event i is recorded with value i
+            logger.log_event(&sim_time, "throttle", "press", i as f32 / 100.);
+        }
+        logger.flush();
+
+        // 10 events: the current file has the last one, the two rotated files the three
+        // before each, and the first three went with the file rotated out
+        let rotated = |index: usize| format!("{}.{}", log_file, index);
+        assert_eq!(logged_values(&log_file), vec![9.]);
+        assert_eq!(logged_values(&rotated(1)), vec![6., 7., 8.]);
+        assert_eq!(logged_values(&rotated(2)), vec![3., 4., 5.]);
+        assert!(fs::metadata(rotated(3)).is_err());
+        for file in [log_file.clone(), rotated(1), rotated(2)] {
+            assert!(fs::metadata(&file).unwrap().len() <= 3 * (line.len() as u64 + 1));
+        }
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,336 @@
+use bevy::prelude::*;
+use bevy_integrator::SimControl;
+use cameras::{
+    camera_az_el::AzElCamera,
+    control::{CameraKeyBindings, FirstPersonCamera, SplitScreen},
+};
+
+use crate::{
+    control::{switch_control_source, CarControl, ControlSource, SteeringConfig},
+    key_bindings::KeyBindings,
+    physics::AbsEnabled,
+    weather::{ParticleBudget, SurfaceWetness, Weather},
+};
+
+// Whether the settings menu is open, and whether the simulation was already
+// paused when it opened (so closing the menu doesn't resume a paused simulation)
+#[derive(Resource, Default)]
+pub struct MenuState {
+    pub open: bool,
+    paused_before: bool,
+}
+
+#[derive(Component)]
+pub struct MenuRoot;
+
+// What a menu button changes
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuButton {
+    Weather,
+    ParticleBudget,
+    WetSurface,
+    ControlSource,
+    Abs,
+    SteeringAssist,
+    Camera,
+    Resume,
+}
+
+// Text of a menu button, refreshed from the resource it changes
+#[derive(Component)]
+pub struct MenuLabel(MenuButton);
+
+const BUTTON_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+const BUTTON_HOVER_COLOR: Color = Color::rgb(0.3, 0.3, 0.3);
+
+// Spawns the (hidden) settings menu: a centered column of buttons
+pub fn menu_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            MenuRoot,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(8.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Settings",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+                    for button in [
+                        MenuButton::Weather,
+                        MenuButton::ParticleBudget,
+                        MenuButton::WetSurface,
+                        MenuButton::ControlSource,
+                        MenuButton::Abs,
+                        MenuButton::SteeringAssist,
+                        MenuButton::Camera,
+                        MenuButton::Resume,
+                    ] {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        width: Val::Px(260.0),
+                                        padding: UiRect::all(Val::Px(8.0)),
+                                        ..default()
+                                    },
+                                    background_color: BUTTON_COLOR.into(),
+                                    ..default()
+                                },
+                                button,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    TextBundle::from_section(
+                                        "",
+                                        TextStyle {
+                                            font: font.clone(),
+                                            font_size: 20.0,
+                                            color: Color::GOLD,
+                                        },
+                                    ),
+                                    MenuLabel(button),
+                                ));
+                            });
+                    }
+                });
+        });
+}
+
+// Press Tab (KeyBindings::menu) to open / close the settings menu.
+// The simulation is paused while the menu is open and left as it was on close.
+// (Escape is taken: RigidBodyPlugin quits on it.)
+pub fn menu_toggle_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    mut menu: ResMut<MenuState>,
+    mut sim_control: ResMut<SimControl>,
+    mut roots: Query<&mut Visibility, With<MenuRoot>>,
+) {
+    if keyboard_input.just_pressed(keys.menu) {
+        set_menu_open(!menu.open, &mut menu, &mut sim_control, &mut roots);
+    }
+}
+
+fn set_menu_open(
+    open: bool,
+    menu: &mut MenuState,
+    sim_control: &mut SimControl,
+    roots: &mut Query<&mut Visibility, With<MenuRoot>>,
+) {
+    if open == menu.open {
+        return;
+    }
+    menu.open = open;
+    if open {
+        menu.paused_before = sim_control.paused;
+        sim_control.paused = true;
+    } else {
+        sim_control.paused = menu.paused_before;
+    }
+    for mut visibility in roots.iter_mut() {
+        *visibility = if open {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+pub fn menu_button_system(
+    mut buttons: Query<(&Interaction, &MenuButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut menu: ResMut<MenuState>,
+    mut sim_control: ResMut<SimControl>,
+    mut roots: Query<&mut Visibility, With<MenuRoot>>,
+    mut weather: ResMut<Weather>,
+    mut particle_budget: ResMut<ParticleBudget>,
+    mut wetness: ResMut<SurfaceWetness>,
+    mut source: ResMut<ControlSource>,
+    mut control: ResMut<CarControl>,
+    mut abs: ResMut<AbsEnabled>,
+    mut steering_config: ResMut<SteeringConfig>,
+    mut orbit_cameras: Query<&mut Camera, (With<AzElCamera>, Without<FirstPersonCamera>)>,
+    mut first_person_cameras: Query<&mut Camera, (With<FirstPersonCamera>, Without<AzElCamera>)>,
+    split_screen: Option<Res<SplitScreen>>,
+) {
+    let split = split_screen.map_or(false, |split_screen| split_screen.enabled);
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        match interaction {
+            Interaction::Hovered => *color = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *color = BUTTON_COLOR.into(),
+            Interaction::Pressed => match button {
+                MenuButton::Weather => *weather = weather.next(),
+                MenuButton::ParticleBudget => *particle_budget = particle_budget.next(),
+                MenuButton::WetSurface => wetness.enabled = !wetness.enabled,
+                MenuButton::ControlSource => switch_control_source(&mut source, &mut control),
+                MenuButton::Abs => abs.enabled = !abs.enabled,
+                MenuButton::SteeringAssist => {
+                    steering_config.speed_assist = !steering_config.speed_assist
+                }
+                // both cameras are already showing in split screen
+                MenuButton::Camera if split => {}
+                MenuButton::Camera => {
+                    // same as the camera toggle key: swap the orbit and first-person cameras
+                    for mut camera in orbit_cameras.iter_mut() {
+                        camera.is_active = !camera.is_active;
+                    }
+                    for mut camera in first_person_cameras.iter_mut() {
+                        camera.is_active = !camera.is_active;
+                    }
+                }
+                MenuButton::Resume => {
+                    set_menu_open(false, &mut menu, &mut sim_control, &mut roots)
+                }
+            },
+        }
+    }
+}
+
+pub fn menu_label_system(
+    mut labels: Query<(&mut Text, &MenuLabel)>,
+    menu: Res<MenuState>,
+    weather: Res<Weather>,
+    particle_budget: Res<ParticleBudget>,
+    wetness: Res<SurfaceWetness>,
+    source: Res<ControlSource>,
+    abs: Res<AbsEnabled>,
+    steering_config: Res<SteeringConfig>,
+    first_person_cameras: Query<&Camera, With<FirstPersonCamera>>,
+    split_screen: Option<Res<SplitScreen>>,
+) {
+    if !menu.open {
+        return;
+    }
+    let on_off = |on: bool| if on { "ON" } else { "OFF" };
+    let first_person = first_person_cameras.iter().any(|camera| camera.is_active);
+    let split = split_screen.map_or(false, |split_screen| split_screen.enabled);
+    for (mut text, label) in labels.iter_mut() {
+        text.sections[0].value = match label.0 {
+            MenuButton::Weather => format!("Weather: {:?}", *weather),
+            MenuButton::ParticleBudget => format!("Rain particles: {:?}", *particle_budget),
+            MenuButton::WetSurface => format!("Wet track: {}", on_off(wetness.enabled)),
+            MenuButton::ControlSource => format!("Input: {:?}", *source),
+            MenuButton::Abs => format!("ABS: {}", on_off(abs.enabled)),
+            MenuButton::SteeringAssist => {
+                format!("Steering assist: {}", on_off(steering_config.speed_assist))
+            }
+            MenuButton::Camera if split => "Camera: Split screen".to_string(),
+            MenuButton::Camera => {
+                format!("Camera: {}", if first_person { "First person" } else { "Orbit" })
+            }
+            MenuButton::Resume => "Resume".to_string(),
+        };
+    }
+}
+
+// Whether the key binding overlay is shown
+#[derive(Resource, Default)]
+pub struct HelpState {
+    pub open: bool,
+}
+
+#[derive(Component)]
+pub struct HelpRoot;
+
+#[derive(Component)]
+pub struct HelpText;
+
+// Spawns the (hidden) help overlay: a panel on the right listing every key binding
+pub fn help_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    padding: UiRect::all(Val::Px(12.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            HelpRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font,
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                HelpText,
+            ));
+        });
+}
+
+// Press F1 (KeyBindings::help) to show / hide the key binding overlay
+pub fn help_toggle_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    mut help: ResMut<HelpState>,
+    mut roots: Query<&mut Visibility, With<HelpRoot>>,
+) {
+    if keyboard_input.just_pressed(keys.help) {
+        help.open = !help.open;
+        for mut visibility in roots.iter_mut() {
+            *visibility = if help.open {
+                Visibility::Visible
+            } else {
+                Visibility::Hidden
+            };
+        }
+    }
+}
+
+// Rebuilds the key table whenever the overlay is opened or a binding is remapped
+pub fn update_help_system(
+    mut texts: Query<&mut Text, With<HelpText>>,
+    help: Res<HelpState>,
+    keys: Res<KeyBindings>,
+    camera_keys: Res<CameraKeyBindings>,
+) {
+    if !help.open || !(help.is_changed() || keys.is_changed() || camera_keys.is_changed()) {
+        return;
+    }
+    let mut table = "Key bindings\n".to_string();
+    for (action, key) in keys.entries().into_iter().chain(camera_keys.entries()) {
+        table.push_str(&format!("{:?}: {}\n", key, action));
+    }
+    table.push_str("Escape: Quit");
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = table.clone();
+    }
+}
@@ -0,0 +1,227 @@
+use bevy::prelude::*;
+use grid_terrain::GridTerrain;
+
+use crate::{line_draw::LineDrawState, vehicle_state::VehicleState};
+
+// Top-down map in the bottom right corner: the terrain grid cells in their colors,
+// the line-draw path and the car as an arrow pointing along its heading.
+// - size: width and height of the map in pixels
+// - margin: flat ground shown around the terrain grid (m)
+// The map covers the grid extents plus the margin; the car marker stays on the
+// map edge while the car is outside that area.
+#[derive(Resource)]
+pub struct Minimap {
+    pub size: f32,
+    pub margin: f32,
+    min: Vec2,   // world point at the bottom left corner of the map
+    scale: f32,  // pixels per meter (0 until the terrain is drawn)
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self {
+            size: 180.,
+            margin: 10.,
+            min: Vec2::ZERO,
+            scale: 0.,
+        }
+    }
+}
+
+impl Minimap {
+    // Pixel position (from the top left corner) of a world point, clamped to the map
+    fn to_map(&self, point: Vec2) -> Vec2 {
+        let map = (point - self.min) * self.scale;
+        Vec2::new(map.x, self.size - map.y).clamp(Vec2::ZERO, Vec2::splat(self.size))
+    }
+}
+
+#[derive(Component)]
+pub struct MinimapRoot;
+
+#[derive(Component)]
+pub struct MinimapPathDot;
+
+#[derive(Component)]
+pub struct MinimapCarMarker;
+
+const PATH_DOT_SIZE: f32 = 2.0;
+const PATH_DOT_SPACING: f32 = 3.0; // pixels between dots along the path
+const MARKER_SIZE: f32 = 12.0;
+
+pub fn minimap_setup(mut commands: Commands, minimap: Res<Minimap>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.0),
+                    bottom: Val::Px(10.0),
+                    width: Val::Px(minimap.size),
+                    height: Val::Px(minimap.size),
+                    ..default()
+                },
+                background_color: Color::rgba_u8(140, 120, 100, 200).into(),
+                ..default()
+            },
+            MinimapRoot,
+        ))
+        .with_children(|parent| {
+            // the car: an arrow built from bars that narrow towards the front,
+            // pointing up before rotation
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            width: Val::Px(MARKER_SIZE),
+                            height: Val::Px(MARKER_SIZE),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        // drawn above the terrain cells and the path
+                        z_index: ZIndex::Local(1),
+                        ..default()
+                    },
+                    MinimapCarMarker,
+                ))
+                .with_children(|parent| {
+                    for row in 1..=4 {
+                        parent.spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Px(MARKER_SIZE * row as f32 / 4.0),
+                                height: Val::Px(MARKER_SIZE / 4.0),
+                                ..default()
+                            },
+                            background_color: Color::RED.into(),
+                            ..default()
+                        });
+                    }
+                });
+        });
+}
+
+// Draws the terrain cells once the terrain resource exists (build_environment
+// inserts it with commands, so it isn't there yet during Startup)
+pub fn minimap_terrain_system(
+    mut commands: Commands,
+    terrain: Option<Res<GridTerrain>>,
+    mut minimap: ResMut<Minimap>,
+    roots: Query<Entity, With<MinimapRoot>>,
+) {
+    let Some(terrain) = terrain else {
+        return;
+    };
+    if !terrain.is_added() {
+        return;
+    }
+    let (min, max) = terrain.extents();
+    let margin = Vec2::splat(minimap.margin);
+    let min = Vec2::new(min.x as f32, min.y as f32) - margin;
+    let max = Vec2::new(max.x as f32, max.y as f32) + margin;
+    minimap.min = min;
+    minimap.scale = minimap.size / (max - min).max_element();
+
+    let (columns, rows) = terrain.cell_count();
+    let (_, grid_max) = terrain.extents();
+    let cell = Vec2::new(
+        grid_max.x as f32 / columns.max(1) as f32,
+        grid_max.y as f32 / rows.max(1) as f32,
+    );
+    for root in roots.iter() {
+        commands.entity(root).with_children(|parent| {
+            for row in 0..rows {
+                for column in 0..columns {
+                    let Some(color) = terrain.cell_color(column, row) else {
+                        continue;
+                    };
+                    // top left corner of the cell on the map
+                    let corner = Vec2::new(column as f32 * cell.x, (row + 1) as f32 * cell.y);
+                    let position = minimap.to_map(corner);
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(position.x),
+                            top: Val::Px(position.y),
+                            width: Val::Px(cell.x * minimap.scale),
+                            height: Val::Px(cell.y * minimap.scale),
+                            ..default()
+                        },
+                        background_color: color.into(),
+                        ..default()
+                    });
+                }
+            }
+        });
+    }
+}
+
+// Redraws the line-draw path as a dotted line whenever it changes
+pub fn minimap_path_system(
+    mut commands: Commands,
+    line_draw_state: Res<LineDrawState>,
+    minimap: Res<Minimap>,
+    roots: Query<Entity, With<MinimapRoot>>,
+    dots: Query<Entity, With<MinimapPathDot>>,
+) {
+    if !line_draw_state.is_changed() && !minimap.is_changed() {
+        return;
+    }
+    for dot in dots.iter() {
+        commands.entity(dot).despawn_recursive();
+    }
+    if minimap.scale <= 0. {
+        return;
+    }
+
+    let mut positions = Vec::new();
+    for pair in line_draw_state.points.windows(2) {
+        let a = minimap.to_map(pair[0].truncate());
+        let b = minimap.to_map(pair[1].truncate());
+        let count = ((b - a).length() / PATH_DOT_SPACING).ceil().max(1.) as usize;
+        positions.extend((0..count).map(|i| a.lerp(b, i as f32 / count as f32)));
+    }
+    if let Some(last) = line_draw_state.points.last() {
+        positions.push(minimap.to_map(last.truncate()));
+    }
+
+    for root in roots.iter() {
+        commands.entity(root).with_children(|parent| {
+            for position in positions.iter() {
+                parent.spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(position.x - 0.5 * PATH_DOT_SIZE),
+                            top: Val::Px(position.y - 0.5 * PATH_DOT_SIZE),
+                            width: Val::Px(PATH_DOT_SIZE),
+                            height: Val::Px(PATH_DOT_SIZE),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    },
+                    MinimapPathDot,
+                ));
+            }
+        });
+    }
+}
+
+// Moves the car marker to the car and turns it along the heading.
+// The map has +y up but UI y points down, so a counterclockwise heading is a negative
+// z rotation; the arrow points up (heading 90°) before rotation.
+pub fn minimap_car_system(
+    vehicle_state: Res<VehicleState>,
+    minimap: Res<Minimap>,
+    mut markers: Query<(&mut Style, &mut Transform), With<MinimapCarMarker>>,
+) {
+    let position = minimap.to_map(vehicle_state.position.truncate());
+    for (mut style, mut transform) in markers.iter_mut() {
+        style.left = Val::Px(position.x - 0.5 * MARKER_SIZE);
+        style.top = Val::Px(position.y - 0.5 * MARKER_SIZE);
+        transform.rotation =
+            Quat::from_rotation_z(std::f32::consts::FRAC_PI_2 - vehicle_state.heading);
+    }
+}
@@ -0,0 +1,186 @@
+use bevy::prelude::*;
+use rigid_body::{
+    joint::Joint,
+    sva::{Force, Vector},
+};
+
+// Collision shape of an obstacle, centered on the obstacle's Transform translation
+// - Sphere: radius (m)
+// - Box: half extents (m) along the world axes
+#[derive(Clone, Copy, Debug)]
+pub enum ObstacleShape {
+    Sphere { radius: f32 },
+    Box { half_extents: Vec3 },
+}
+
+// A static object the car body can hit (cones, walls, barriers, ...)
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Obstacle {
+    pub shape: ObstacleShape,
+}
+
+// Contact model between the car body and obstacles (a stiff spring-damper, like the tires)
+// - car_half_extents: half size of the car body box, matches the chassis dimensions in build_car
+// - stiffness: contact force per meter of penetration (N/m)
+// - damping: contact force per m/s of approach speed (N s/m)
+#[derive(Resource, Clone)]
+pub struct ObstacleCollision {
+    pub car_half_extents: Vec3,
+    pub stiffness: f64,
+    pub damping: f64,
+}
+
+impl Default for ObstacleCollision {
+    fn default() -> Self {
+        Self {
+            car_half_extents: Vec3::new(1.5, 0.6, 0.2),
+            stiffness: 2e5,
+            damping: 1e4,
+        }
+    }
+}
+
+// Number of sample points along each side of the car body used against box obstacles
+const BODY_SAMPLES: [usize; 3] = [9, 3, 2];
+
+fn to_vector(v: Vec3) -> Vector {
+    Vector::new(v.x as f64, v.y as f64, v.z as f64)
+}
+
+// Points on the car body box (body coordinates)
+fn body_points(half_extents: Vec3) -> Vec<Vector> {
+    let half = to_vector(half_extents);
+    let mut points = Vec::new();
+    for i in 0..BODY_SAMPLES[0] {
+        for j in 0..BODY_SAMPLES[1] {
+            for k in 0..BODY_SAMPLES[2] {
+                let t = Vector::new(
+                    i as f64 / (BODY_SAMPLES[0] - 1) as f64,
+                    j as f64 / (BODY_SAMPLES[1] - 1) as f64,
+                    k as f64 / (BODY_SAMPLES[2] - 1) as f64,
+                );
+                points.push((2. * t - Vector::new(1., 1., 1.)).component_mul(&half));
+            }
+        }
+    }
+    points
+}
+
+// Pushes the car body out of any obstacle it overlaps.
+// Each contact is a penetration depth, a contact point and a normal (absolute coordinates,
+// pointing from the obstacle toward the car); the spring-damper force is applied to the
+// car body (the chassis roll joint) at the contact point.
+pub fn obstacle_collision_system(
+    mut joints: Query<&mut Joint>,
+    obstacles: Query<(&Obstacle, &Transform)>,
+    collision: Res<ObstacleCollision>,
+) {
+    if obstacles.is_empty() {
+        return;
+    }
+    let Some(mut joint) = joints.iter_mut().find(|joint| joint.name == "chassis_rx") else {
+        return;
+    };
+    let x0i = joint.x.inverse(); // body to absolute coordinates
+    let v0 = x0i * joint.v; // body spatial velocity in absolute coordinates
+    let half = to_vector(collision.car_half_extents);
+
+    let mut contacts: Vec<(f64, Vector, Vector)> = Vec::new();
+    for (obstacle, transform) in obstacles.iter() {
+        let center = to_vector(transform.translation);
+        match obstacle.shape {
+            ObstacleShape::Sphere { radius } => {
+                // closest point of the body box to the sphere center
+                let center_body = joint.x.transform_point(center);
+                let closest_body = Vector::new(
+                    center_body.x.clamp(-half.x, half.x),
+                    center_body.y.clamp(-half.y, half.y),
+                    center_body.z.clamp(-half.z, half.z),
+                );
+                let closest = x0i.transform_point(closest_body);
+                let offset = closest - center;
+                let distance = offset.norm();
+                if distance < radius as f64 && distance > f64::EPSILON {
+                    contacts.push((radius as f64 - distance, closest, offset / distance));
+                }
+            }
+            ObstacleShape::Box { half_extents } => {
+                let box_half = to_vector(half_extents);
+                for point in body_points(collision.car_half_extents) {
+                    let point_abs = x0i.transform_point(point);
+                    let offset = point_abs - center;
+                    // push out along the axis with the least penetration
+                    let mut best: Option<(f64, Vector)> = None;
+                    for axis in 0..3 {
+                        let depth = box_half[axis] - offset[axis].abs();
+                        if depth <= 0. {
+                            best = None;
+                            break;
+                        }
+                        if best.map_or(true, |(best_depth, _)| depth < best_depth) {
+                            let mut normal = Vector::zeros();
+                            normal[axis] = offset[axis].signum();
+                            best = Some((depth, normal));
+                        }
+                    }
+                    if let Some((depth, normal)) = best {
+                        contacts.push((depth, point_abs, normal));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut f_ext = Force::zero();
+    for (depth, point, normal) in contacts {
+        let approach_speed = v0.velocity_point(point).vel.dot(&normal);
+        let force_magnitude =
+            (collision.stiffness * depth - collision.damping * approach_speed).max(0.);
+        f_ext += Force::force_point(force_magnitude * normal, point);
+    }
+    joint.f_ext += f_ext;
+}
+
+// Spawns `count` traffic cones in a line from `start`, `spacing` meters apart along `direction`
+// (the cones stand on z = 0, so place them on flat ground)
+pub fn spawn_cone_row(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    start: Vec3,
+    direction: Vec3,
+    count: usize,
+    spacing: f32,
+) {
+    let height = 0.5;
+    let radius = 0.15;
+    let mesh = meshes.add(Mesh::from(shape::Cylinder {
+        radius,
+        height,
+        ..default()
+    }));
+    let material = materials.add(StandardMaterial {
+        base_color: Color::rgb(1.0, 0.4, 0.0),
+        perceptual_roughness: 0.8,
+        ..default()
+    });
+    let direction = direction.normalize_or_zero();
+    for i in 0..count {
+        let base = start + direction * spacing * i as f32;
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                // the cylinder mesh is along y, stand it up along z
+                transform: Transform::from_translation(base + Vec3::Z * height / 2.)
+                    .with_rotation(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+                ..default()
+            },
+            Obstacle {
+                shape: ObstacleShape::Sphere {
+                    radius: height / 2.,
+                },
+            },
+        ));
+    }
+}
@@ -1,12 +1,19 @@
 use std::collections::HashMap;
 
 use bevy::prelude::*;
+use bevy_integrator::SimTime;
 
-use rigid_body::joint::Joint;
+use rigid_body::{
+    joint::Joint,
+    sva::{Force, Vector},
+};
 
-use crate::interpolate::Interpolator1D;
+use crate::{
+    interpolate::Interpolator1D, key_bindings::KeyBindings, vehicle_state::VehicleState,
+    weather::Weather,
+};
 
-use super::control::CarControl;
+use super::control::{CarControl, SteeringConfig};
 
 #[derive(Component)]
 pub struct SuspensionComponent {
@@ -23,15 +30,91 @@ impl SuspensionComponent {
             preload,
         }
     }
+
+    pub fn set_rates(&mut self, stiffness: f64, damping: f64) {
+        self.stiffness = stiffness;
+        self.damping = damping;
+    }
+
+    // Spring and damper rates after the live tuning in `config`
+    pub fn stiffness(&self, config: &SuspensionConfig) -> f64 {
+        self.stiffness * config.stiffness_scale
+    }
+
+    pub fn damping(&self, config: &SuspensionConfig) -> f64 {
+        self.damping * config.damping_scale
+    }
+}
+
+// Live suspension tuning, applied on top of every suspension's built rates
+// - stiffness_scale: spring rate multiplier
+// - damping_scale: damper rate multiplier
+// - step: change of a scale per key press
+// - min_scale: lower limit of both scales
+#[derive(Resource)]
+pub struct SuspensionConfig {
+    pub stiffness_scale: f64,
+    pub damping_scale: f64,
+    pub step: f64,
+    pub min_scale: f64,
+}
+
+impl Default for SuspensionConfig {
+    fn default() -> Self {
+        Self {
+            stiffness_scale: 1.,
+            damping_scale: 1.,
+            step: 0.1,
+            min_scale: 0.1,
+        }
+    }
 }
 
-pub fn suspension_system(mut joints: Query<(&mut Joint, &SuspensionComponent)>) {
+pub fn suspension_system(
+    mut joints: Query<(&mut Joint, &SuspensionComponent)>,
+    config: Res<SuspensionConfig>,
+) {
     for (mut joint, suspension) in joints.iter_mut() {
-        joint.tau -=
-            suspension.stiffness * joint.q + suspension.damping * joint.qd + suspension.preload;
+        joint.tau -= suspension.stiffness(&config) * joint.q
+            + suspension.damping(&config) * joint.qd
+            + suspension.preload;
     }
 }
 
+// Home / End (KeyBindings::stiffer_springs / softer_springs) change the spring rates,
+// Page Up / Page Down (KeyBindings::more_damping / less_damping) the damper rates
+pub fn suspension_tuning_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    mut config: ResMut<SuspensionConfig>,
+) {
+    let step = config.step;
+    let min_scale = config.min_scale;
+    let stiffness_change = if keyboard_input.just_pressed(keys.stiffer_springs) {
+        step
+    } else if keyboard_input.just_pressed(keys.softer_springs) {
+        -step
+    } else {
+        0.
+    };
+    let damping_change = if keyboard_input.just_pressed(keys.more_damping) {
+        step
+    } else if keyboard_input.just_pressed(keys.less_damping) {
+        -step
+    } else {
+        0.
+    };
+    if stiffness_change == 0. && damping_change == 0. {
+        return;
+    }
+    config.stiffness_scale = (config.stiffness_scale + stiffness_change).max(min_scale);
+    config.damping_scale = (config.damping_scale + damping_change).max(min_scale);
+    println!(
+        "Suspension: springs x{:.1}, dampers x{:.1}",
+        config.stiffness_scale, config.damping_scale
+    );
+}
+
 #[derive(Clone)]
 pub enum SteeringType {
     None,
@@ -50,9 +133,15 @@ impl Steering {
     }
 }
 
-pub fn steering_system(mut joints: Query<(&mut Joint, &Steering)>, control: Res<CarControl>) {
+pub fn steering_system(
+    mut joints: Query<(&mut Joint, &Steering)>,
+    control: Res<CarControl>,
+    config: Res<SteeringConfig>,
+    vehicle_state: Res<VehicleState>,
+) {
+    let command = config.effective(control.steering, vehicle_state.speed) as f64;
     for (mut joint, steering) in joints.iter_mut() {
-        joint.q = control.steering as f64 * steering.max_angle;
+        joint.q = command * steering.max_angle;
     }
 }
 
@@ -76,15 +165,42 @@ impl SteeringCurvature {
 pub fn steering_curvature_system(
     mut joints: Query<(&mut Joint, &SteeringCurvature)>,
     control: Res<CarControl>,
+    config: Res<SteeringConfig>,
+    vehicle_state: Res<VehicleState>,
 ) {
+    let command = config.effective(control.steering, vehicle_state.speed) as f64;
     for (mut joint, steering) in joints.iter_mut() {
-        let vehicle_curvature_target = steering.max_curvature * control.steering as f64;
+        let vehicle_curvature_target = steering.max_curvature * command;
         let wheel_curvature_target =
             vehicle_curvature_target / (1.0 - vehicle_curvature_target * steering.y);
         joint.q = (wheel_curvature_target * steering.x).atan();
     }
 }
 
+// Marks the rotating joint of a wheel (so wheel readouts skip the chassis,
+// suspension and steering joints)
+// - driven: the drivetrain applies torque to this wheel
+#[derive(Component, Clone, Copy, Debug)]
+pub struct WheelJoint {
+    pub driven: bool,
+}
+
+// Average spin (RPM) of the driven wheels, or of all wheels if none are driven
+pub fn average_wheel_rpm(wheels: &[(&Joint, &WheelJoint)]) -> f64 {
+    let any_driven = wheels.iter().any(|(_, wheel)| wheel.driven);
+    let mut total_rpm = 0.0;
+    let mut count = 0;
+    for (joint, _) in wheels.iter().filter(|(_, wheel)| wheel.driven || !any_driven) {
+        total_rpm += (joint.qd * 60.0) / (2.0 * std::f64::consts::PI);
+        count += 1;
+    }
+    if count > 0 {
+        total_rpm / count as f64
+    } else {
+        0.0
+    }
+}
+
 #[derive(Clone)]
 pub enum DriveType {
     None,
@@ -180,20 +296,667 @@ pub fn driven_wheel_lookup_system(
     }
 }
 
+// Engine and gearbox shared by all driven wheels.
+// The engine speed is derived from the average driven wheel speed and the current gear,
+// the engine torque is looked up from the torque curve and multiplied back through the gearing.
+#[derive(Resource, Clone)]
+pub struct Transmission {
+    pub gear_ratios: Vec<f64>,
+    pub final_drive: f64,
+    pub efficiency: f64,
+    pub torque_curve: Interpolator1D, // engine torque (Nm) vs engine speed (RPM)
+    pub idle_rpm: f64,
+    pub redline_rpm: f64,
+    pub upshift_rpm: f64,
+    pub downshift_rpm: f64,
+    pub reverse_ratio: f64,
+    pub gear: usize,      // index into gear_ratios
+    pub reverse: bool,    // reverse gear engaged, follows CarControl.reverse
+    pub engine_rpm: f64,  // output, updated by transmission_system
+}
+
+impl Default for Transmission {
+    fn default() -> Self {
+        let rpms = vec![1000., 2000., 3000., 4000., 5000., 6000., 7000.];
+        let torques = vec![200., 250., 280., 300., 290., 260., 220.];
+        Self {
+            gear_ratios: vec![2.9, 1.9, 1.35, 1.0, 0.8],
+            final_drive: 3.4,
+            efficiency: 0.9,
+            torque_curve: Interpolator1D::new(rpms, torques),
+            idle_rpm: 1000.,
+            redline_rpm: 7000.,
+            upshift_rpm: 6000.,
+            downshift_rpm: 2500.,
+            reverse_ratio: 3.2,
+            gear: 0,
+            reverse: false,
+            engine_rpm: 1000.,
+        }
+    }
+}
+
+impl Transmission {
+    // Overall ratio between engine speed and wheel speed in the current gear
+    pub fn total_ratio(&self) -> f64 {
+        if self.reverse {
+            return self.reverse_ratio * self.final_drive;
+        }
+        self.gear_ratios[self.gear] * self.final_drive
+    }
+
+    // Engine speed (RPM) for a given wheel speed (rad/s), never below idle
+    pub fn engine_rpm(&self, wheel_speed: f64) -> f64 {
+        let engine_speed = wheel_speed.abs() * self.total_ratio();
+        (engine_speed * 60. / (2. * std::f64::consts::PI)).max(self.idle_rpm)
+    }
+
+    // Total torque at the wheels for a throttle position and engine speed.
+    // Past the redline the rev limiter cuts the torque. In reverse the torque is negative.
+    pub fn wheel_torque(&self, throttle: f64, rpm: f64) -> f64 {
+        if rpm >= self.redline_rpm {
+            return 0.0;
+        }
+        let direction = if self.reverse { -1. } else { 1. };
+        direction * throttle * self.torque_curve.interpolate(rpm) * self.total_ratio() * self.efficiency
+    }
+
+    // Automatic gearbox: shift up/down one gear when the engine leaves the shift window
+    // (reverse has a single gear)
+    pub fn update_gear(&mut self, rpm: f64) {
+        if self.reverse {
+            self.gear = 0;
+        } else if rpm > self.upshift_rpm && self.gear + 1 < self.gear_ratios.len() {
+            self.gear += 1;
+        } else if rpm < self.downshift_rpm && self.gear > 0 {
+            self.gear -= 1;
+        }
+    }
+}
+
+// Drives the wheels tagged with DrivenWheelLookup through the transmission
+// (used in place of driven_wheel_lookup_system)
+pub fn transmission_system(
+    mut joints: Query<(&mut Joint, &mut DrivenWheelLookup)>,
+    mut transmission: ResMut<Transmission>,
+    control: Res<CarControl>,
+    fuel: Res<FuelTank>,
+) {
+    let wheel_count = joints.iter().count();
+    if wheel_count == 0 {
+        return;
+    }
+    let wheel_speed = joints.iter().map(|(joint, _)| joint.qd).sum::<f64>() / wheel_count as f64;
+    transmission.reverse = control.reverse;
+
+    let rpm = transmission.engine_rpm(wheel_speed);
+    transmission.update_gear(rpm);
+    let rpm = transmission.engine_rpm(wheel_speed);
+    transmission.engine_rpm = rpm;
+
+    // an empty tank cuts the engine
+    let throttle = if fuel.is_empty() { 0.0 } else { control.throttle as f64 };
+    let wheel_torque = transmission.wheel_torque(throttle, rpm) / wheel_count as f64;
+    for (mut joint, mut driven_wheel) in joints.iter_mut() {
+        joint.tau += wheel_torque;
+        driven_wheel
+            .outputs
+            .insert("torque".to_string(), wheel_torque);
+        driven_wheel
+            .outputs
+            .insert("engine_rpm".to_string(), rpm);
+    }
+}
+
+// Fuel budget for the engine (liters)
+// The engine burns idle_rate while running, plus up to max_rate at full throttle on the redline.
+#[derive(Resource, Clone)]
+pub struct FuelTank {
+    pub capacity: f64,
+    pub level: f64,
+    pub idle_rate: f64, // L/s
+    pub max_rate: f64,  // L/s
+}
+
+impl Default for FuelTank {
+    fn default() -> Self {
+        Self {
+            capacity: 50.,
+            level: 50.,
+            idle_rate: 0.0003,
+            max_rate: 0.01,
+        }
+    }
+}
+
+impl FuelTank {
+    // Fuel flow (L/s) for a throttle position and engine load (rpm / redline)
+    pub fn consumption_rate(&self, throttle: f64, rpm_fraction: f64) -> f64 {
+        self.idle_rate + throttle.clamp(0., 1.) * rpm_fraction.clamp(0., 1.) * self.max_rate
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.level <= 0.
+    }
+
+    pub fn refuel(&mut self) {
+        self.level = self.capacity;
+    }
+}
+
+// Burns fuel once per physics step (runs in FixedUpdate, not in the physics schedule,
+// which is evaluated several times per step by the solver)
+pub fn fuel_system(
+    mut fuel: ResMut<FuelTank>,
+    transmission: Res<Transmission>,
+    control: Res<CarControl>,
+    sim_time: Res<SimTime>,
+) {
+    if fuel.is_empty() {
+        return;
+    }
+    let rpm_fraction = transmission.engine_rpm / transmission.redline_rpm;
+    let burned = fuel.consumption_rate(control.throttle as f64, rpm_fraction) * sim_time.dt;
+    fuel.level = (fuel.level - burned).max(0.);
+    if fuel.is_empty() {
+        println!("Out of fuel");
+    }
+}
+
+// Press 'F' (KeyBindings::refuel) to refill the tank
+pub fn refuel_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    mut fuel: ResMut<FuelTank>,
+) {
+    if keyboard_input.just_pressed(keys.refuel) {
+        fuel.refuel();
+        println!("Refueled to {:.1} L", fuel.level);
+    }
+}
+
 #[derive(Component)]
 pub struct BrakeWheel {
     pub max_torque: f64,
+    pub handbrake_torque: f64, // zero for wheels without a handbrake
+    pub rolling_radius: f64,
+    pub handbrake_hold: Option<f64>, // wheel angle held by the handbrake (None when off)
 }
 
 impl BrakeWheel {
-    pub fn new(max_torque: f64) -> Self {
-        Self { max_torque }
+    pub fn new(max_torque: f64, handbrake_torque: f64, rolling_radius: f64) -> Self {
+        Self {
+            max_torque,
+            handbrake_torque,
+            rolling_radius,
+            handbrake_hold: None,
+        }
     }
 }
 
-pub fn brake_wheel_system(mut joints: Query<(&mut Joint, &BrakeWheel)>, control: Res<CarControl>) {
-    for (mut joint, brake_wheel) in joints.iter_mut() {
+// Anti-lock braking.
+// When enabled, the brake torque on each wheel is reduced as its slip ratio
+// (vehicle speed vs wheel surface speed) rises past the target slip, and fully released at max slip.
+#[derive(Resource)]
+pub struct AbsEnabled {
+    pub enabled: bool,
+    pub target_slip: f64, // slip ratio with the best braking grip
+    pub max_slip: f64,    // slip ratio where the brake is fully released
+    pub min_speed: f64,   // below this vehicle speed (m/s) ABS is inactive
+}
+
+impl Default for AbsEnabled {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            target_slip: 0.15,
+            max_slip: 0.3,
+            min_speed: 1.0,
+        }
+    }
+}
+
+// Whether ABS reduced any brake torque at the latest force evaluation, written by
+// brake_wheel_system for the systems that run once per step
+#[derive(Resource, Default)]
+pub struct AbsActivity {
+    pub modulating: bool,
+}
+
+impl AbsEnabled {
+    // Fraction of the requested brake torque to apply for a wheel slip ratio
+    pub fn brake_factor(&self, slip: f64) -> f64 {
+        ((self.max_slip - slip) / (self.max_slip - self.target_slip)).clamp(0., 1.)
+    }
+}
+
+// Press 'B' (KeyBindings::toggle_abs) to toggle ABS
+pub fn abs_toggle_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    mut abs: ResMut<AbsEnabled>,
+) {
+    if keyboard_input.just_pressed(keys.toggle_abs) {
+        abs.enabled = !abs.enabled;
+        println!("ABS {}", if abs.enabled { "enabled" } else { "disabled" });
+    }
+}
+
+// The handbrake holds a stopped wheel like static friction: a stiff torsion spring
+// (Nm/rad) and damper (Nm s/rad) pull the wheel back to the angle where it stopped, up to
+// the handbrake torque. Past that the wheel slips and the hold angle slides along with it.
+const HANDBRAKE_STIFFNESS: f64 = 1e5;
+const HANDBRAKE_DAMPING: f64 = 1e3;
+
+pub fn brake_wheel_system(
+    mut joints: Query<(&mut Joint, &mut BrakeWheel)>,
+    control: Res<CarControl>,
+    abs: Res<AbsEnabled>,
+    vehicle_state: Res<VehicleState>,
+    mut abs_activity: ResMut<AbsActivity>,
+) {
+    let vehicle_speed = vehicle_state.speed as f64;
+    let mut modulating = false;
+    for (mut joint, mut brake_wheel) in joints.iter_mut() {
+        let mut brake = control.brake as f64;
+
+        if abs.enabled && brake > 0. && vehicle_speed > abs.min_speed {
+            let wheel_speed = joint.qd.abs() * brake_wheel.rolling_radius;
+            let slip = (vehicle_speed - wheel_speed) / vehicle_speed;
+            let factor = abs.brake_factor(slip);
+            if factor < 1. {
+                modulating = true;
+            }
+            brake *= factor;
+        }
+
         // TODO: make better? What to do around zero speed?
-        joint.tau += -control.brake as f64 * brake_wheel.max_torque * joint.qd.min(1.).max(-1.);
+        let torque = brake * brake_wheel.max_torque * joint.qd.clamp(-1., 1.);
+
+        // the handbrake is not modulated by ABS, it is meant to lock the wheel
+        let handbrake = handbrake_torque(&joint, &mut brake_wheel, control.handbrake as f64);
+
+        joint.tau += handbrake - torque;
+    }
+
+    abs_activity.modulating = modulating;
+}
+
+// Logs when ABS starts and stops modulating the brakes. Runs once per physics step in
+// FixedUpdate: brake_wheel_system runs at every solver stage, where the modulation can
+// flip back and forth within a step.
+pub fn abs_log_system(
+    abs_activity: Res<AbsActivity>,
+    vehicle_state: Res<VehicleState>,
+    mut abs_active: Local<bool>,
+) {
+    if abs_activity.modulating != *abs_active {
+        *abs_active = abs_activity.modulating;
+        if abs_activity.modulating {
+            println!("ABS modulating brake torque at {:.1} m/s", vehicle_state.speed);
+        } else {
+            println!("ABS released");
+        }
+    }
+}
+
+// Torque of the handbrake on a wheel (see HANDBRAKE_STIFFNESS), opposing the wheel's
+// motion away from its hold angle. Unlike the brake it doesn't fade out at zero speed,
+// so the handbrake keeps the car parked on a slope.
+pub fn handbrake_torque(joint: &Joint, brake_wheel: &mut BrakeWheel, handbrake: f64) -> f64 {
+    let limit = handbrake * brake_wheel.handbrake_torque;
+    if limit <= 0. {
+        brake_wheel.handbrake_hold = None;
+        return 0.;
+    }
+    // slide the hold angle along while the wheel turns harder than the handbrake can hold
+    let max_twist = limit / HANDBRAKE_STIFFNESS;
+    let hold = brake_wheel.handbrake_hold.unwrap_or(joint.q);
+    let hold = joint.q - (joint.q - hold).clamp(-max_twist, max_twist);
+    brake_wheel.handbrake_hold = Some(hold);
+    (-HANDBRAKE_STIFFNESS * (joint.q - hold) - HANDBRAKE_DAMPING * joint.qd).clamp(-limit, limit)
+}
+
+// Aerodynamic forces on the car body
+// drag = 0.5 * air_density * drag_coefficient * frontal_area * v^2, opposite the velocity
+// downforce = 0.5 * air_density * downforce_coefficient * frontal_area * v^2, pushing the body down
+// (set downforce_coefficient to zero to disable downforce)
+#[derive(Resource, Clone)]
+pub struct Aerodynamics {
+    pub drag_coefficient: f64,
+    pub frontal_area: f64,
+    pub air_density: f64,
+    pub downforce_coefficient: f64,
+}
+
+impl Default for Aerodynamics {
+    fn default() -> Self {
+        Self {
+            drag_coefficient: 0.32,
+            frontal_area: 2.2,
+            air_density: 1.225,
+            downforce_coefficient: 0.0,
+        }
+    }
+}
+
+impl Aerodynamics {
+    // Drag force (absolute coordinates) for a vehicle velocity
+    pub fn drag_force(&self, velocity: Vector) -> Vector {
+        -0.5 * self.air_density * self.drag_coefficient * self.frontal_area * velocity.norm() * velocity
+    }
+
+    // Downward force (absolute coordinates) for a vehicle velocity, only horizontal motion counts
+    pub fn downforce(&self, velocity: Vector) -> Vector {
+        let horizontal_speed_squared = velocity.x.powi(2) + velocity.y.powi(2);
+        -0.5 * self.air_density
+            * self.downforce_coefficient
+            * self.frontal_area
+            * horizontal_speed_squared
+            * Vector::z()
+    }
+}
+
+// Steady wind with gusts
+// - direction: direction the wind blows towards (rad, from +x, counterclockwise)
+// - speed: mean wind speed (m/s), scaled by Weather::wind_factor
+// - gustiness: gust amplitude as a fraction of the speed (0 for a steady wind)
+// The gusts are a sum of slow sines of sim time, so they repeat exactly on replay.
+#[derive(Resource, Clone)]
+pub struct Wind {
+    pub direction: f64,
+    pub speed: f64,
+    pub gustiness: f64,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self {
+            direction: 0.,
+            speed: 0.,
+            gustiness: 0.3,
+        }
+    }
+}
+
+impl Wind {
+    // Wind velocity without gusts (absolute coordinates, horizontal)
+    pub fn mean_velocity(&self, weather_factor: f64) -> Vector {
+        self.speed * weather_factor * Vector::new(self.direction.cos(), self.direction.sin(), 0.)
+    }
+
+    // Wind velocity with gusts at sim time `time`
+    pub fn velocity(&self, time: f64, weather_factor: f64) -> Vector {
+        let gust = 0.6 * (0.7 * time).sin()
+            + 0.3 * (1.9 * time + 1.).sin()
+            + 0.1 * (4.3 * time + 2.).sin();
+        (1. + self.gustiness * gust) * self.mean_velocity(weather_factor)
+    }
+}
+
+// Applies drag and downforce to the car body (the chassis roll joint) at its origin.
+// Both use the velocity relative to the air, so a crosswind pushes the car sideways
+// and a headwind adds drag.
+pub fn aero_drag_system(
+    mut joints: Query<&mut Joint>,
+    aero: Res<Aerodynamics>,
+    wind: Res<Wind>,
+    weather: Option<Res<Weather>>,
+    sim_time: Res<SimTime>,
+    vehicle_state: Res<VehicleState>,
+) {
+    let weather_factor = weather.map_or(1., |weather| weather.wind_factor());
+    let velocity = Vector::new(
+        vehicle_state.velocity.x as f64,
+        vehicle_state.velocity.y as f64,
+        vehicle_state.velocity.z as f64,
+    ) - wind.velocity(sim_time.time(), weather_factor);
+    let force = aero.drag_force(velocity) + aero.downforce(velocity);
+
+    for mut joint in joints.iter_mut() {
+        if joint.name == "chassis_rx" {
+            let body_abs = joint.x.inverse().transform_point(Vector::zeros()); // body origin in absolute coordinates
+            joint.f_ext += Force::force_point(force, body_abs);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rigid_body::sva::{Inertia, Xform};
+
+    fn wheel_joint(q: f64, qd: f64) -> Joint {
+        let mut joint = Joint::ry("wheel".to_string(), Inertia::zero(), Xform::identity());
+        joint.q = q;
+        joint.qd = qd;
+        joint
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn handbrake_holds_a_stopped_wheel() {
+        let mut brake_wheel = BrakeWheel::new(1000., 3000., 0.3);
+        let mut joint = wheel_joint(2., 0.);
+
+        // engaging holds the wheel where it is
+        assert_eq!(handbrake_torque(&joint, &mut brake_wheel, 1.), 0.);
+        assert_eq!(brake_wheel.handbrake_hold, Some(2.));
+
+        // a stopped wheel pushed off its hold angle is pulled back (the brake alone
+        // gives no torque at zero speed)
+        joint.q = 2. + 1e-3;
+        assert!(handbrake_torque(&joint, &mut brake_wheel, 1.) < 0.);
+        joint.q = 2. - 1e-3;
+        assert!(handbrake_torque(&joint, &mut brake_wheel, 1.) > 0.);
+
+        // pushed past the handbrake torque the wheel slips at the full torque
+        joint.q = 3.;
+        assert_close(handbrake_torque(&joint, &mut brake_wheel, 1.), -3000.);
+        assert_close(handbrake_torque(&joint, &mut brake_wheel, 0.5), -1500.);
+
+        // released
+        assert_eq!(handbrake_torque(&joint, &mut brake_wheel, 0.), 0.);
+        assert_eq!(brake_wheel.handbrake_hold, None);
+    }
+
+    #[test]
+    fn handbrake_opposes_a_turning_wheel() {
+        let mut brake_wheel = BrakeWheel::new(1000., 3000., 0.3);
+        let joint = wheel_joint(0., 5.);
+        assert_close(handbrake_torque(&joint, &mut brake_wheel, 1.), -3000.);
+        let joint = wheel_joint(0., -5.);
+        brake_wheel.handbrake_hold = None;
+        assert_close(handbrake_torque(&joint, &mut brake_wheel, 1.), 3000.);
+    }
+
+    #[test]
+    fn engine_rpm_follows_the_gearing() {
+        let mut transmission = Transmission::default();
+        let rad_s_to_rpm = 60. / (2. * std::f64::consts::PI);
+
+        // first gear: 2.9 * 3.4 engine turns per wheel turn, either way the wheel spins
+        assert_close(transmission.engine_rpm(50.), 50. * 2.9 * 3.4 * rad_s_to_rpm);
+        assert_close(transmission.engine_rpm(-50.), 50. * 2.9 * 3.4 * rad_s_to_rpm);
+        transmission.gear = 3;
+        assert_close(transmission.engine_rpm(50.), 50. * 3.4 * rad_s_to_rpm);
+        transmission.reverse = true;
+        assert_close(transmission.engine_rpm(-20.), 20. * 3.2 * 3.4 * rad_s_to_rpm);
+
+        // a slow or stopped car idles
+        assert_close(transmission.engine_rpm(0.), 1000.);
+        assert_close(transmission.engine_rpm(5.), 1000.);
+    }
+
+    #[test]
+    fn wheel_torque_multiplies_through_the_gearing_up_to_the_redline() {
+        let mut transmission = Transmission::default();
+
+        // 300 Nm at 4000 RPM, through first gear and the final drive, minus losses
+        assert_close(transmission.wheel_torque(1., 4000.), 300. * 2.9 * 3.4 * 0.9);
+        assert_close(transmission.wheel_torque(0.5, 4000.), 0.5 * 300. * 2.9 * 3.4 * 0.9);
+        assert_close(transmission.wheel_torque(1., 2500.), 265. * 2.9 * 3.4 * 0.9);
+        transmission.gear = 4;
+        assert_close(transmission.wheel_torque(1., 4000.), 300. * 0.8 * 3.4 * 0.9);
+
+        // the rev limiter cuts the torque at and past the redline
+        assert!(transmission.wheel_torque(1., 6999.) > 0.);
+        assert_eq!(transmission.wheel_torque(1., 7000.), 0.);
+        assert_eq!(transmission.wheel_torque(1., 8000.), 0.);
+
+        // reverse drives the other way
+        transmission.reverse = true;
+        assert_close(transmission.wheel_torque(1., 4000.), -300. * 3.2 * 3.4 * 0.9);
+    }
+
+    #[test]
+    fn abs_releases_the_brake_past_the_target_slip() {
+        let abs = AbsEnabled::default();
+        // up to the target slip the full brake torque is applied
+        for slip in [-0.1, 0., 0.1, 0.15] {
+            assert_close(abs.brake_factor(slip), 1.);
+        }
+        // past it the brake lets go, linearly down to nothing at max slip
+        assert_close(abs.brake_factor(0.2), 2. / 3.);
+        assert_close(abs.brake_factor(0.25), 1. / 3.);
+        assert!(abs.brake_factor(0.16) < 1.);
+        assert!(abs.brake_factor(0.2) > abs.brake_factor(0.25));
+        for slip in [0.3, 0.5, 1.] {
+            assert_close(abs.brake_factor(slip), 0.);
+        }
+    }
+
+    #[test]
+    fn drag_limits_the_car_to_its_terminal_velocity() {
+        let aero = Aerodynamics::default();
+        let (mass, thrust) = (1500., 3000.);
+        let terminal = (thrust
+            / (0.5 * aero.air_density * aero.drag_coefficient * aero.frontal_area))
+            .sqrt();
+
+        // accelerate a point mass from rest under a constant thrust along x
+        let dt = 0.01;
+        let mut velocity = Vector::zeros();
+        for _ in 0..60_000 {
+            let force = thrust * Vector::x() + aero.drag_force(velocity) + aero.downforce(velocity);
+            velocity += force / mass * dt;
+        }
+        assert!((velocity.x - terminal).abs() < 1e-3, "{} != {}", velocity.x, terminal);
+        assert!(velocity.y.abs() < 1e-12 && velocity.z.abs() < 1e-12);
+
+        // at the terminal velocity drag balances the thrust
+        assert!((aero.drag_force(terminal * Vector::x()).x + thrust).abs() < 1e-6);
+    }
+
+
+    #[test]
+    fn more_damping_settles_a_bump_faster() {
+        // a quarter car mass on one suspension, kicked upward by a bump
+        let suspension = SuspensionComponent::new(30000., 2000., 0.);
+        let mass = 400.;
+        let bounce = |damping_scale: f64| {
+            let config = SuspensionConfig {
+                damping_scale,
+                ..default()
+            };
+            let (stiffness, damping) = (suspension.stiffness(&config), suspension.damping(&config));
+            let dt = 1e-4;
+            let (mut q, mut qd) = (0., 1.);
+            // largest travel after the suspension first swings back through its rest position
+            let (mut crossed, mut rebound) = (false, 0_f64);
+            for _ in 0..20_000 {
+                qd += -(stiffness * q + damping * qd) / mass * dt;
+                q += qd * dt;
+                crossed |= q < 0.;
+                if crossed {
+                    rebound = rebound.max(q.abs());
+                }
+            }
+            assert!(q.abs() < 1e-3, "still moving after 2 s: {}", q);
+            rebound
+        };
+
+        let (soft, firm) = (bounce(1.), bounce(2.));
+        assert!(soft > 0.);
+        assert!(firm < 0.5 * soft, "{} >= {}", firm, soft);
+    }
+
+
+    #[test]
+    fn steady_crosswind_pushes_the_car_sideways() {
+        let aero = Aerodynamics::default();
+        let wind = Wind {
+            direction: std::f64::consts::FRAC_PI_2,
+            speed: 10.,
+            gustiness: 0.,
+        };
+        let mass = 1500.;
+
+        // a point mass at rest, pushed only by the drag of the air moving past it
+        let dt = 0.1;
+        let (mut position, mut velocity) = (Vector::zeros(), Vector::zeros());
+        for step in 0..100_000 {
+            let time = step as f64 * dt;
+            let wind_velocity = wind.velocity(time, 1.);
+            // without gusts the wind is the same at every time
+            assert_eq!(wind_velocity, wind.mean_velocity(1.));
+
+            let force = aero.drag_force(velocity - wind_velocity) + aero.downforce(velocity);
+            let previous = (position.y, velocity.y);
+            velocity += force / mass * dt;
+            position += velocity * dt;
+            assert!(velocity.y > previous.1 && velocity.y < wind.speed);
+            assert!(position.y > previous.0);
+        }
+        // the drift speeds up to the wind speed, along the wind only
+        assert!((velocity.y - wind.speed).abs() < 0.5, "{} != {}", velocity.y, wind.speed);
+        assert!(velocity.x.abs() < 1e-9 && position.x.abs() < 1e-6);
+    }
+
+
+    #[test]
+    fn wheel_rpm_averages_the_driven_wheels() {
+        use std::f64::consts::TAU;
+        let driven = WheelJoint { driven: true };
+        let free = WheelJoint { driven: false };
+        let (spinning, rolling) = (wheel_joint(0., TAU), wheel_joint(0., 3. * TAU));
+
+        // one turn per second on the driven wheel, the free wheel doesn't count
+        assert_close(average_wheel_rpm(&[(&spinning, &driven), (&rolling, &free)]), 60.);
+        // with no driven wheels every wheel counts
+        assert_close(average_wheel_rpm(&[(&spinning, &free), (&rolling, &free)]), 120.);
+        assert_eq!(average_wheel_rpm(&[]), 0.);
+    }
+
+
+    #[test]
+    fn brake_evaluation_reports_abs_activity() {
+        let mut app = App::new();
+        app.init_resource::<AbsEnabled>()
+            .init_resource::<AbsActivity>()
+            .insert_resource(CarControl {
+                brake: 1.,
+                ..default()
+            })
+            .insert_resource(VehicleState {
+                speed: 10.,
+                ..default()
+            })
+            .add_systems(Update, brake_wheel_system);
+        let wheel = app
+            .world
+            .spawn((wheel_joint(0., 0.), BrakeWheel::new(1000., 3000., 0.3)))
+            .id();
+
+        // a locked wheel at speed has its brake released
+        app.update();
+        assert!(app.world.resource::<AbsActivity>().modulating);
+        // rolling with the car, the full brake goes through
+        app.world.get_mut::<Joint>(wheel).unwrap().qd = 10. / 0.3;
+        app.update();
+        assert!(!app.world.resource::<AbsActivity>().modulating);
     }
 }
@@ -0,0 +1,295 @@
+use bevy::prelude::*;
+use bevy_integrator::{PhysicsState, SimTime, Stateful};
+use grid_terrain::GridTerrain;
+use rigid_body::{
+    joint::{Joint, JointState},
+    sva::Force,
+};
+use std::collections::HashMap;
+
+use crate::{
+    control::CarControl, key_bindings::KeyBindings, line_draw::LineDrawState,
+    physics::Transmission, vehicle_state::VehicleState,
+};
+
+// Where the car is placed on reset.
+// - position: chassis position in absolute coordinates, None keeps the startup position
+//   from the car definition
+// - yaw: chassis heading (rad)
+#[derive(Resource, Default, Clone)]
+pub struct SpawnPoint {
+    pub position: Option<Vec3>,
+    pub yaw: f64,
+}
+
+// Joint states right after startup, restored by reset_car_system
+#[derive(Resource, Default)]
+pub struct InitialJointStates(pub HashMap<Entity, JointState>);
+
+// Records the startup state of every joint (runs in PostStartup, once the car is built)
+pub fn capture_initial_state_system(
+    joints: Query<(Entity, &Joint)>,
+    mut initial_states: ResMut<InitialJointStates>,
+) {
+    initial_states.0 = joints
+        .iter()
+        .map(|(entity, joint)| (entity, joint.get_state()))
+        .collect();
+}
+
+// A list of places the car can be respawned at (e.g. checkpoints around a track)
+// - points: ground position and heading (rad) of each spawn point
+#[derive(Resource, Default, Clone)]
+pub struct SpawnPoints {
+    pub points: Vec<(Vec3, f64)>,
+}
+
+impl SpawnPoints {
+    // The closest spawn point in front of the car (or the closest overall if none is ahead)
+    pub fn nearest_ahead(&self, position: Vec3, yaw: f64) -> Option<(Vec3, f64)> {
+        let forward = Vec2::from_angle(yaw as f32);
+        let distance = |point: &&(Vec3, f64)| (point.0 - position).truncate().length();
+        let ahead = self
+            .points
+            .iter()
+            .filter(|point| (point.0 - position).truncate().dot(forward) > 0.)
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)));
+        ahead
+            .or_else(|| self.points.iter().min_by(|a, b| distance(a).total_cmp(&distance(b))))
+            .copied()
+    }
+}
+
+// Puts every joint back to its startup state, then moves the chassis to `position`
+// (if given) with heading `yaw`. The integrator's copy of the state is overwritten too,
+// otherwise the next step would bring back the old velocities.
+fn restore_joints(
+    joints: &mut Query<(Entity, &mut Joint)>,
+    physics_state: &mut PhysicsState<Joint>,
+    initial_states: &InitialJointStates,
+    position: Option<Vec3>,
+    yaw: f64,
+) {
+    for (entity, mut joint) in joints.iter_mut() {
+        if let Some(state) = initial_states.0.get(&entity) {
+            joint.set_state(state);
+        }
+        if let Some(position) = position {
+            match joint.name.as_str() {
+                "chassis_px" => joint.q = position.x as f64,
+                "chassis_py" => joint.q = position.y as f64,
+                "chassis_pz" => joint.q = position.z as f64,
+                _ => {}
+            }
+        }
+        if joint.name == "chassis_rz" {
+            joint.q = yaw;
+        }
+        joint.qdd = 0.;
+        joint.tau = 0.;
+        joint.f_ext = Force::zero();
+        physics_state.states.insert(entity, joint.get_state());
+    }
+}
+
+// Controls, vehicle state and gearbox back to a car standing still in first gear
+fn clear_car_state(
+    control: &mut CarControl,
+    vehicle_state: &mut VehicleState,
+    transmission: &mut Transmission,
+) {
+    *control = CarControl::default();
+    *vehicle_state = VehicleState::default();
+    transmission.gear = 0;
+    transmission.reverse = false;
+}
+
+// Startup height of the chassis above the ground, kept when respawning at a ground point
+fn ride_height(joints: &Query<(Entity, &mut Joint)>, initial_states: &InitialJointStates) -> f32 {
+    joints
+        .iter()
+        .find(|(_, joint)| joint.name == "chassis_pz")
+        .and_then(|(entity, _)| initial_states.0.get(&entity))
+        .map_or(0., |state| state.q as f32)
+}
+
+// Press 'Backspace' (KeyBindings::reset_car) to put the car back at the spawn point at rest.
+pub fn reset_car_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    initial_states: Res<InitialJointStates>,
+    spawn_point: Res<SpawnPoint>,
+    mut joints: Query<(Entity, &mut Joint)>,
+    mut physics_state: ResMut<PhysicsState<Joint>>,
+    mut control: ResMut<CarControl>,
+    mut vehicle_state: ResMut<VehicleState>,
+    mut transmission: ResMut<Transmission>,
+) {
+    if !keyboard_input.just_pressed(keys.reset_car) {
+        return;
+    }
+
+    restore_joints(
+        &mut joints,
+        &mut physics_state,
+        &initial_states,
+        spawn_point.position,
+        spawn_point.yaw,
+    );
+    clear_car_state(&mut control, &mut vehicle_state, &mut transmission);
+    println!("Car reset");
+}
+
+// Press 'J' (KeyBindings::add_spawn_point) to add a spawn point at the last line-draw click,
+// facing from the click before it.
+// Press 'N' (KeyBindings::respawn) to reset the car at rest at the nearest spawn point ahead.
+pub fn respawn_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    line_draw_state: Option<Res<LineDrawState>>,
+    initial_states: Res<InitialJointStates>,
+    mut spawn_points: ResMut<SpawnPoints>,
+    mut joints: Query<(Entity, &mut Joint)>,
+    mut physics_state: ResMut<PhysicsState<Joint>>,
+    mut control: ResMut<CarControl>,
+    mut vehicle_state: ResMut<VehicleState>,
+    mut transmission: ResMut<Transmission>,
+) {
+    if keyboard_input.just_pressed(keys.add_spawn_point) {
+        let points = line_draw_state.as_ref().map_or(&[][..], |state| state.points.as_slice());
+        match points {
+            [.., previous, last] => {
+                let yaw = (*last - *previous).y.atan2((*last - *previous).x) as f64;
+                spawn_points.points.push((*last, yaw));
+                println!("Spawn point {} added at {:?}", spawn_points.points.len(), last);
+            }
+            [last] => {
+                spawn_points.points.push((*last, 0.));
+                println!("Spawn point {} added at {:?}", spawn_points.points.len(), last);
+            }
+            [] => println!("Click on the terrain (line draw) to place a spawn point"),
+        }
+    }
+
+    if !keyboard_input.just_pressed(keys.respawn) {
+        return;
+    }
+
+    // current chassis position and heading
+    let mut position = Vec3::ZERO;
+    let mut yaw = 0.;
+    for (_, joint) in joints.iter() {
+        match joint.name.as_str() {
+            "chassis_px" => position.x = joint.q as f32,
+            "chassis_py" => position.y = joint.q as f32,
+            "chassis_pz" => position.z = joint.q as f32,
+            "chassis_rz" => yaw = joint.q,
+            _ => {}
+        }
+    }
+
+    let Some((point, point_yaw)) = spawn_points.nearest_ahead(position, yaw) else {
+        println!("No spawn points, add one with {:?}", keys.add_spawn_point);
+        return;
+    };
+    let ride_height = ride_height(&joints, &initial_states);
+    restore_joints(
+        &mut joints,
+        &mut physics_state,
+        &initial_states,
+        Some(point + Vec3::Z * ride_height),
+        point_yaw,
+    );
+    clear_car_state(&mut control, &mut vehicle_state, &mut transmission);
+    println!("Respawned at {:?}", point);
+}
+
+// What happens when the car leaves the terrain grid
+// - Warn: only the HUD warning
+// - Respawn: after the grace period, respawn at the nearest spawn point (or reset to
+//   the SpawnPoint if there are none)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutOfBoundsAction {
+    Warn,
+    Respawn,
+}
+
+// Bounds check against the terrain grid extents
+// - margin: how far (m) past the grid edges the car may go
+// - action: see OutOfBoundsAction
+// - grace_period: sim seconds out of bounds before the action
+// - outside_time: sim seconds the car has been out of bounds (None while inside)
+#[derive(Resource)]
+pub struct OutOfBounds {
+    pub margin: f32,
+    pub action: OutOfBoundsAction,
+    pub grace_period: f64,
+    pub outside_time: Option<f64>,
+}
+
+impl Default for OutOfBounds {
+    fn default() -> Self {
+        Self {
+            margin: 50.,
+            action: OutOfBoundsAction::Respawn,
+            grace_period: 5.,
+            outside_time: None,
+        }
+    }
+}
+
+// Tracks how long the car has been off the terrain grid (plus margin) and brings it
+// back after the grace period (runs once per physics step, so the grace period is in
+// sim time and an unattended run at a high time scale is caught just as quickly)
+pub fn out_of_bounds_system(
+    terrain: Option<Res<GridTerrain>>,
+    sim_time: Res<SimTime>,
+    spawn_point: Res<SpawnPoint>,
+    spawn_points: Res<SpawnPoints>,
+    initial_states: Res<InitialJointStates>,
+    mut out_of_bounds: ResMut<OutOfBounds>,
+    mut joints: Query<(Entity, &mut Joint)>,
+    mut physics_state: ResMut<PhysicsState<Joint>>,
+    mut control: ResMut<CarControl>,
+    mut vehicle_state: ResMut<VehicleState>,
+    mut transmission: ResMut<Transmission>,
+) {
+    let Some(terrain) = terrain else {
+        return;
+    };
+    let (min, max) = terrain.extents();
+    let margin = out_of_bounds.margin;
+    let position = vehicle_state.position;
+    let inside = position.x >= min.x as f32 - margin
+        && position.x <= max.x as f32 + margin
+        && position.y >= min.y as f32 - margin
+        && position.y <= max.y as f32 + margin;
+    if inside {
+        out_of_bounds.outside_time = None;
+        return;
+    }
+
+    let outside_time = out_of_bounds.outside_time.map_or(0., |time| time + sim_time.dt);
+    if outside_time == 0. {
+        println!("Out of bounds at {:?}", position);
+    }
+    out_of_bounds.outside_time = Some(outside_time);
+    if out_of_bounds.action != OutOfBoundsAction::Respawn
+        || outside_time < out_of_bounds.grace_period
+    {
+        return;
+    }
+
+    let yaw = vehicle_state.heading as f64;
+    let (target, target_yaw) = match spawn_points.nearest_ahead(position, yaw) {
+        Some((point, point_yaw)) => (
+            Some(point + Vec3::Z * ride_height(&joints, &initial_states)),
+            point_yaw,
+        ),
+        None => (spawn_point.position, spawn_point.yaw),
+    };
+    restore_joints(&mut joints, &mut physics_state, &initial_states, target, target_yaw);
+    clear_car_state(&mut control, &mut vehicle_state, &mut transmission);
+    out_of_bounds.outside_time = None;
+    println!("Out of bounds for {:.1} s, car brought back", outside_time);
+}
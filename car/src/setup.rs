@@ -1,35 +1,57 @@
 #![allow(dead_code)]
 
 use bevy::prelude::*;
-use bevy_integrator::{PhysicsSchedule, PhysicsSet};
+use bevy_integrator::{PhysicsSchedule, PhysicsSet, SimTime};
 
 use crate::{
-    control::user_control_system,
+    autonomous_control::AutonomousPlugin,
+    build::{vehicle_config_system, VehicleConfig},
+    camera_focus::{camera_focus_system, camera_terrain_system, CameraTerrainConstraint},
+    environment::ContactConfig,
+    control::{
+        control_source_system, rumble_system, sim_control_system, steering_assist_toggle_system,
+        time_scale_system, user_control_system, ControlSource, GamepadTuning, HapticsEnabled, SteeringConfig,
+        TimeScale,
+    },
+    headlights::{headlight_system, headlight_toggle_system, HeadlightMode},
+    key_bindings::KeyBindings,
+    obstacle::{obstacle_collision_system, ObstacleCollision},
     physics::{
-        brake_wheel_system, driven_wheel_lookup_system, steering_curvature_system, steering_system,
-        suspension_system,
+        abs_log_system, abs_toggle_system, aero_drag_system, brake_wheel_system, fuel_system,
+        refuel_system, steering_curvature_system, steering_system, suspension_system,
+        suspension_tuning_system, transmission_system, AbsActivity, AbsEnabled, Aerodynamics,
+        FuelTank, SuspensionConfig, Transmission, Wind,
     },
-    tire::point_tire_system,
-    // Autonomous_control.rs sections commented out - Input conflict error with control.rs
-    // autonomous_control::{AutonomousPlugin, autonomous_control_system},  // update navigation and control
-
+    reset::{
+        capture_initial_state_system, out_of_bounds_system, reset_car_system, respawn_system,
+        InitialJointStates, OutOfBounds, SpawnPoint, SpawnPoints,
+    },
+    tire::{
+        point_tire_system, replace_tires_system, terrain_impact_system, tire_thermal_system,
+        tire_wear_system, TerrainImpact, TerrainImpactThresholds, TireThermal, TireWear,
+    },
+    vehicle_state::{g_force_system, vehicle_state_update_system, GForce, VehicleState},
 };
 
 use super::control::CarControl;
 use cameras::{
     camera_az_el::{self, camera_builder},
-    control::{camera_parent_system, camera_toggle_system}
+    capture::{screenshot_system, ScreenshotSettings},
+    control::{
+        camera_parent_system, camera_toggle_system, split_screen_system, CameraKeyBindings,
+        SplitScreen,
+    }
 };
 
 pub fn simulation_setup(app: &mut App) {
     app
-        // .add_plugins(AutonomousPlugin)  // update autonomous plugin
+        .add_plugins(AutonomousPlugin)
         .add_systems(
             PhysicsSchedule,
             (
                 steering_system, 
                 steering_curvature_system,
-                // autonomous_control_system,  // update autonomous system
+                vehicle_state_update_system,
             ).in_set(PhysicsSet::Pre),
         )
         .add_systems(
@@ -37,13 +59,77 @@ pub fn simulation_setup(app: &mut App) {
             (
                 suspension_system,
                 point_tire_system,
-                driven_wheel_lookup_system,
+                transmission_system,
                 brake_wheel_system,
+                aero_drag_system,
+                obstacle_collision_system,
             )
                 .in_set(PhysicsSet::Evaluate),
         )
-        .add_systems(Update, (user_control_system,))
-        .init_resource::<CarControl>();
+        // these run once per physics step, so they only run when the integrator stepped
+        .add_systems(
+            FixedUpdate,
+            (
+                fuel_system,
+                abs_log_system,
+                tire_thermal_system,
+                tire_wear_system,
+                terrain_impact_system,
+                out_of_bounds_system,
+            )
+                .run_if(resource_changed::<SimTime>()),
+        )
+        .add_systems(PostStartup, capture_initial_state_system)
+        .add_systems(
+            Update,
+            (
+                // only the active ControlSource writes CarControl
+                user_control_system.run_if(resource_equals(ControlSource::User)),
+                control_source_system,
+                sim_control_system,
+                time_scale_system,
+                abs_toggle_system,
+                steering_assist_toggle_system,
+                refuel_system,
+                replace_tires_system,
+                reset_car_system,
+                respawn_system,
+                g_force_system,
+                rumble_system,
+                headlight_toggle_system,
+                headlight_system,
+                vehicle_config_system,
+                suspension_tuning_system,
+            ),
+        )
+        .init_resource::<CarControl>()
+        .init_resource::<ControlSource>()
+        .init_resource::<KeyBindings>()
+        .init_resource::<VehicleConfig>()
+        .init_resource::<SuspensionConfig>()
+        .init_resource::<TimeScale>()
+        .init_resource::<SteeringConfig>()
+        .init_resource::<GamepadTuning>()
+        .init_resource::<HapticsEnabled>()
+        .init_resource::<HeadlightMode>()
+        .init_resource::<Transmission>()
+        .init_resource::<AbsEnabled>()
+        .init_resource::<AbsActivity>()
+        .init_resource::<Aerodynamics>()
+        .init_resource::<Wind>()
+        .init_resource::<FuelTank>()
+        .init_resource::<TireThermal>()
+        .init_resource::<TireWear>()
+        .init_resource::<TerrainImpactThresholds>()
+        .init_resource::<ContactConfig>()
+        .add_event::<TerrainImpact>()
+        .init_resource::<ObstacleCollision>()
+        .init_resource::<SpawnPoint>()
+        .init_resource::<SpawnPoints>()
+        .init_resource::<OutOfBounds>()
+        .init_resource::<InitialJointStates>()
+        .init_resource::<VehicleState>()
+        .init_resource::<GForce>();
 }
 
 pub fn camera_setup(app: &mut App) {
@@ -61,5 +147,16 @@ pub fn camera_setup(app: &mut App) {
             camera_az_el::UpDirection::Z,
         ),
     )
-    .add_systems(Update, (camera_az_el::az_el_camera, camera_parent_system, camera_toggle_system)); // setup the camera
+    .add_systems(Update, (camera_az_el::az_el_camera, camera_parent_system, camera_toggle_system, split_screen_system)) // setup the camera
+    .add_systems(
+        Update,
+        (camera_focus_system, camera_terrain_system)
+            .chain()
+            .after(camera_az_el::az_el_camera),
+    )
+    .init_resource::<CameraTerrainConstraint>()
+    .add_systems(Update, screenshot_system)
+    .init_resource::<ScreenshotSettings>()
+    .init_resource::<CameraKeyBindings>()
+    .init_resource::<SplitScreen>();
 }
\ No newline at end of file
@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+use grid_terrain::GridTerrain;
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    environment::ContactConfig, line_draw::snap_point_to_terrain, tire::TireContact,
+};
+
+// Skid marks left behind by sliding tires.
+// - slip_threshold: combined normalized slip above which a tire leaves a mark (1 = grip limit)
+// - max_marks: the oldest marks are removed past this count, so entities don't pile up
+// - width: width of each mark (m)
+// - min_spacing: distance a tire travels before the next piece of mark is laid (m)
+#[derive(Resource)]
+pub struct SkidMarks {
+    pub slip_threshold: f64,
+    pub max_marks: usize,
+    pub width: f32,
+    pub min_spacing: f32,
+    marks: VecDeque<Entity>,
+    last_points: HashMap<Entity, Vec3>,
+    mesh: Option<Handle<Mesh>>,
+    material: Option<Handle<StandardMaterial>>,
+}
+
+impl Default for SkidMarks {
+    fn default() -> Self {
+        Self {
+            slip_threshold: 1.0,
+            max_marks: 500,
+            width: 0.2,
+            min_spacing: 0.15,
+            marks: VecDeque::new(),
+            last_points: HashMap::new(),
+            mesh: None,
+            material: None,
+        }
+    }
+}
+
+// A mark is only drawn between two contact points this close together
+// (a longer jump means the tire was airborne or the car was reset)
+const MAX_MARK_LENGTH: f32 = 2.0;
+
+// Lays dark strips on the terrain behind every tire sliding past its grip limit.
+// Each strip joins the tire's previous contact point to the current one, both snapped
+// to the terrain like the line-draw tool does.
+pub fn skid_mark_system(
+    mut commands: Commands,
+    tires: Query<(Entity, &TireContact)>,
+    mut skid_marks: ResMut<SkidMarks>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    grid_terrain: Res<GridTerrain>,
+    contact_config: Res<ContactConfig>,
+) {
+    let skid_marks = skid_marks.as_mut();
+
+    // all marks share one mesh and one material
+    let mesh = skid_marks
+        .mesh
+        .get_or_insert_with(|| meshes.add(Mesh::from(shape::Box::new(1.0, 1.0, 0.01))))
+        .clone();
+    let material = skid_marks
+        .material
+        .get_or_insert_with(|| {
+            materials.add(StandardMaterial {
+                base_color: Color::rgb(0.05, 0.05, 0.05),
+                perceptual_roughness: 1.0,
+                ..default()
+            })
+        })
+        .clone();
+
+    for (tire, contact) in tires.iter() {
+        let position = match contact.position {
+            Some(position) if contact.slip > skid_marks.slip_threshold => position,
+            _ => {
+                skid_marks.last_points.remove(&tire);
+                continue;
+            }
+        };
+        let point = snap_point_to_terrain(position, &grid_terrain, &contact_config).unwrap_or(position);
+
+        let Some(last_point) = skid_marks.last_points.get(&tire).copied() else {
+            skid_marks.last_points.insert(tire, point);
+            continue;
+        };
+        let segment = point - last_point;
+        let length = segment.length();
+        if length < skid_marks.min_spacing {
+            continue;
+        }
+        skid_marks.last_points.insert(tire, point);
+        if length > MAX_MARK_LENGTH {
+            continue;
+        }
+
+        let entity = commands
+            .spawn(PbrBundle {
+                transform: Transform {
+                    translation: last_point + 0.5 * segment,
+                    rotation: Quat::from_rotation_z(segment.y.atan2(segment.x)),
+                    scale: Vec3::new(length, skid_marks.width, 1.0),
+                },
+                mesh: mesh.clone(),
+                material: material.clone(),
+                ..default()
+            })
+            .id();
+        skid_marks.marks.push_back(entity);
+
+        // ring buffer: remove the oldest marks
+        while skid_marks.marks.len() > skid_marks.max_marks {
+            if let Some(oldest) = skid_marks.marks.pop_front() {
+                commands.entity(oldest).despawn();
+            }
+        }
+    }
+}
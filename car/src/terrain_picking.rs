@@ -0,0 +1,245 @@
+use bevy::prelude::*;
+use cameras::camera_az_el::{AzElCamera, PointerOverUi};
+use grid_terrain::{
+    mirror::Mirror, plane::Plane, rotate::Rotate, slope::Slope, step::Step, GridElement,
+    GridTerrain, TerrainCell,
+};
+use rigid_body::sva::Vector;
+
+use crate::{environment::ContactConfig, key_bindings::KeyBindings, line_draw::screen_to_world_ray};
+
+// How far from the camera a click still picks the terrain (m)
+const PICK_DISTANCE: f64 = 200.;
+
+// Pieces the terrain editor can place
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TerrainPalette {
+    #[default]
+    Plane,
+    Step,
+    Slope,
+}
+
+impl TerrainPalette {
+    pub fn next(&self) -> Self {
+        match self {
+            TerrainPalette::Plane => TerrainPalette::Step,
+            TerrainPalette::Step => TerrainPalette::Slope,
+            TerrainPalette::Slope => TerrainPalette::Plane,
+        }
+    }
+
+    // Piece filling a cell of `size`; steps and slopes assume square cells
+    // (slopes can't be mirrored, the mirror is ignored for them)
+    pub fn build(
+        &self,
+        size: [f64; 2],
+        height: f64,
+        rotate: Rotate,
+        mirror: Mirror,
+    ) -> Box<dyn GridElement> {
+        match self {
+            TerrainPalette::Plane => Box::new(Plane {
+                size,
+                subdivisions: 1,
+                height: 0.,
+            }),
+            TerrainPalette::Step => Box::new(Step {
+                size: size[0],
+                height,
+                rotate,
+                mirror,
+            }),
+            TerrainPalette::Slope => Box::new(Slope {
+                size: size[0],
+                height,
+                rotate,
+            }),
+        }
+    }
+}
+
+// Picking and editing terrain cells with the mouse, a lightweight track editor.
+// Press 'F7' (KeyBindings::pick_terrain) to toggle picking, then right click the terrain:
+// the cell, its piece and the piece's parameters are printed and the cell is tinted
+// until another cell is picked or picking is switched off.
+// While picking, the selected cell can be edited (only that cell is re-meshed):
+// - 'F8' (KeyBindings::terrain_palette): next piece in the palette
+// - 'Insert' (KeyBindings::place_terrain): put the palette piece in the cell
+// - 'Delete' (KeyBindings::remove_terrain): flatten the cell
+// - ',' / ''' (KeyBindings::rotate_terrain / mirror_terrain): turn / mirror the palette
+//   piece and put it in the cell
+// Right clicks also place line points while line draw is on, so use one tool at a time.
+// - enabled: right clicks pick cells and the edit keys work
+// - tint: color of the picked cell
+// - selected: picked cell [column, row]
+// - palette, height, rotate, mirror: the piece placed by the edit keys
+#[derive(Resource)]
+pub struct TerrainPicker {
+    pub enabled: bool,
+    pub tint: Color,
+    pub selected: Option<[usize; 2]>,
+    pub palette: TerrainPalette,
+    pub height: f64,
+    pub rotate: Rotate,
+    pub mirror: Mirror,
+    // tinted cell mesh and the material it had before it was tinted
+    highlight: Option<(Entity, Handle<StandardMaterial>)>,
+}
+
+impl Default for TerrainPicker {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tint: Color::rgb(1.0, 0.6, 0.1),
+            selected: None,
+            palette: TerrainPalette::default(),
+            height: 0.4,
+            rotate: Rotate::Zero,
+            mirror: Mirror::None,
+            highlight: None,
+        }
+    }
+}
+
+pub fn terrain_pick_system(
+    mut commands: Commands,
+    windows: Query<&Window>,
+    mouse: Res<Input<MouseButton>>,
+    keyboard: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<AzElCamera>>,
+    pointer_over_ui: Res<PointerOverUi>,
+    mut grid_terrain: ResMut<GridTerrain>,
+    contact: Res<ContactConfig>,
+    mut picker: ResMut<TerrainPicker>,
+    cells: Query<&Handle<StandardMaterial>, With<TerrainCell>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if keyboard.just_pressed(keys.pick_terrain) {
+        picker.enabled = !picker.enabled;
+        println!("Terrain picking {}", if picker.enabled { "enabled" } else { "disabled" });
+        if !picker.enabled {
+            picker.selected = None;
+        }
+    }
+
+    if picker.enabled {
+        if !pointer_over_ui.check() && mouse.just_pressed(MouseButton::Right) {
+            if let Some(cell) = pick_cell(&windows, &camera_query, &grid_terrain, &contact) {
+                picker.selected = Some(cell);
+            }
+        }
+        let edited = edit_selected_cell(
+            &keyboard,
+            &keys,
+            &mut picker,
+            &mut grid_terrain,
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+        );
+        // the edited cell gets its plain material back at the end of the frame,
+        // it is tinted again on the next
+        if edited {
+            picker.highlight = None;
+            return;
+        }
+    }
+
+    // keep the tint on the selected cell's current mesh entity
+    let selected = picker
+        .selected
+        .and_then(|[column, row]| grid_terrain.cell_entity(column, row));
+    if picker.highlight.as_ref().map(|(entity, _)| *entity) == selected {
+        return;
+    }
+    if let Some((entity, material)) = picker.highlight.take() {
+        if cells.contains(entity) {
+            commands.entity(entity).insert(material);
+        }
+    }
+    let Some(entity) = selected else { return; };
+    let Ok(material) = cells.get(entity) else { return; };
+    // tint a copy of the cell's material, the original is shared with other cells
+    let mut tinted = materials.get(material).cloned().unwrap_or_default();
+    tinted.base_color = picker.tint;
+    commands.entity(entity).insert(materials.add(tinted));
+    picker.highlight = Some((entity, material.clone()));
+}
+
+// Cell under the cursor, printing what is in it
+fn pick_cell(
+    windows: &Query<&Window>,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<AzElCamera>>,
+    grid_terrain: &GridTerrain,
+    contact: &ContactConfig,
+) -> Option<[usize; 2]> {
+    let window = windows.get_single().ok()?;
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+    let cursor_pos = window.cursor_position()?;
+    let (origin, dir) = screen_to_world_ray(cursor_pos, window, camera, camera_transform)?;
+
+    let origin = Vector::new(origin.x as f64, origin.y as f64, origin.z as f64);
+    let dir = Vector::new(dir.x as f64, dir.y as f64, dir.z as f64);
+    let distance = grid_terrain.raycast(origin, dir, PICK_DISTANCE, contact.ray_step as f64)?;
+    // the cell under the hit, a hit on the ground around the grid picks nothing
+    let point = origin + dir.normalize() * distance;
+    let Some([column, row]) = grid_terrain.cell_at(&point) else {
+        println!("No terrain cell at ({:.1}, {:.1})", point.x, point.y);
+        return None;
+    };
+    if let Some(element) = grid_terrain.element(column, row) {
+        println!("Cell [{}, {}]: {}", column, row, element.description());
+    }
+    Some([column, row])
+}
+
+// Edit keys: change the palette piece and put it (or a flat plane) in the selected cell.
+// True if the cell was replaced.
+fn edit_selected_cell(
+    keyboard: &Input<KeyCode>,
+    keys: &KeyBindings,
+    picker: &mut TerrainPicker,
+    grid_terrain: &mut ResMut<GridTerrain>, // only changed when a cell is replaced
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+) -> bool {
+    if keyboard.just_pressed(keys.terrain_palette) {
+        picker.palette = picker.palette.next();
+        println!("Terrain palette: {:?}", picker.palette);
+    }
+    let mut place = keyboard.just_pressed(keys.place_terrain);
+    if keyboard.just_pressed(keys.rotate_terrain) {
+        picker.rotate = picker.rotate.next();
+        place = true;
+    }
+    if keyboard.just_pressed(keys.mirror_terrain) {
+        picker.mirror = picker.mirror.next();
+        place = true;
+    }
+    let remove = keyboard.just_pressed(keys.remove_terrain);
+
+    let Some([column, row]) = picker.selected else { return false; };
+    let palette = match (place, remove) {
+        (_, true) => TerrainPalette::Plane,
+        (true, false) => picker.palette,
+        (false, false) => return false,
+    };
+    let element = palette.build(
+        grid_terrain.cell_size(),
+        picker.height,
+        picker.rotate,
+        picker.mirror,
+    );
+    let description = element.description();
+    let replaced = grid_terrain
+        .replace_element(column, row, element, commands, meshes, materials)
+        .is_some();
+    if replaced {
+        println!("Cell [{}, {}]: {}", column, row, description);
+    }
+    replaced
+}
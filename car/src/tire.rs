@@ -1,10 +1,19 @@
 use bevy::prelude::*;
+use bevy_integrator::SimTime;
 use grid_terrain::GridTerrain;
 use rigid_body::{
     joint::Joint,
     sva::{Force, Vector},
 };
 
+use std::collections::HashMap;
+
+use crate::{
+    environment::ContactConfig,
+    key_bindings::KeyBindings,
+    weather::{SurfaceWetness, Weather},
+};
+
 #[derive(Component)]
 pub struct PointTire {
     joint_entity: Entity,
@@ -92,13 +101,284 @@ impl PointTire {
     }
 }
 
+// Temperature of a tire's tread (°C), on the same entity as its PointTire.
+// heat_input is the power (W) turned into heat by the tire at the last force evaluation.
+#[derive(Component)]
+pub struct TireTemperature {
+    pub temperature: f64,
+    pub heat_input: f64,
+}
+
+impl Default for TireTemperature {
+    fn default() -> Self {
+        Self {
+            temperature: 20.,
+            heat_input: 0.,
+        }
+    }
+}
+
+// Parameters of the tire thermal model
+// - optimal_temperature: tread temperature with full grip (°C)
+// - window: how far from optimal (°C) the grip drops to min_grip
+// - min_grip: grip factor of a very cold or overheated tire
+// - heat_capacity: energy to warm the tread by one degree (J/°C)
+// - cooling_rate: rate the tread cools toward ambient (1/s)
+// - rolling_loss: fraction of the load * rolling speed turned into heat
+#[derive(Resource, Clone)]
+pub struct TireThermal {
+    pub optimal_temperature: f64,
+    pub window: f64,
+    pub min_grip: f64,
+    pub heat_capacity: f64,
+    pub cooling_rate: f64,
+    pub rolling_loss: f64,
+}
+
+impl Default for TireThermal {
+    fn default() -> Self {
+        Self {
+            optimal_temperature: 90.,
+            window: 60.,
+            min_grip: 0.75,
+            heat_capacity: 4000.,
+            cooling_rate: 0.017,
+            rolling_loss: 0.01,
+        }
+    }
+}
+
+impl TireThermal {
+    // Grip multiplier for a tread temperature, 1 at the optimal temperature,
+    // falling off (quadratically) to min_grip when too cold or too hot
+    pub fn grip_factor(&self, temperature: f64) -> f64 {
+        let offset = (temperature - self.optimal_temperature) / self.window;
+        (1. - (1. - self.min_grip) * offset * offset).max(self.min_grip)
+    }
+}
+
+// Air temperature (°C) the tires cool toward
+pub fn ambient_temperature(weather: Weather) -> f64 {
+    match weather {
+        Weather::Sunny => 25.,
+        Weather::Cloudy => 18.,
+        Weather::Rain => 12.,
+        Weather::Night => 10.,
+    }
+}
+
+// Heats each tire with the power from slip and rolling under load, and cools it toward
+// ambient. Runs once per physics step (in FixedUpdate), heat_input comes from point_tire_system.
+pub fn tire_thermal_system(
+    mut tire_query: Query<&mut TireTemperature>,
+    thermal: Res<TireThermal>,
+    weather: Option<Res<Weather>>,
+    sim_time: Res<SimTime>,
+) {
+    let ambient = weather.map_or(20., |weather| ambient_temperature(*weather));
+    for mut tire in tire_query.iter_mut() {
+        let heating = tire.heat_input / thermal.heat_capacity;
+        let cooling = thermal.cooling_rate * (tire.temperature - ambient);
+        tire.temperature += (heating - cooling) * sim_time.dt;
+    }
+}
+
+// Where a tire touches the terrain and how hard it is sliding, for visual effects.
+// - position: active-weighted average contact point in absolute coordinates (None when airborne)
+// - slip: combined normalized slip, above 1 the tire is past its grip limit
+// - penetration: deepest interference of the tire's points with the terrain (m)
+// - normal: active-weighted average terrain normal at the contact (zero when airborne)
+// - closing_speed: fastest speed of the wheel center into the terrain at a contact (m/s)
+#[derive(Component, Default)]
+pub struct TireContact {
+    pub position: Option<Vec3>,
+    pub slip: f64,
+    pub penetration: f64,
+    pub normal: Vec3,
+    pub closing_speed: f64,
+}
+
+// A hard hit of the car against the terrain, sent once per hit (not every step of it)
+// by terrain_impact_system, from the tire with the deepest contact
+// - magnitude: tire penetration (m)
+// - speed: closing speed into the terrain (m/s)
+#[derive(Event, Clone, Debug)]
+pub struct TerrainImpact {
+    pub tire: Entity,
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub magnitude: f64,
+    pub speed: f64,
+}
+
+// What counts as a hit: any tire deeper than `penetration` (m) or closing faster than
+// `closing_speed` (m/s). The next impact can only fire once every tire is back under both.
+#[derive(Resource)]
+pub struct TerrainImpactThresholds {
+    pub penetration: f64,
+    pub closing_speed: f64,
+}
+
+impl Default for TerrainImpactThresholds {
+    fn default() -> Self {
+        Self {
+            penetration: 0.04,
+            closing_speed: 2.0,
+        }
+    }
+}
+
+// Sends a TerrainImpact when the tires cross the thresholds (runs once per physics step)
+pub fn terrain_impact_system(
+    tires: Query<(Entity, &TireContact)>,
+    thresholds: Res<TerrainImpactThresholds>,
+    mut impacts: EventWriter<TerrainImpact>,
+    mut in_impact: Local<bool>,
+) {
+    let hard = |contact: &TireContact| {
+        contact.penetration > thresholds.penetration
+            || contact.closing_speed > thresholds.closing_speed
+    };
+    let deepest = tires
+        .iter()
+        .filter(|(_, contact)| contact.position.is_some() && hard(contact))
+        .max_by(|(_, a), (_, b)| a.penetration.total_cmp(&b.penetration));
+    match deepest {
+        Some((tire, contact)) => {
+            if !*in_impact {
+                impacts.send(TerrainImpact {
+                    tire,
+                    position: contact.position.unwrap_or_default(),
+                    normal: contact.normal,
+                    magnitude: contact.penetration,
+                    speed: contact.closing_speed,
+                });
+            }
+            *in_impact = true;
+        }
+        None => *in_impact = false,
+    }
+}
+
+// Tread wear of every tire, keyed by the PointTire entity
+// - tread: remaining tread, 1 for a new tire down to 0 for a worn out one
+// - slip_power: sliding power (W) of each tire at the last force evaluation
+// - wear_per_joule: tread lost per joule of sliding energy
+// - worn_grip: grip factor of a fully worn tire
+#[derive(Resource, Clone)]
+pub struct TireWear {
+    pub tread: HashMap<Entity, f64>,
+    pub slip_power: HashMap<Entity, f64>,
+    pub wear_per_joule: f64,
+    pub worn_grip: f64,
+}
+
+impl Default for TireWear {
+    fn default() -> Self {
+        Self {
+            tread: HashMap::new(),
+            slip_power: HashMap::new(),
+            wear_per_joule: 4e-7, // about 10 minutes of hard sliding per set of tires
+            worn_grip: 0.6,
+        }
+    }
+}
+
+impl TireWear {
+    // Remaining tread of a tire (tires that haven't slid yet are new)
+    pub fn tread(&self, tire: Entity) -> f64 {
+        self.tread.get(&tire).copied().unwrap_or(1.)
+    }
+
+    // Grip multiplier, from 1 for a new tire down to worn_grip
+    pub fn grip_factor(&self, tire: Entity) -> f64 {
+        self.worn_grip + (1. - self.worn_grip) * self.tread(tire)
+    }
+
+    // Average remaining tread over all tires
+    pub fn average_tread(&self) -> f64 {
+        if self.tread.is_empty() {
+            return 1.;
+        }
+        self.tread.values().sum::<f64>() / self.tread.len() as f64
+    }
+
+    // Fits a new set of tires
+    pub fn replace(&mut self) {
+        self.tread.clear();
+        self.slip_power.clear();
+    }
+}
+
+// Wears each tire by its sliding energy, once per physics step (in FixedUpdate)
+pub fn tire_wear_system(mut wear: ResMut<TireWear>, sim_time: Res<SimTime>) {
+    let wear = wear.as_mut();
+    for (tire, slip_power) in wear.slip_power.iter() {
+        let tread = wear.tread.entry(*tire).or_insert(1.);
+        *tread = (*tread - slip_power * wear.wear_per_joule * sim_time.dt).max(0.);
+    }
+}
+
+// Press 'G' (KeyBindings::replace_tires) to fit new tires
+pub fn replace_tires_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    mut wear: ResMut<TireWear>,
+) {
+    if keyboard_input.just_pressed(keys.replace_tires) {
+        wear.replace();
+        println!("Tires replaced");
+    }
+}
+
+// Normalized (per unit of normal force and friction) longitudinal and lateral tire forces
+// for the normalized slips, limited by the friction circle: together they can't exceed
+// the grip, so a locked or spinning wheel has little grip left for cornering. This applies
+// to every tire, with or without the handbrake: braking or accelerating hard while turning
+// costs cornering grip (and the other way around), as on real tires.
+pub fn friction_circle(long_slip: f64, lat_slip: f64) -> (f64, f64) {
+    let long = long_slip.clamp(-1., 1.);
+    let lat = lat_slip.clamp(-1., 1.);
+    let combined = long.hypot(lat);
+    if combined > 1. {
+        (long / combined, lat / combined)
+    } else {
+        (long, lat)
+    }
+}
+
 pub fn point_tire_system(
-    mut tire_query: Query<&mut PointTire>,
+    mut tire_query: Query<(
+        Entity,
+        &mut PointTire,
+        Option<&mut TireTemperature>,
+        Option<&mut TireContact>,
+    )>,
     mut query_joints: Query<&mut Joint>,
     grid_terrain: Res<GridTerrain>,
+    contact_config: Res<ContactConfig>,
+    thermal: Res<TireThermal>,
+    mut wear: ResMut<TireWear>,
+    wetness: Option<Res<SurfaceWetness>>,
 ) {
     let terrain = grid_terrain.as_ref();
-    for mut tire in tire_query.iter_mut() {
+    let wet_grip = wetness.map_or(1., |wetness| wetness.grip_factor());
+    for (tire_entity, mut tire, mut temperature, mut tire_contact) in tire_query.iter_mut() {
+        // a tire outside its temperature window, with worn tread or on a wet track
+        // has less grip
+        let grip = temperature
+            .as_ref()
+            .map_or(1., |temperature| thermal.grip_factor(temperature.temperature))
+            * wear.grip_factor(tire_entity)
+            * wet_grip;
+        let mut heat_input = 0.;
+        let mut slip_power = 0.;
+        let mut contact_position = Vector::zeros();
+        let mut contact_slip = 0.;
+        let mut penetration: f64 = 0.;
+        let mut contact_normal = Vector::zeros();
+        let mut closing_speed: f64 = 0.;
+        let mut total_active = 0.;
         if let Ok([mut joint, parent]) =
             query_joints.get_many_mut([tire.joint_entity, tire.joint_parent])
         {
@@ -115,13 +395,19 @@ pub fn point_tire_system(
             let mut active_points = 0.0;
             for point in tire.points.iter() {
                 let point_abs = x0i.transform_point(*point); // point in absolute coordinates
-                if let Some(contact) = terrain.interference(point_abs) {
+                if let Some(mut contact) = terrain.interference(point_abs) {
+                    contact.magnitude -= contact_config.penetration_allowance;
+                    if contact.magnitude <= 0. {
+                        continue;
+                    }
                     let active = (contact.magnitude / tire.activation_length).clamp(0.0, 1.0);
                     contacts.push((contact, point_abs, active));
                     active_points += active;
                 }
             }
 
+            total_active = active_points;
+
             // calculate forces for each contact point
             for (contact, point_abs, active) in contacts {
                 // critical directions - all in absolute coordinates
@@ -176,16 +462,33 @@ pub fn point_tire_system(
                 let normal_force = normal_force_magnitude * contact.normal;
 
                 // in plane forces
-                let normalized_long_force =
-                    (slip_ratio_point * tire.normalized_slip_stiffness).clamp(-1., 1.);
-                let normalized_lat_force =
-                    (slip_angle_point * tire.normalized_slip_stiffness).clamp(-1., 1.);
+                let (normalized_long_force, normalized_lat_force) = friction_circle(
+                    slip_ratio_point * tire.normalized_slip_stiffness,
+                    slip_angle_point * tire.normalized_slip_stiffness,
+                );
 
-                let long_force =
-                    normalized_long_force * normal_force_magnitude * tire.coefficient_of_friction;
+                let friction =
+                    tire.coefficient_of_friction * grip * terrain.friction_at(&contact.position);
+                let long_force = normalized_long_force * normal_force_magnitude * friction;
 
-                let lat_force =
-                    normalized_lat_force * normal_force_magnitude * tire.coefficient_of_friction;
+                let lat_force = normalized_lat_force * normal_force_magnitude * friction;
+
+                // power lost to sliding wears the tread, plus rolling under load heats it
+                let point_slip_power = active
+                    * ((long_force * ground_speed_long).abs() + (lat_force * ground_speed_lat).abs());
+                slip_power += point_slip_power;
+                contact_position += active * contact.position;
+                penetration = penetration.max(contact.magnitude);
+                contact_normal += active * contact.normal;
+                closing_speed = closing_speed.max(-normal_speed_parent);
+                contact_slip += active
+                    * slip_ratio_point.hypot(slip_angle_point)
+                    * tire.normalized_slip_stiffness;
+                heat_input += point_slip_power
+                    + active
+                        * thermal.rolling_loss
+                        * normal_force_magnitude.max(0.)
+                        * ground_speed_parent_long.abs();
 
                 let plane_force = lat_force * contact_lateral + long_force * contact_longitudinal;
 
@@ -203,5 +506,129 @@ pub fn point_tire_system(
             // apply the force to the joint
             joint.f_ext += f_ext;
         }
+        if let Some(temperature) = temperature.as_mut() {
+            temperature.heat_input = heat_input;
+        }
+        wear.slip_power.insert(tire_entity, slip_power);
+        if let Some(tire_contact) = tire_contact.as_mut() {
+            if total_active > 0. {
+                let position = contact_position / total_active;
+                tire_contact.position =
+                    Some(Vec3::new(position.x as f32, position.y as f32, position.z as f32));
+                tire_contact.slip = contact_slip / total_active;
+                let normal = contact_normal / total_active;
+                tire_contact.normal =
+                    Vec3::new(normal.x as f32, normal.y as f32, normal.z as f32).normalize_or_zero();
+            } else {
+                tire_contact.position = None;
+                tire_contact.slip = 0.;
+                tire_contact.normal = Vec3::ZERO;
+            }
+            tire_contact.penetration = penetration;
+            tire_contact.closing_speed = closing_speed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locked_wheel_has_less_lateral_force_than_rolling() {
+        // same slip angle, the locked wheel slides at the full vehicle speed (slip ratio -1,
+        // normalized slip stiffness 2)
+        let slip_angle = 0.15 * 2.;
+        let (_, rolling) = friction_circle(0., slip_angle);
+        let (locked_long, locked) = friction_circle(-2., slip_angle);
+        assert!(locked.abs() < rolling.abs());
+        assert!((locked_long.hypot(locked) - 1.).abs() < 1e-12);
+        assert_eq!(locked.signum(), rolling.signum());
+    }
+
+    #[test]
+    fn friction_circle_leaves_small_slips_alone() {
+        assert_eq!(friction_circle(0.3, -0.4), (0.3, -0.4));
+    }
+
+    #[test]
+    fn tire_temperature_plateaus_under_constant_slip() {
+        // a tire sliding at a constant slip turns a constant 3 kW into heat
+        let heat_input = 3000.;
+        let thermal = TireThermal::default();
+        let dt = 0.05;
+        let mut app = App::new();
+        app.insert_resource(thermal.clone())
+            .insert_resource(SimTime::new(dt, 0., None))
+            .add_systems(Update, tire_thermal_system);
+        let tire = app
+            .world
+            .spawn(TireTemperature {
+                heat_input,
+                ..default()
+            })
+            .id();
+        let temperature = |app: &App| app.world.get::<TireTemperature>(tire).unwrap().temperature;
+
+        // heating balances cooling at ambient + heat / (heat capacity * cooling rate)
+        let plateau = 20. + heat_input / (thermal.heat_capacity * thermal.cooling_rate);
+        let mut last = temperature(&app);
+        for _ in 0..(600. / dt) as usize {
+            app.update();
+            let current = temperature(&app);
+            assert!(current >= last && current < plateau, "{} °C", current);
+            last = current;
+        }
+        // after 10 minutes (10 time constants) it has leveled off at the plateau
+        assert!((last - plateau).abs() < 0.01 * (plateau - 20.), "{} != {}", last, plateau);
+        for _ in 0..(60. / dt) as usize {
+            app.update();
+        }
+        assert!((temperature(&app) - last).abs() < 0.01, "still rising");
+    }
+
+
+    #[test]
+    fn hard_landing_sends_one_impact() {
+        let mut app = App::new();
+        app.add_event::<TerrainImpact>()
+            .init_resource::<TerrainImpactThresholds>()
+            .add_systems(Update, terrain_impact_system);
+        let tire = app.world.spawn(TireContact::default()).id();
+        // an airborne tire never counts, however deep its last contact was
+        app.world.spawn(TireContact {
+            penetration: 1.,
+            ..default()
+        });
+
+        let mut reader = app.world.resource::<Events<TerrainImpact>>().get_reader();
+        let mut run = |app: &mut App, penetration: f64, closing_speed: f64| {
+            *app.world.get_mut::<TireContact>(tire).unwrap() = TireContact {
+                position: Some(Vec3::ZERO),
+                penetration,
+                normal: Vec3::Z,
+                closing_speed,
+                ..default()
+            };
+            let mut impacts = Vec::new();
+            for _ in 0..5 {
+                app.update();
+                let events = app.world.resource::<Events<TerrainImpact>>();
+                impacts.extend(reader.iter(events).cloned());
+            }
+            impacts
+        };
+
+        // a landing deeper than the threshold for several steps is one impact
+        let impacts = run(&mut app, 0.06, 0.5);
+        assert_eq!(impacts.len(), 1);
+        assert_eq!(impacts[0].tire, tire);
+        assert_eq!(impacts[0].magnitude, 0.06);
+        // settled back under both thresholds
+        assert!(run(&mut app, 0.01, 0.5).is_empty());
+        // the next hit, this time by closing speed, is a new impact
+        let impacts = run(&mut app, 0.01, 3.);
+        assert_eq!(impacts.len(), 1);
+        assert_eq!(impacts[0].speed, 3.);
     }
 }
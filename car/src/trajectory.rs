@@ -0,0 +1,212 @@
+use bevy::{app::AppExit, prelude::*};
+use bevy_integrator::SimTime;
+use serde_json::json;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::{key_bindings::KeyBindings, vehicle_state::VehicleState};
+
+// Car path sampled during the run, for plotting outside the sim.
+// Press 'F10' (KeyBindings::export_trajectory) to write it, it is also written when the
+// app exits. Two files are written next to each other:
+// - <file_name>.csv: one row per sample, "sim_time,x,y,z,heading,speed"
+//   (s, m, m, m, rad, m/s)
+// - <file_name>.geojson: a FeatureCollection with one LineString of the x, y positions
+//   (sim coordinates in m, not longitude/latitude), with the sim time, heading and speed
+//   of every point as properties
+// A reset of the sim time (SimTime::reset) starts a new trajectory.
+// - sample_interval: sim time between samples (s)
+// - export_on_exit: write the files when the app exits
+#[derive(Resource)]
+pub struct TrajectoryRecorder {
+    pub file_name: String,
+    pub sample_interval: f64,
+    pub export_on_exit: bool,
+    samples: Vec<TrajectorySample>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TrajectorySample {
+    pub sim_time: f64, // s
+    pub position: Vec3,
+    pub heading: f32, // rad
+    pub speed: f32,   // m/s
+}
+
+impl Default for TrajectoryRecorder {
+    fn default() -> Self {
+        Self::new("trajectory".to_string())
+    }
+}
+
+impl TrajectoryRecorder {
+    pub fn new(file_name: String) -> Self {
+        Self {
+            file_name,
+            sample_interval: 0.1,
+            export_on_exit: true,
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn samples(&self) -> &[TrajectorySample] {
+        &self.samples
+    }
+
+    // Adds a sample if `sample_interval` has passed since the last one
+    pub fn record(&mut self, sim_time: f64, state: &VehicleState) {
+        if let Some(last) = self.samples.last() {
+            if sim_time < last.sim_time {
+                self.samples.clear();
+            } else if sim_time - last.sim_time < self.sample_interval {
+                return;
+            }
+        }
+        self.samples.push(TrajectorySample {
+            sim_time,
+            position: state.position,
+            heading: state.heading,
+            speed: state.speed,
+        });
+    }
+
+    // Writes <file_name>.csv and <file_name>.geojson
+    pub fn export(&self) -> io::Result<()> {
+        let mut csv = BufWriter::new(File::create(format!("{}.csv", self.file_name))?);
+        self.write_csv(&mut csv)?;
+        csv.flush()?;
+        let mut geojson = BufWriter::new(File::create(format!("{}.geojson", self.file_name))?);
+        self.write_geojson(&mut geojson)?;
+        geojson.flush()
+    }
+
+    pub fn write_csv(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "sim_time,x,y,z,heading,speed")?;
+        for sample in &self.samples {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{}",
+                sample.sim_time,
+                sample.position.x,
+                sample.position.y,
+                sample.position.z,
+                sample.heading,
+                sample.speed
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn write_geojson(&self, writer: &mut impl Write) -> io::Result<()> {
+        let coordinates: Vec<[f32; 2]> = self
+            .samples
+            .iter()
+            .map(|sample| [sample.position.x, sample.position.y])
+            .collect();
+        let collection = json!({
+            "type": "FeatureCollection",
+            "features": [{
+                "type": "Feature",
+                "geometry": {
+                    "type": "LineString",
+                    "coordinates": coordinates,
+                },
+                "properties": {
+                    "sim_time": self.samples.iter().map(|sample| sample.sim_time).collect::<Vec<_>>(),
+                    "heading": self.samples.iter().map(|sample| sample.heading).collect::<Vec<_>>(),
+                    "speed": self.samples.iter().map(|sample| sample.speed).collect::<Vec<_>>(),
+                },
+            }],
+        });
+        serde_json::to_writer_pretty(&mut *writer, &collection)?;
+        writeln!(writer)
+    }
+
+    fn export_and_report(&self) {
+        match self.export() {
+            Ok(()) => println!(
+                "Trajectory ({} samples) written to {}.csv and {}.geojson",
+                self.samples.len(),
+                self.file_name,
+                self.file_name
+            ),
+            Err(e) => println!("Failed to write trajectory {}: {}", self.file_name, e),
+        }
+    }
+}
+
+// Samples the car state (runs once per physics step)
+pub fn trajectory_record_system(
+    sim_time: Res<SimTime>,
+    vehicle_state: Res<VehicleState>,
+    mut recorder: ResMut<TrajectoryRecorder>,
+) {
+    // the vehicle state isn't filled in before the first step
+    if sim_time.index == 0 {
+        return;
+    }
+    recorder.record(sim_time.time(), &vehicle_state);
+}
+
+// Writes the trajectory on the export key and when the app is closing (runs in Last)
+pub fn trajectory_export_system(
+    keyboard_input: Option<Res<Input<KeyCode>>>,
+    keys: Res<KeyBindings>,
+    recorder: Res<TrajectoryRecorder>,
+    exit: EventReader<AppExit>,
+) {
+    let pressed = keyboard_input.map_or(false, |input| input.just_pressed(keys.export_trajectory));
+    if pressed || (recorder.export_on_exit && !exit.is_empty()) {
+        recorder.export_and_report();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripted_run_is_written_as_csv() {
+        let dt = 0.01;
+        let mut app = App::new();
+        app.init_resource::<VehicleState>()
+            .insert_resource(SimTime::new(dt, 0., None))
+            .insert_resource(TrajectoryRecorder {
+                // just under 10 steps, so float error in the sim time can't skip a sample
+                sample_interval: 0.099,
+                ..default()
+            })
+            .add_systems(Update, trajectory_record_system.run_if(resource_changed::<SimTime>()));
+
+        // 1 s of driving along x at 2 m/s, the step before the first one isn't recorded
+        for step in 0..=100 {
+            app.world.resource_mut::<SimTime>().index = step;
+            let t = step as f32 * dt as f32;
+            let mut vehicle_state = app.world.resource_mut::<VehicleState>();
+            vehicle_state.position = Vec3::new(2. * t, 0., 0.5);
+            vehicle_state.heading = 0.;
+            vehicle_state.speed = 2.;
+            app.update();
+        }
+
+        let recorder = app.world.resource::<TrajectoryRecorder>();
+        let mut csv = Vec::new();
+        recorder.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("sim_time,x,y,z,heading,speed"));
+
+        // a sample every 10 steps from step 1 to step 91
+        let rows: Vec<Vec<f64>> = lines
+            .map(|line| line.split(',').map(|field| field.parse().unwrap()).collect())
+            .collect();
+        assert_eq!(rows.len(), 10);
+        for (i, row) in rows.iter().enumerate() {
+            let sim_time = (1 + 10 * i) as f64 * dt;
+            assert_eq!(row.len(), 6);
+            assert!((row[0] - sim_time).abs() < 1e-9, "row {}: {:?}", i, row);
+            assert!((row[1] - 2. * sim_time).abs() < 1e-5, "row {}: {:?}", i, row);
+            assert_eq!(&row[2..], &[0., 0.5, 0., 2.]);
+        }
+    }
+}
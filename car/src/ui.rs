@@ -1,9 +1,24 @@
 use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use bevy_integrator::{SimControl, SimTime};
 use rigid_body::joint::Joint;
 use crate::{
-    control::CarControl,
-    weather::Weather,
-    line_draw::LineDrawState,
+    build::CarSuspension,
+    key_bindings::KeyBindings,
+    control::{CarControl, ControlSource, SteeringConfig, TimeScale},
+    external_control::ExternalControls,
+    ghost::GhostCar,
+    physics::{
+        average_wheel_rpm, FuelTank, Steering, SteeringCurvature, SuspensionComponent,
+        SuspensionConfig, Transmission, WheelJoint,
+    },
+    weather::{RainIntensity, SurfaceWetness, TimeOfDay, Weather},
+    line_draw::{LineDrawState, LineStyle, PathLengthUnit},
+    lap_timer::LapTimer,
+    reset::{OutOfBounds, OutOfBoundsAction},
+    tire::{TireTemperature, TireWear},
+    vehicle_state::{GForce, VehicleState},
 };
 
 
@@ -13,21 +28,51 @@ pub struct SpeedometerText;
 #[derive(Component)]
 pub struct RpmText;
 
+#[derive(Component)]
+pub struct GearText;
+
+#[derive(Component)]
+pub struct FuelText;
+
+#[derive(Component)]
+pub struct GForceText;
+
+#[derive(Component)]
+pub struct TireTempText;
+
 #[derive(Component)]
 pub struct ControlsText;
 
+#[derive(Component)]
+pub struct SimSpeedText;
+
+#[derive(Component)]
+pub struct ControlSourceText;
+
 #[derive(Component)]
 pub struct LineColorText;
 
 #[derive(Component)]
 pub struct WeatherText;
 
+#[derive(Component)]
+pub struct PathLengthText;
+
+#[derive(Component)]
+pub struct LapText;
+
+#[derive(Component)]
+pub struct OutOfBoundsText;
+
+#[derive(Component)]
+pub struct SuspensionText;
+
 pub fn hud_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands
         .spawn(NodeBundle {
             style: Style {
                 width: Val::Px(170.0),  
-                height: Val::Px(210.0),
+                height: Val::Px(640.0),
                 ..default()
             },
             background_color: Color::BLACK.into(),
@@ -75,6 +120,94 @@ pub fn hud_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                     RpmText,
                 ));
 
+                // Gear display
+                parent.spawn((
+                    TextBundle::from_section(
+                        "Gear: 1",
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 24.0,
+                            color: Color::rgb(0.0, 0.5, 1.0),
+                        },
+                    ),
+                    GearText,
+                ));
+
+                // Fuel display
+                parent.spawn((
+                    TextBundle::from_section(
+                        "Fuel: 100%",
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 24.0,
+                            color: Color::rgb(0.0, 0.5, 1.0),
+                        },
+                    ),
+                    FuelText,
+                ));
+
+                // Longitudinal / lateral acceleration
+                parent.spawn((
+                    TextBundle::from_section(
+                        "Long: 0.00 g\nLat: 0.00 g",
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 18.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    GForceText,
+                ));
+
+                // Simulation speed / paused indicator
+                parent.spawn((
+                    TextBundle::from_section(
+                        "Sim: 1x",
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 18.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    SimSpeedText,
+                ));
+
+                // Active control source and connected WebSocket clients
+                parent.spawn((
+                    TextBundle::from_section(
+                        "Input: User",
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 18.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    ControlSourceText,
+                ));
+
+                // Average tire temperature and remaining tread
+                parent.spawn((
+                    TextBundle::from_sections([
+                        TextSection::new(
+                            "Tires: 20 °C",
+                            TextStyle {
+                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 18.0,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        TextSection::new(
+                            "  Tread: 100%",
+                            TextStyle {
+                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 18.0,
+                                color: Color::GOLD,
+                            },
+                        ),
+                    ]),
+                    TireTempText,
+                ));
+
                 // Controls
                 parent.spawn((
                     TextBundle::from_sections([
@@ -144,82 +277,694 @@ pub fn hud_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                     LineColorText,
                 ));
 
+                // Drawn path length display
+                parent.spawn((
+                    TextBundle::from_sections([
+                        TextSection::new(
+                            "Path: ",
+                            TextStyle {
+                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 20.0,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        TextSection::new(
+                            "0.0 m",
+                            TextStyle {
+                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 20.0,
+                                color: Color::GOLD,
+                            },
+                        ),
+                    ]),
+                    PathLengthText,
+                ));
+
+                // Lap times (current / last / best)
+                parent.spawn((
+                    TextBundle::from_section(
+                        "Lap: --",
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 18.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    LapText,
+                ));
+
+                // Out of bounds warning (empty while on the terrain)
+                parent.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 18.0,
+                            color: Color::RED,
+                        },
+                    ),
+                    OutOfBoundsText,
+                ));
+
+                // Suspension rates (live tuning)
+                parent.spawn((
+                    TextBundle::from_section(
+                        "Springs: --\nDampers: --",
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 16.0,
+                            color: Color::GRAY,
+                        },
+                    ),
+                    SuspensionText,
+                ));
+
             });
         });
 }
 
+// Unit of the speedometer, toggled with 'M' (KeyBindings::toggle_speed_unit)
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpeedUnit {
+    #[default]
+    Mph,
+    Kph,
+}
+
+impl SpeedUnit {
+    // Converts a speed in m/s to this unit
+    pub fn convert(&self, speed: f32) -> f32 {
+        match self {
+            SpeedUnit::Mph => speed * 2.237,
+            SpeedUnit::Kph => speed * 3.6,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SpeedUnit::Mph => "MPH",
+            SpeedUnit::Kph => "KPH",
+        }
+    }
+}
+
+pub fn speed_unit_toggle_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    mut unit: ResMut<SpeedUnit>,
+) {
+    if keyboard_input.just_pressed(keys.toggle_speed_unit) {
+        *unit = match *unit {
+            SpeedUnit::Mph => SpeedUnit::Kph,
+            SpeedUnit::Kph => SpeedUnit::Mph,
+        };
+    }
+}
+
+// Speed of the car body along its heading (from VehicleState, so wheel spin and
+// sliding don't affect the reading)
 pub fn update_speedometer_system(
     mut query: Query<&mut Text, With<SpeedometerText>>,
-    wheel_query: Query<&Joint>,
+    vehicle_state: Res<VehicleState>,
+    control: Res<CarControl>,
+    unit: Res<SpeedUnit>,
 ) {
+    let forward = Vec2::new(vehicle_state.heading.cos(), vehicle_state.heading.sin());
+    let speed = unit.convert(vehicle_state.velocity.truncate().dot(forward));
     for mut text in query.iter_mut() {
-        let mut total_speed = 0.0;
-        let wheel_count = wheel_query.iter().count() as f64;
-        
-        for joint in wheel_query.iter() {
-            let wheel_speed = joint.qd * 0.3 * 2.237;
-            total_speed += wheel_speed;
+        if control.reverse {
+            text.sections[0].value = format!("R {:.1} {}", speed.abs(), unit.label());
+        } else {
+            text.sections[0].value = format!("{:.1} {}", speed, unit.label());
         }
-        
-        let average_speed = total_speed / wheel_count;
-        text.sections[0].value = format!("{:.1} MPH", average_speed);
     }
 }
 
+// Average spin of the driven wheels (all wheels if none are driven)
 pub fn update_rpm_system(
     mut query: Query<&mut Text, With<RpmText>>,
-    joints: Query<&Joint>,
+    wheels: Query<(&Joint, &WheelJoint)>,
 ) {
+    let wheels: Vec<_> = wheels.iter().collect();
+    let average_rpm = average_wheel_rpm(&wheels);
     for mut text in query.iter_mut() {
-        let mut total_rpm = 0.0;
-        let count = joints.iter().count() as f64;
-        
-        for joint in joints.iter() {
-            total_rpm += (joint.qd * 60.0) / (2.0 * std::f64::consts::PI);
-        }
-        
-        let average_rpm = total_rpm / count;
         text.sections[0].value = format!("{:.0} RPM", average_rpm);
     }
 }
 
+pub fn update_gear_system(
+    mut query: Query<&mut Text, With<GearText>>,
+    transmission: Res<Transmission>,
+) {
+    for mut text in query.iter_mut() {
+        let gear = if transmission.reverse {
+            "R".to_string()
+        } else {
+            (transmission.gear + 1).to_string()
+        };
+        text.sections[0].value = format!("Gear: {} ({:.0} RPM)", gear, transmission.engine_rpm);
+    }
+}
+
+pub fn update_fuel_system(
+    mut query: Query<&mut Text, With<FuelText>>,
+    fuel: Res<FuelTank>,
+) {
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!(
+            "Fuel: {:.1} L ({:.0}%)",
+            fuel.level,
+            100. * fuel.level / fuel.capacity
+        );
+        text.sections[0].style.color = if fuel.is_empty() {
+            Color::RED
+        } else {
+            Color::rgb(0.0, 0.5, 1.0)
+        };
+    }
+}
+
+pub fn update_g_force_system(
+    mut query: Query<&mut Text, With<GForceText>>,
+    g_force: Res<GForce>,
+) {
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!(
+            "Long: {:+.2} g\nLat: {:+.2} g",
+            g_force.longitudinal, g_force.lateral
+        );
+    }
+}
+
+pub fn update_tire_temperature_system(
+    mut query: Query<&mut Text, With<TireTempText>>,
+    tires: Query<&TireTemperature>,
+) {
+    let count = tires.iter().count();
+    if count == 0 {
+        return;
+    }
+    let average = tires.iter().map(|tire| tire.temperature).sum::<f64>() / count as f64;
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!("Tires: {:.0} °C", average);
+    }
+}
+
+pub fn update_tire_wear_system(
+    mut query: Query<&mut Text, With<TireTempText>>,
+    wear: Res<TireWear>,
+) {
+    for mut text in query.iter_mut() {
+        if text.sections.len() > 1 {
+            text.sections[1].value = format!("  Tread: {:.0}%", 100. * wear.average_tread());
+        }
+    }
+}
+
+// The steering readout is the average road-wheel angle of the steered wheels, so it
+// follows the steering lock, SteeringConfig and the speed assist
 pub fn update_controls_system(
     mut query: Query<&mut Text, With<ControlsText>>,
     control: Res<CarControl>,
+    steering_config: Res<SteeringConfig>,
+    vehicle_state: Res<VehicleState>,
+    steered_wheels: Query<&Joint, Or<(With<Steering>, With<SteeringCurvature>)>>,
 ) {
+    let steered_count = steered_wheels.iter().count();
+    let steering_angle = if steered_count > 0 {
+        steered_wheels.iter().map(|joint| joint.q).sum::<f64>().to_degrees() / steered_count as f64
+    } else {
+        0.
+    };
+    let assist = if steering_config.speed_assist {
+        format!("{:.0}%", steering_config.assist_factor(vehicle_state.speed) * 100.0)
+    } else {
+        "OFF".to_string()
+    };
     for mut text in query.iter_mut() {
         text.sections[1].value = format!(
-            "Throttle: {:.0}%\nBrake: {:.0}%\nSteering: {:.0}°",
-            control.throttle * 100.0,
-            control.brake * 100.0,
-            control.steering * 30.0
+            "Throttle: {:.0}%\nBrake: {:.0}%\nSteering: {:.0}°\nHandbrake: {}\nSteer assist: {}",
+            control.throttle.max(0.0) * 100.0,
+            control.brake.max(0.0) * 100.0,
+            steering_angle,
+            if control.handbrake > 0.0 { "ON" } else { "OFF" },
+            assist
         );
     }
 }
 
+pub fn update_sim_speed_system(
+    mut query: Query<&mut Text, With<SimSpeedText>>,
+    time_scale: Res<TimeScale>,
+    sim_control: Res<SimControl>,
+) {
+    if !time_scale.is_changed() && !sim_control.is_changed() {
+        return;
+    }
+    for mut text in query.iter_mut() {
+        if sim_control.paused {
+            text.sections[0].value = "Sim: PAUSED".to_string();
+            text.sections[0].style.color = Color::ORANGE;
+        } else {
+            text.sections[0].value = format!("Sim: {}x", time_scale.scale);
+            text.sections[0].style.color = Color::WHITE;
+        }
+    }
+}
+
+pub fn update_control_source_system(
+    mut query: Query<&mut Text, With<ControlSourceText>>,
+    source: Res<ControlSource>,
+    external_controls: Option<Res<ExternalControls>>,
+) {
+    let clients = external_controls.map_or(0, |external| external.client_count());
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!("Input: {:?}  Clients: {}", *source, clients);
+    }
+}
+
 pub fn update_weather_system(
     weather: Res<Weather>,
+    intensity: Res<RainIntensity>,
+    time_of_day: Res<TimeOfDay>,
+    wetness: Res<SurfaceWetness>,
     mut query: Query<&mut Text, With<WeatherText>>,
 ) {
-    if weather.is_changed() {
+    if weather.is_changed()
+        || intensity.is_changed()
+        || time_of_day.is_changed()
+        || wetness.is_changed()
+    {
+        let mut label = if *weather == Weather::Rain {
+            format!("{:?} ({:?})", *weather, *intensity)
+        } else {
+            format!("{:?}", *weather)
+        };
+        if wetness.wetness > 0. {
+            label.push_str(&format!(" wet {:.0}%", wetness.wetness * 100.));
+        }
+        if time_of_day.enabled {
+            label.push_str(&format!(" {}", time_of_day.clock()));
+        }
         for mut text in query.iter_mut() {
-            text.sections[1].value = format!("{:?}", *weather);
+            text.sections[1].value = label.clone();
         }
     }
 }
 
 
 pub fn update_line_color_system(
-    line_draw_state: Res<LineDrawState>,
+    line_style: Res<LineStyle>,
     mut query: Query<&mut Text, With<LineColorText>>,
 ) {
-    let label = match line_draw_state.color {
+    let label = match line_style.palette {
         crate::line_draw::LineColor::White => "White",
+        crate::line_draw::LineColor::Yellow => "Yellow",
         crate::line_draw::LineColor::Cyan => "Cyan",
         crate::line_draw::LineColor::Magenta => "Magenta",
     };
 
     for mut text in query.iter_mut() {
-        text.sections[1].value = label.to_string();
+        text.sections[1].value = format!(
+            "{} ({:.2} m, lane {:.2} m)",
+            label, line_style.width, line_style.lane_width
+        );
+    }
+}
+
+pub fn update_path_length_system(
+    line_draw_state: Res<LineDrawState>,
+    unit: Res<PathLengthUnit>,
+    mut query: Query<&mut Text, With<PathLengthText>>,
+) {
+    if line_draw_state.is_changed() || unit.is_changed() {
+        let meters = line_draw_state.path_length;
+        let closed = if line_draw_state.closed { "\nLoop closed" } else { "" };
+        for mut text in query.iter_mut() {
+            text.sections[1].value = format!(
+                "{:.1} m ({:.1} {}){}",
+                meters,
+                meters * unit.per_meter,
+                unit.label,
+                closed
+            );
+        }
+    }
+}
+
+pub fn update_out_of_bounds_system(
+    mut query: Query<&mut Text, With<OutOfBoundsText>>,
+    out_of_bounds: Res<OutOfBounds>,
+) {
+    if !out_of_bounds.is_changed() {
+        return;
+    }
+    let warning = match (out_of_bounds.outside_time, out_of_bounds.action) {
+        (None, _) => String::new(),
+        (Some(_), OutOfBoundsAction::Warn) => "OUT OF BOUNDS".to_string(),
+        (Some(time), OutOfBoundsAction::Respawn) => format!(
+            "OUT OF BOUNDS\nRespawn in {:.1} s",
+            (out_of_bounds.grace_period - time).max(0.)
+        ),
+    };
+    for mut text in query.iter_mut() {
+        text.sections[0].value = warning.clone();
+    }
+}
+
+// Spring and damper rate of the car's corners with the live tuning scales
+pub fn update_suspension_system(
+    mut query: Query<&mut Text, With<SuspensionText>>,
+    config: Res<SuspensionConfig>,
+    suspensions: Query<&SuspensionComponent, With<CarSuspension>>,
+) {
+    let Some(suspension) = suspensions.iter().next() else {
+        return;
+    };
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!(
+            "Springs: {:.0} N/m (x{:.1})\nDampers: {:.0} N s/m (x{:.1})",
+            suspension.stiffness(&config),
+            config.stiffness_scale,
+            suspension.damping(&config),
+            config.damping_scale
+        );
+    }
+}
+
+fn format_lap(lap: Option<f64>) -> String {
+    match lap {
+        Some(t) => format!("{}:{:06.3}", (t / 60.) as u32, t % 60.),
+        None => "--".to_string(),
+    }
+}
+
+pub fn update_lap_timer_system(
+    mut query: Query<&mut Text, With<LapText>>,
+    lap_timer: Res<LapTimer>,
+    ghost: Option<Res<GhostCar>>,
+) {
+    for mut text in query.iter_mut() {
+        let current = lap_timer.lap_start.map(|_| lap_timer.current_lap);
+        text.sections[0].value = format!(
+            "Lap {}: {}\nLast: {}\nBest: {}",
+            lap_timer.laps + 1,
+            format_lap(current),
+            format_lap(lap_timer.last_lap),
+            format_lap(lap_timer.best_lap)
+        );
+        if let Some(ghost) = &ghost {
+            text.sections[0].value += &format!("\nGhost: {}", format_lap(ghost.best_lap));
+        }
+    }
+}
+
+// Signals the telemetry graph can plot, each scaled to 0..1 for the graph height
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TelemetrySignal {
+    Speed,    // 0 .. TelemetryGraph::max_speed
+    Throttle, // 0 .. 1
+    Brake,    // 0 .. 1
+    Steering, // -1 (bottom) .. 1 (top)
+}
+
+impl TelemetrySignal {
+    pub fn color(&self) -> Color {
+        match self {
+            TelemetrySignal::Speed => Color::rgb(0.0, 0.5, 1.0),
+            TelemetrySignal::Throttle => Color::GREEN,
+            TelemetrySignal::Brake => Color::RED,
+            TelemetrySignal::Steering => Color::GOLD,
+        }
+    }
+
+    fn sample(&self, vehicle_state: &VehicleState, control: &CarControl, max_speed: f32) -> f32 {
+        let value = match self {
+            TelemetrySignal::Speed => vehicle_state.speed / max_speed,
+            TelemetrySignal::Throttle => control.throttle,
+            TelemetrySignal::Brake => control.brake,
+            TelemetrySignal::Steering => 0.5 * (control.steering + 1.0),
+        };
+        value.clamp(0.0, 1.0)
+    }
+}
+
+// Scrolling graph of recent telemetry.
+// - signals: what to plot (read when the graph is spawned)
+// - window: seconds of sim time shown across the graph
+// - max_speed: speed at the top of the graph (m/s)
+// Samples are kept in a ring buffer and dropped once they are older than the window.
+#[derive(Resource)]
+pub struct TelemetryGraph {
+    pub signals: Vec<TelemetrySignal>,
+    pub window: f64,
+    pub max_speed: f32,
+    samples: VecDeque<(f64, Vec<f32>)>,
+}
+
+impl Default for TelemetryGraph {
+    fn default() -> Self {
+        Self {
+            signals: vec![TelemetrySignal::Speed, TelemetrySignal::Throttle],
+            window: 10.,
+            max_speed: 40.,
+            samples: VecDeque::new(),
+        }
+    }
+}
+
+impl TelemetryGraph {
+    // Most recent sample at or before `time`
+    fn sample_at(&self, time: f64) -> Option<&Vec<f32>> {
+        let index = self.samples.partition_point(|(t, _)| *t <= time);
+        if index == 0 {
+            None
+        } else {
+            Some(&self.samples[index - 1].1)
+        }
+    }
+}
+
+const GRAPH_WIDTH: f32 = 240.0;
+const GRAPH_HEIGHT: f32 = 80.0;
+const GRAPH_POINTS: usize = 120;
+const GRAPH_DOT_SIZE: f32 = 2.0;
+
+// One point of one signal's line; moved up and down by update_telemetry_graph_system
+#[derive(Component)]
+pub struct GraphDot {
+    signal: usize,
+    point: usize,
+}
+
+// Spawns the graph below the HUD panel: GRAPH_POINTS dots per signal, oldest on the left
+pub fn telemetry_graph_setup(mut commands: Commands, graph: Res<TelemetryGraph>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                width: Val::Px(GRAPH_WIDTH),
+                height: Val::Px(GRAPH_HEIGHT),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            for (signal, telemetry_signal) in graph.signals.iter().enumerate() {
+                for point in 0..GRAPH_POINTS {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Val::Px(
+                                    point as f32 * (GRAPH_WIDTH - GRAPH_DOT_SIZE)
+                                        / (GRAPH_POINTS - 1) as f32,
+                                ),
+                                width: Val::Px(GRAPH_DOT_SIZE),
+                                height: Val::Px(GRAPH_DOT_SIZE),
+                                display: Display::None,
+                                ..default()
+                            },
+                            background_color: telemetry_signal.color().into(),
+                            ..default()
+                        },
+                        GraphDot { signal, point },
+                    ));
+                }
+            }
+        });
+}
+
+// Records a sample whenever sim time advances and redraws the graph
+pub fn update_telemetry_graph_system(
+    mut graph: ResMut<TelemetryGraph>,
+    sim_time: Res<SimTime>,
+    vehicle_state: Res<VehicleState>,
+    control: Res<CarControl>,
+    mut dots: Query<(&mut Style, &GraphDot)>,
+) {
+    let time = sim_time.time();
+    let last_time = graph.samples.back().map(|(t, _)| *t);
+    if last_time.map_or(false, |t| time < t) {
+        // sim time went backwards (reset), start over
+        graph.samples.clear();
+    }
+    if last_time != Some(time) {
+        let values = graph
+            .signals
+            .iter()
+            .map(|signal| signal.sample(&vehicle_state, &control, graph.max_speed))
+            .collect();
+        graph.samples.push_back((time, values));
+    }
+    let start = time - graph.window;
+    while graph.samples.front().map_or(false, |(t, _)| *t < start) {
+        graph.samples.pop_front();
+    }
+
+    for (mut style, dot) in dots.iter_mut() {
+        let point_time = start + graph.window * dot.point as f64 / (GRAPH_POINTS - 1) as f64;
+        match graph.sample_at(point_time).and_then(|values| values.get(dot.signal)) {
+            Some(value) => {
+                style.display = Display::Flex;
+                style.top = Val::Px((1.0 - value) * (GRAPH_HEIGHT - GRAPH_DOT_SIZE));
+            }
+            None => style.display = Display::None,
+        }
+    }
+}
+
+// Rotation of the steering wheel widget at full steering
+const STEERING_WHEEL_MAX_ANGLE: f32 = std::f32::consts::PI;
+const STEERING_WHEEL_SIZE: f32 = 70.0;
+const PEDAL_BAR_HEIGHT: f32 = 70.0;
+
+#[derive(Component)]
+pub struct SteeringWheelIndicator;
+
+// Fill of a pedal bar; its height follows the pedal
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PedalBar {
+    Throttle,
+    Brake,
+}
+
+// Spawns the steering wheel (a spoke with a marker at the top) and the throttle and
+// brake bars to the right of the telemetry graph
+pub fn steering_widget_setup(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(260.0),
+                bottom: Val::Px(10.0),
+                column_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(5.0)),
+                align_items: AlignItems::End,
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            // steering wheel: rotated as a whole by update_steering_widget_system
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Px(STEERING_WHEEL_SIZE),
+                            height: Val::Px(STEERING_WHEEL_SIZE),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                        ..default()
+                    },
+                    SteeringWheelIndicator,
+                ))
+                .with_children(|parent| {
+                    // spoke
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(STEERING_WHEEL_SIZE),
+                            height: Val::Px(6.0),
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.6, 0.6, 0.6).into(),
+                        ..default()
+                    });
+                    // top-center marker
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(0.5 * STEERING_WHEEL_SIZE - 3.0),
+                            top: Val::Px(0.0),
+                            width: Val::Px(6.0),
+                            height: Val::Px(12.0),
+                            ..default()
+                        },
+                        background_color: Color::ORANGE.into(),
+                        ..default()
+                    });
+                });
+
+            for pedal in [PedalBar::Throttle, PedalBar::Brake] {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(12.0),
+                            height: Val::Px(PEDAL_BAR_HEIGHT),
+                            flex_direction: FlexDirection::Column,
+                            justify_content: JustifyContent::End,
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Percent(100.0),
+                                    height: Val::Percent(0.0),
+                                    ..default()
+                                },
+                                background_color: match pedal {
+                                    PedalBar::Throttle => Color::GREEN,
+                                    PedalBar::Brake => Color::RED,
+                                }
+                                .into(),
+                                ..default()
+                            },
+                            pedal,
+                        ));
+                    });
+            }
+        });
+}
+
+// Rotates the wheel with CarControl.steering and fills the pedal bars.
+// UI y points down, so a positive (left) steering is a negative z rotation.
+pub fn update_steering_widget_system(
+    control: Res<CarControl>,
+    mut wheels: Query<&mut Transform, With<SteeringWheelIndicator>>,
+    mut bars: Query<(&mut Style, &PedalBar)>,
+) {
+    if !control.is_changed() {
+        return;
+    }
+    for mut transform in wheels.iter_mut() {
+        transform.rotation = Quat::from_rotation_z(-control.steering * STEERING_WHEEL_MAX_ANGLE);
+    }
+    for (mut style, pedal) in bars.iter_mut() {
+        let value = match pedal {
+            PedalBar::Throttle => control.throttle,
+            PedalBar::Brake => control.brake,
+        };
+        style.height = Val::Percent(100.0 * value.clamp(0.0, 1.0));
     }
 }
@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+use bevy_integrator::SimTime;
+use rigid_body::joint::Joint;
+use std::collections::VecDeque;
+
+const GRAVITY: f32 = 9.81;
+
+// Summary of the car body's motion, for systems that need the vehicle's speed
+// rather than individual joint states (ABS, HUD, controllers, ...)
+// - position: chassis position in absolute coordinates (m)
+// - velocity: ground velocity of the chassis in absolute coordinates (m/s)
+// - speed: magnitude of the horizontal velocity (m/s)
+// - heading: yaw of the chassis about the z axis (rad, 0 = facing +x)
+// - angular_velocity: angular velocity of the car body in absolute coordinates (rad/s)
+#[derive(Resource, Default, Debug, Clone)]
+pub struct VehicleState {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub speed: f32,
+    pub heading: f32,
+    pub angular_velocity: Vec3,
+}
+
+// Reads the chassis joints and writes the vehicle position and motion.
+// The chassis x/y/z joints are prismatic joints in absolute coordinates
+// (see Chassis::build), so their positions and speeds are the world position and
+// velocity of the car. The heading is the yaw joint angle; the angular velocity is
+// taken from the car body (the roll joint, last in the chassis chain) so it includes
+// pitch and roll rates.
+pub fn vehicle_state_update_system(
+    joints: Query<&Joint>,
+    mut vehicle_state: ResMut<VehicleState>,
+) {
+    let mut position = Vec3::ZERO;
+    let mut velocity = Vec3::ZERO;
+    let mut heading = 0.;
+    let mut angular_velocity = Vec3::ZERO;
+    for joint in joints.iter() {
+        match joint.name.as_str() {
+            "chassis_px" => (position.x, velocity.x) = (joint.q as f32, joint.qd as f32),
+            "chassis_py" => (position.y, velocity.y) = (joint.q as f32, joint.qd as f32),
+            "chassis_pz" => (position.z, velocity.z) = (joint.q as f32, joint.qd as f32),
+            "chassis_rz" => heading = joint.q as f32,
+            "chassis_rx" => {
+                let w = (joint.x.inverse() * joint.v).w;
+                angular_velocity = Vec3::new(w.x as f32, w.y as f32, w.z as f32);
+            }
+            _ => {}
+        }
+    }
+    vehicle_state.position = position;
+    vehicle_state.velocity = velocity;
+    vehicle_state.speed = velocity.truncate().length();
+    vehicle_state.heading = heading;
+    vehicle_state.angular_velocity = angular_velocity;
+}
+
+// Acceleration of the car body in its own frame, in g (read-only telemetry)
+// - longitudinal: positive when speeding up going forward
+// - lateral: positive when accelerating to the left
+// - window: number of samples in the moving average
+#[derive(Resource)]
+pub struct GForce {
+    pub longitudinal: f32,
+    pub lateral: f32,
+    pub window: usize,
+    samples: VecDeque<Vec2>,
+    previous: Option<(f64, Vec3)>,
+}
+
+impl Default for GForce {
+    fn default() -> Self {
+        Self {
+            longitudinal: 0.,
+            lateral: 0.,
+            window: 25,
+            samples: VecDeque::new(),
+            previous: None,
+        }
+    }
+}
+
+// Differentiates the chassis velocity between sim steps and rotates the result
+// into the car frame using the chassis yaw (chassis_rz).
+pub fn g_force_system(
+    joints: Query<&Joint>,
+    sim_time: Res<SimTime>,
+    vehicle_state: Res<VehicleState>,
+    mut g_force: ResMut<GForce>,
+) {
+    let time = sim_time.time();
+    let velocity = vehicle_state.velocity;
+    let Some((previous_time, previous_velocity)) = g_force.previous else {
+        g_force.previous = Some((time, velocity));
+        return;
+    };
+    // nothing to differentiate until the simulation has stepped
+    let dt = (time - previous_time) as f32;
+    if dt <= 0. {
+        return;
+    }
+    g_force.previous = Some((time, velocity));
+
+    let yaw = joints
+        .iter()
+        .find(|joint| joint.name == "chassis_rz")
+        .map_or(0., |joint| joint.q as f32);
+    let acceleration = ((velocity - previous_velocity) / dt).truncate();
+    let forward = Vec2::from_angle(yaw);
+    let local = Vec2::new(acceleration.dot(forward), acceleration.dot(forward.perp())) / GRAVITY;
+
+    let window = g_force.window.max(1);
+    g_force.samples.push_back(local);
+    while g_force.samples.len() > window {
+        g_force.samples.pop_front();
+    }
+    let average = g_force.samples.iter().sum::<Vec2>() / g_force.samples.len() as f32;
+    g_force.longitudinal = average.x;
+    g_force.lateral = average.y;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rigid_body::sva::{Inertia, Xform};
+
+    #[test]
+    fn driving_forward_moves_the_car_forward() {
+        let mut app = App::new();
+        app.init_resource::<VehicleState>()
+            .add_systems(Update, vehicle_state_update_system);
+        let px = Joint::px("chassis_px".to_string(), Inertia::zero(), Xform::identity());
+        let chassis = app.world.spawn(px).id();
+
+        app.update();
+        assert_eq!(app.world.resource::<VehicleState>().speed, 0.);
+
+        // the chassis slides along +x at 3 m/s, moved by hand in place of the solver
+        let dt = 0.01;
+        let mut previous = app.world.resource::<VehicleState>().position.x;
+        for _ in 0..10 {
+            let mut joint = app.world.get_mut::<Joint>(chassis).unwrap();
+            joint.qd = 3.;
+            joint.q += joint.qd * dt;
+            app.update();
+
+            let vehicle_state = app.world.resource::<VehicleState>();
+            assert!(vehicle_state.position.x > previous);
+            assert!((vehicle_state.speed - 3.).abs() < 1e-6);
+            assert_eq!(vehicle_state.velocity, Vec3::new(3., 0., 0.));
+            previous = vehicle_state.position.x;
+        }
+        assert!((previous - 0.3).abs() < 1e-5);
+    }
+}
@@ -2,6 +2,11 @@ use bevy::prelude::*;
 use bevy_hanabi::prelude::*;
 use std::f32::consts::PI;
 use bevy::pbr::{CascadeShadowConfigBuilder, DirectionalLightShadowMap};
+use cameras::capture::ScreenshotSettings;
+use grid_terrain::TerrainCell;
+use std::collections::HashMap;
+
+use crate::{key_bindings::KeyBindings, physics::Wind};
 
 #[derive(Resource, PartialEq, Eq, Clone, Copy, Debug)]
 pub enum Weather {
@@ -11,29 +16,61 @@ pub enum Weather {
     Night,
 }
 
+impl Weather {
+    // The weather after this one when cycling
+    pub fn next(self) -> Self {
+        match self {
+            Weather::Sunny => Weather::Cloudy,
+            Weather::Cloudy => Weather::Rain,
+            Weather::Rain => Weather::Night,
+            Weather::Night => Weather::Sunny,
+        }
+    }
+
+    // Ambient light color and brightness
+    pub fn ambient_light(&self) -> (Color, f32) {
+        match self {
+            Weather::Sunny => (Color::rgb(1.0, 1.0, 1.0), 0.5),
+            Weather::Cloudy => (Color::rgb(0.6, 0.6, 0.7), 0.3),
+            Weather::Rain => (Color::rgb(0.6, 0.6, 0.7), 0.3),
+            Weather::Night => (Color::rgb(0.2, 0.2, 0.3), 0.1),
+        }
+    }
+
+    // Directional (sun / moon) light color and illuminance
+    pub fn directional_light(&self) -> (Color, f32) {
+        match self {
+            Weather::Sunny => (Color::rgb(1.0, 1.0, 0.9), 100000.0),
+            Weather::Cloudy => (Color::rgb(0.7, 0.7, 0.8), 50000.0),
+            Weather::Rain => (Color::rgb(0.7, 0.7, 0.8), 50000.0),
+            Weather::Night => (Color::rgb(0.2, 0.2, 0.5), 5000.0),
+        }
+    }
+
+    // Scale of the Wind speed in this weather
+    pub fn wind_factor(&self) -> f64 {
+        match self {
+            Weather::Sunny => 1.0,
+            Weather::Cloudy => 1.3,
+            Weather::Rain => 1.8,
+            Weather::Night => 0.8,
+        }
+    }
+}
+
 pub fn setup_lighting_system(
     mut commands: Commands,
     weather: Res<Weather>,
 ) {
     // Set ambient light
-    let (ambient_color, ambient_brightness) = match *weather {
-        Weather::Sunny => (Color::rgb(1.0, 1.0, 1.0), 0.5),
-        Weather::Cloudy => (Color::rgb(0.6, 0.6, 0.7), 0.3),
-        Weather::Rain => (Color::rgb(0.6, 0.6, 0.7), 0.3),
-        Weather::Night => (Color::rgb(0.2, 0.2, 0.3), 0.1),
-    };
+    let (ambient_color, ambient_brightness) = weather.ambient_light();
     commands.insert_resource(AmbientLight {
         color: ambient_color,
         brightness: ambient_brightness,
     });
 
     // Set directional light
-    let (directional_light_color, illuminance) = match *weather {
-        Weather::Sunny => (Color::rgb(1.0, 1.0, 0.9), 100000.0),
-        Weather::Cloudy => (Color::rgb(0.7, 0.7, 0.8), 50000.0),
-        Weather::Rain => (Color::rgb(0.7, 0.7, 0.8), 50000.0),
-        Weather::Night => (Color::rgb(0.2, 0.2, 0.5), 5000.0),
-    };
+    let (directional_light_color, illuminance) = weather.directional_light();
     commands.spawn(DirectionalLightBundle {
         directional_light: DirectionalLight {
             color: directional_light_color,
@@ -45,7 +82,7 @@ pub fn setup_lighting_system(
         },
         transform: Transform {
             translation: Vec3::new(0.0, 0.0, 10.0),
-            rotation: Quat::from_rotation_x(-PI / 4.) * Quat::from_rotation_y(-PI / 4.),
+            rotation: fixed_sun_rotation(),
             ..default()
         },
         cascade_shadow_config: CascadeShadowConfigBuilder {
@@ -65,48 +102,179 @@ pub fn setup_lighting_system(
 
 pub fn cycle_weather_system(
     keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
     mut weather: ResMut<Weather>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::P) {
+    if keyboard_input.just_pressed(keys.cycle_weather) {
         // Cycle to the next weather state
-        *weather = match *weather {
-            Weather::Sunny => Weather::Cloudy,
-            Weather::Cloudy => Weather::Rain,
-            Weather::Rain => Weather::Night,
-            Weather::Night => Weather::Sunny,
-        };
+        *weather = weather.next();
         println!("Weather changed to: {:?}", *weather);
     }
 }
 
+// Weather lighting with the sun fixed in the sky. Skipped while the time of day
+// cycle is on, time_of_day_system sets the lights then.
 pub fn update_environment_system(
     weather: Res<Weather>,
+    time_of_day: Option<Res<TimeOfDay>>,
     mut ambient_light: ResMut<AmbientLight>,
-    mut query: Query<&mut DirectionalLight>,
+    mut query: Query<(&mut DirectionalLight, &mut Transform)>,
 ) {
-    if weather.is_changed() {
-        // Update ambient light
-        let (ambient_color, ambient_brightness) = match *weather {
-            Weather::Sunny => (Color::rgb(1.0, 1.0, 1.0), 0.5),
-            Weather::Cloudy => (Color::rgb(0.6, 0.6, 0.7), 0.3),
-            Weather::Rain => (Color::rgb(0.6, 0.6, 0.7), 0.3),
-            Weather::Night => (Color::rgb(0.2, 0.2, 0.3), 0.1),
-        };
-        ambient_light.color = ambient_color;
-        ambient_light.brightness = ambient_brightness;
+    let cycle_on = time_of_day.as_ref().map_or(false, |time_of_day| time_of_day.enabled);
+    let cycle_toggled = time_of_day.as_ref().map_or(false, |time_of_day| time_of_day.is_changed());
+    if cycle_on || !(weather.is_changed() || cycle_toggled) {
+        return;
+    }
+    // Update ambient light
+    let (ambient_color, ambient_brightness) = weather.ambient_light();
+    ambient_light.color = ambient_color;
+    ambient_light.brightness = ambient_brightness;
 
-        // Update directional light
-        let (directional_light_color, illuminance) = match *weather {
-            Weather::Sunny => (Color::rgb(1.0, 1.0, 0.9), 100000.0),
-            Weather::Cloudy => (Color::rgb(0.7, 0.7, 0.8), 50000.0),
-            Weather::Rain => (Color::rgb(0.7, 0.7, 0.8), 50000.0),
-            Weather::Night => (Color::rgb(0.2, 0.2, 0.5), 5000.0),
+    // Update directional light
+    let (directional_light_color, illuminance) = weather.directional_light();
+    for (mut dir_light, mut transform) in query.iter_mut() {
+        dir_light.color = directional_light_color;
+        dir_light.illuminance = illuminance;
+        transform.rotation = fixed_sun_rotation();
+    }
+}
+
+// Sun direction when the time of day cycle is off
+fn fixed_sun_rotation() -> Quat {
+    Quat::from_rotation_x(-PI / 4.) * Quat::from_rotation_y(-PI / 4.)
+}
+
+// Optional continuous time of day, orthogonal to Weather (which keeps the clouds and rain)
+// - enabled: move the sun and blend the light colors by the hour, instead of the fixed
+//   sun of the Weather lighting
+// - hour: 0..24
+// - day_length: real seconds for a full day
+// - scrub_rate: hours per second while the scrub key is held
+#[derive(Resource)]
+pub struct TimeOfDay {
+    pub enabled: bool,
+    pub hour: f32,
+    pub day_length: f32,
+    pub scrub_rate: f32,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hour: 12.,
+            day_length: 600.,
+            scrub_rate: 2.,
+        }
+    }
+}
+
+// (hour, ambient color, ambient brightness, sun color, sun illuminance) through the day,
+// for clear weather
+const DAY_KEYFRAMES: [(f32, [f32; 3], f32, [f32; 3], f32); 5] = [
+    (0., [0.2, 0.2, 0.3], 0.1, [0.2, 0.2, 0.5], 5000.),      // night (moonlight)
+    (6., [0.8, 0.6, 0.5], 0.3, [1.0, 0.6, 0.4], 30000.),     // dawn
+    (12., [1.0, 1.0, 1.0], 0.5, [1.0, 1.0, 0.9], 100000.),   // noon
+    (18., [0.8, 0.5, 0.5], 0.3, [1.0, 0.5, 0.3], 30000.),    // dusk
+    (24., [0.2, 0.2, 0.3], 0.1, [0.2, 0.2, 0.5], 5000.),     // night
+];
+
+impl TimeOfDay {
+    // Ambient color and brightness, sun color and illuminance at the current hour
+    pub fn lighting(&self) -> (Color, f32, Color, f32) {
+        let hour = self.hour.rem_euclid(24.);
+        let i = DAY_KEYFRAMES
+            .iter()
+            .rposition(|keyframe| keyframe.0 <= hour)
+            .unwrap_or(0)
+            .min(DAY_KEYFRAMES.len() - 2);
+        let (h0, ambient0, brightness0, sun0, illuminance0) = DAY_KEYFRAMES[i];
+        let (h1, ambient1, brightness1, sun1, illuminance1) = DAY_KEYFRAMES[i + 1];
+        let t = (hour - h0) / (h1 - h0);
+        let color = |a: [f32; 3], b: [f32; 3]| {
+            let c = Vec3::from(a).lerp(Vec3::from(b), t);
+            Color::rgb(c.x, c.y, c.z)
         };
-        for mut dir_light in query.iter_mut() {
-            dir_light.color = directional_light_color;
-            dir_light.illuminance = illuminance;
+        (
+            color(ambient0, ambient1),
+            brightness0 + (brightness1 - brightness0) * t,
+            color(sun0, sun1),
+            illuminance0 + (illuminance1 - illuminance0) * t,
+        )
+    }
+
+    // Direction towards the sun: rises in +x at 6:00, highest at noon, sets in -x at 18:00.
+    // At night it is the moon, opposite the sun, so there is still a shadow casting light.
+    pub fn sun_direction(&self) -> Vec3 {
+        let angle = (self.hour - 6.) / 12. * PI;
+        let direction = Vec3::new(angle.cos(), 0.3, angle.sin()).normalize();
+        if direction.z < 0. {
+            -direction
+        } else {
+            direction
         }
     }
+
+    // Night on the cycle (always false while the cycle is off)
+    pub fn is_dark(&self) -> bool {
+        self.enabled && !(6.5..17.5).contains(&self.hour.rem_euclid(24.))
+    }
+
+    pub fn clock(&self) -> String {
+        let minutes = (self.hour.rem_euclid(24.) * 60.) as u32;
+        format!("{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+}
+
+// Press 'X' (KeyBindings::toggle_time_of_day) to switch the day/night cycle on / off,
+// and hold 'Y' (KeyBindings::scrub_time) to fast forward (with Shift: rewind) the clock.
+// While on, the sun moves across the sky (the shadows follow it) and the light colors
+// blend through dawn, noon, dusk and night, dimmed by the weather.
+pub fn time_of_day_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    time: Res<Time>,
+    weather: Res<Weather>,
+    mut time_of_day: ResMut<TimeOfDay>,
+    mut ambient_light: ResMut<AmbientLight>,
+    mut query: Query<(&mut DirectionalLight, &mut Transform)>,
+) {
+    if keyboard_input.just_pressed(keys.toggle_time_of_day) {
+        time_of_day.enabled = !time_of_day.enabled;
+        println!(
+            "Day/night cycle {}",
+            if time_of_day.enabled { "on" } else { "off" }
+        );
+    }
+    if !time_of_day.enabled {
+        return;
+    }
+
+    let dt = time.delta_seconds();
+    let mut hours = dt / time_of_day.day_length * 24.;
+    if keyboard_input.pressed(keys.scrub_time) {
+        let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        hours = if shift { -1. } else { 1. } * time_of_day.scrub_rate * dt;
+    }
+    time_of_day.hour = (time_of_day.hour + hours).rem_euclid(24.);
+
+    // clouds and rain dim the light by the same ratio as the fixed-sun lighting
+    let (_, clear_brightness) = Weather::Sunny.ambient_light();
+    let (_, clear_illuminance) = Weather::Sunny.directional_light();
+    let (_, weather_brightness) = weather.ambient_light();
+    let (_, weather_illuminance) = weather.directional_light();
+
+    let (ambient_color, brightness, sun_color, illuminance) = time_of_day.lighting();
+    ambient_light.color = ambient_color;
+    ambient_light.brightness = brightness * weather_brightness / clear_brightness;
+    let rotation = Transform::IDENTITY
+        .looking_to(-time_of_day.sun_direction(), Vec3::Z)
+        .rotation;
+    for (mut dir_light, mut transform) in query.iter_mut() {
+        dir_light.color = sun_color;
+        dir_light.illuminance = illuminance * weather_illuminance / clear_illuminance;
+        transform.rotation = rotation;
+    }
 }
 
 #[derive(Resource)]
@@ -114,10 +282,206 @@ pub struct RainEffect {
     pub entity: Entity,
 }
 
-pub fn setup_rain_system(
-    mut commands: Commands,
-    mut effects: ResMut<Assets<EffectAsset>>,
+// How hard it rains while the weather is Rain: the drop spawn rate and drop size
+#[derive(Resource, Default, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum RainIntensity {
+    Drizzle,
+    #[default]
+    Rain,
+    Downpour,
+}
+
+impl RainIntensity {
+    // Drops spawned per second
+    pub fn spawn_rate(&self) -> f32 {
+        match self {
+            RainIntensity::Drizzle => 10000.0,
+            RainIntensity::Rain => 40000.0,
+            RainIntensity::Downpour => 100000.0,
+        }
+    }
+
+    pub fn drop_size(&self) -> f32 {
+        match self {
+            RainIntensity::Drizzle => 0.4,
+            RainIntensity::Rain => 0.7,
+            RainIntensity::Downpour => 1.0,
+        }
+    }
+
+    pub fn heavier(self) -> Self {
+        match self {
+            RainIntensity::Drizzle => RainIntensity::Rain,
+            _ => RainIntensity::Downpour,
+        }
+    }
+
+    pub fn lighter(self) -> Self {
+        match self {
+            RainIntensity::Downpour => RainIntensity::Rain,
+            _ => RainIntensity::Drizzle,
+        }
+    }
+}
+
+// How many rain particles the GPU is asked to handle, chosen in the settings menu.
+// The capacity is the most drops alive at once (the effect's buffers are sized for it),
+// the spawn scale multiplies the RainIntensity spawn rate. Once the capacity is reached
+// no more drops spawn until old ones expire, so heavy rain looks thinner on Low.
+#[derive(Resource, Default, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ParticleBudget {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl ParticleBudget {
+    // The budget after this one when cycling
+    pub fn next(self) -> Self {
+        match self {
+            ParticleBudget::Low => ParticleBudget::Medium,
+            ParticleBudget::Medium => ParticleBudget::High,
+            ParticleBudget::High => ParticleBudget::Low,
+        }
+    }
+
+    // Most particles alive at once
+    pub fn capacity(&self) -> u32 {
+        match self {
+            ParticleBudget::Low => 100000,
+            ParticleBudget::Medium => 300000,
+            ParticleBudget::High => 1000000,
+        }
+    }
+
+    pub fn spawn_scale(&self) -> f32 {
+        match self {
+            ParticleBudget::Low => 0.25,
+            ParticleBudget::Medium => 0.5,
+            ParticleBudget::High => 1.0,
+        }
+    }
+}
+
+// Height (m) where rain drops hit the ground and disappear, so they don't fall through the
+// road. The particles don't know about the terrain or the car: drops still pass through
+// terrain pieces above this height and through the car. Raise it to the top of the
+// terrain to hide drops inside it (they then stop in the air over the flat ground).
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct RainGround {
+    pub height: f32,
+}
+
+impl Default for RainGround {
+    fn default() -> Self {
+        Self { height: 0.0 }
+    }
+}
+
+// How wet the track is, shared by the look of the terrain and the tire grip.
+// The track soaks up while it rains and dries off otherwise; the terrain cells turn glossy
+// and reflective and the tires lose grip in proportion. Toggled in the settings menu.
+// - enabled: the track gets wet in the rain (off keeps it dry)
+// - wetness: 0 dry .. 1 soaked
+// - wetting_time / drying_time: time (s) to go from dry to soaked / back (0 = at once)
+// - wet_roughness, wet_metallic: terrain material of a soaked track
+// - wet_grip: grip factor of a soaked track
+#[derive(Resource)]
+pub struct SurfaceWetness {
+    pub enabled: bool,
+    pub wetness: f32,
+    pub wetting_time: f32,
+    pub drying_time: f32,
+    pub wet_roughness: f32,
+    pub wet_metallic: f32,
+    pub wet_grip: f64,
+    // dry (roughness, metallic) of every terrain material made wet
+    dry_materials: HashMap<Handle<StandardMaterial>, (f32, f32)>,
+    applied_wetness: f32,
+}
+
+impl Default for SurfaceWetness {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            wetness: 0.,
+            wetting_time: 30.,
+            drying_time: 120.,
+            wet_roughness: 0.15,
+            wet_metallic: 0.3,
+            wet_grip: 0.7,
+            dry_materials: HashMap::new(),
+            applied_wetness: 0.,
+        }
+    }
+}
+
+impl SurfaceWetness {
+    // Grip multiplier, from 1 on a dry track down to wet_grip on a soaked one
+    pub fn grip_factor(&self) -> f64 {
+        1. - (1. - self.wet_grip) * self.wetness as f64
+    }
+}
+
+// Soaks the track while it rains and dries it otherwise, and blends the roughness and
+// metallic of the terrain cell materials between their dry values and the wet look
+pub fn wet_surface_system(
+    time: Res<Time>,
+    weather: Res<Weather>,
+    mut wetness: ResMut<SurfaceWetness>,
+    cells: Query<&Handle<StandardMaterial>, With<TerrainCell>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
+    let wetness = wetness.as_mut();
+    if !wetness.enabled {
+        wetness.wetness = 0.;
+    } else if *weather == Weather::Rain {
+        let rate = time.delta_seconds() / wetness.wetting_time.max(f32::EPSILON);
+        wetness.wetness = (wetness.wetness + rate).min(1.);
+    } else {
+        let rate = time.delta_seconds() / wetness.drying_time.max(f32::EPSILON);
+        wetness.wetness = (wetness.wetness - rate).max(0.);
+    }
+
+    // cells share materials, each is changed once; cells re-meshed by the terrain editor
+    // may bring new ones, which are picked up then
+    let new_material = cells
+        .iter()
+        .any(|material| !wetness.dry_materials.contains_key(material));
+    if wetness.wetness == wetness.applied_wetness && !new_material {
+        return;
+    }
+    wetness.applied_wetness = wetness.wetness;
+    let w = wetness.wetness;
+    for handle in cells.iter() {
+        let Some(material) = materials.get_mut(handle) else { continue; };
+        let (roughness, metallic) = *wetness
+            .dry_materials
+            .entry(handle.clone())
+            .or_insert((material.perceptual_roughness, material.metallic));
+        material.perceptual_roughness = roughness + (wetness.wet_roughness - roughness) * w;
+        material.metallic = metallic + (wetness.wet_metallic - metallic) * w;
+    }
+}
+
+// Horizontal offset of the rain velocity center per m/s of wind (see rain_effect)
+const RAIN_WIND_SKEW: f32 = 10.0;
+
+// Wind without gusts while it rains, used to slant the rain
+fn rain_drift(wind: &Wind) -> Vec2 {
+    let velocity = wind.mean_velocity(Weather::Rain.wind_factor());
+    Vec2::new(velocity.x as f32, velocity.y as f32)
+}
+
+// The rain particle effect for an intensity within a particle budget, slanted by the
+// wind `drift` (m/s) and stopping at the `ground` height
+fn rain_effect(
+    intensity: RainIntensity,
+    budget: ParticleBudget,
+    ground: RainGround,
+    drift: Vec2,
+) -> EffectAsset {
     let mut module = Module::default();
 
     // Define expressions
@@ -126,8 +490,19 @@ pub fn setup_rain_system(
     // weather effect radius
     let radius = module.lit(500.0);
 
+    // drops are killed once they leave the box between the ground and the top of the
+    // effect sphere (the box reaches past the sphere sideways)
+    let top = 500.0;
+    let keep_center = module.lit(Vec3::new(0.0, 0.0, 0.5 * (ground.height + top)));
+    let keep_half_size = module.lit(Vec3::new(1000.0, 1000.0, 0.5 * (top - ground.height)));
+
     // particle fall direction (falling down vs rising up)
-    let velocity_center = module.lit(Vec3::new(0.0, 0.0, 80.0)); 
+    // (moved upwind so the drops fall slanted with the wind)
+    let velocity_center = module.lit(Vec3::new(
+        -RAIN_WIND_SKEW * drift.x,
+        -RAIN_WIND_SKEW * drift.y,
+        80.0,
+    ));
 
     // particle speed and acceleration 
     let speed = module.lit(1000.0);
@@ -137,10 +512,10 @@ pub fn setup_rain_system(
     let lifetime = module.lit(8.0);
 
     // Define the rain particle effect
-    let effect = EffectAsset::new(
-        // capacity and spawn rate (rain vs drizzle)
-        1000000,
-        Spawner::rate(40000.0.into()),
+    EffectAsset::new(
+        // capacity and spawn rate (rain vs drizzle, scaled to the budget)
+        budget.capacity(),
+        Spawner::rate((intensity.spawn_rate() * budget.spawn_scale()).into()),
         module,
     )
     .with_name("Rain".to_string())
@@ -155,33 +530,112 @@ pub fn setup_rain_system(
     })
     .init(SetAttributeModifier::new(Attribute::LIFETIME, lifetime))
     .update(AccelModifier::new(accel))
+    .update(KillAabbModifier::new(keep_center, keep_half_size))
     .render(BillboardModifier {})
     .render(ColorOverLifetimeModifier {
         gradient: Gradient::constant(Vec4::new(0.5, 0.5, 1.0, 1.0)),
     })
     .render(SizeOverLifetimeModifier {
         // rain particle size
-        gradient: Gradient::constant(Vec2::splat(0.7)),
+        gradient: Gradient::constant(Vec2::splat(intensity.drop_size())),
         screen_space_size: false,
-    });
-
-    let effect_handle = effects.add(effect);
+    })
+}
 
-    let entity = commands
+fn spawn_rain(
+    commands: &mut Commands,
+    effects: &mut Assets<EffectAsset>,
+    intensity: RainIntensity,
+    budget: ParticleBudget,
+    ground: RainGround,
+    wind: &Wind,
+    visibility: Visibility,
+) -> Entity {
+    let effect_handle = effects.add(rain_effect(intensity, budget, ground, rain_drift(wind)));
+    commands
         .spawn((
             Name::new("RainEffect"),
             ParticleEffectBundle {
                 effect: ParticleEffect::new(effect_handle),
                 transform: Transform::from_translation(Vec3::ZERO),
-                visibility: Visibility::Visible,
+                visibility,
                 ..default()
             },
         ))
-        .id();
+        .id()
+}
 
+pub fn setup_rain_system(
+    mut commands: Commands,
+    mut effects: ResMut<Assets<EffectAsset>>,
+    intensity: Res<RainIntensity>,
+    budget: Res<ParticleBudget>,
+    ground: Res<RainGround>,
+    wind: Res<Wind>,
+) {
+    let entity = spawn_rain(
+        &mut commands,
+        &mut effects,
+        *intensity,
+        *budget,
+        *ground,
+        &wind,
+        Visibility::Visible,
+    );
     commands.insert_resource(RainEffect { entity });
 }
 
+// While it rains, 'E' / 'Q' (KeyBindings::rain_heavier / rain_lighter) step the intensity
+// up / down. The spawner rate, drop size, wind slant, particle capacity and ground height
+// are baked into the effect asset, so the rain effect is replaced with a new one when the
+// intensity, the wind, the ParticleBudget or the RainGround changes.
+pub fn rain_intensity_system(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    keys: Res<KeyBindings>,
+    weather: Res<Weather>,
+    wind: Res<Wind>,
+    budget: Res<ParticleBudget>,
+    ground: Res<RainGround>,
+    mut intensity: ResMut<RainIntensity>,
+    mut rain_effect: ResMut<RainEffect>,
+    mut effects: ResMut<Assets<EffectAsset>>,
+) {
+    if *weather != Weather::Rain {
+        return;
+    }
+    let new_intensity = if keyboard_input.just_pressed(keys.rain_heavier) {
+        intensity.heavier()
+    } else if keyboard_input.just_pressed(keys.rain_lighter) {
+        intensity.lighter()
+    } else {
+        *intensity
+    };
+    // (wind, budget and ground changes while it's dry are picked up when the rain starts)
+    if new_intensity == *intensity
+        && !wind.is_changed()
+        && !budget.is_changed()
+        && !ground.is_changed()
+        && !weather.is_changed()
+    {
+        return;
+    }
+    if new_intensity != *intensity {
+        *intensity = new_intensity;
+        println!("Rain intensity: {:?}", *intensity);
+    }
+    commands.entity(rain_effect.entity).despawn_recursive();
+    rain_effect.entity = spawn_rain(
+        &mut commands,
+        &mut effects,
+        *intensity,
+        *budget,
+        *ground,
+        &wind,
+        Visibility::Visible,
+    );
+}
+
 pub fn toggle_rain_system(
     weather: Res<Weather>,
     rain_effect: Res<RainEffect>,
@@ -196,4 +650,13 @@ pub fn toggle_rain_system(
             }
         }
     }
-}
\ No newline at end of file
+}
+// Keep the screenshot file names tagged with the current weather
+pub fn screenshot_label_system(
+    weather: Res<Weather>,
+    mut settings: ResMut<ScreenshotSettings>,
+) {
+    if weather.is_changed() {
+        settings.label = format!("{:?}", *weather);
+    }
+}
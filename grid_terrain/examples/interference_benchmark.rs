@@ -0,0 +1,147 @@
+use std::time::Instant;
+
+use grid_terrain::{
+    examples::{cached_wave, wave},
+    mesh_element::MeshElement,
+    mirror::Mirror,
+    plane::Plane,
+    rotate::Rotate,
+    step::Step,
+    GridElement, GridTerrain,
+};
+use rigid_body::sva::Vector;
+
+// Times terrain collision queries on a large terrain:
+// - a 50x50 grid of mixed pieces (GridTerrain cell lookup), one point at a time and batched
+// - a single MeshElement of 50x50 bumpy quads (SpatialGrid broadphase inside the element)
+// - the wave terrain, evaluating its functions on every query and from the Function cache
+// Run with --release for meaningful numbers.
+
+const CELLS: usize = 50;
+const CELL_SIZE: f64 = 10.;
+const QUERIES: usize = 200_000;
+
+fn main() {
+    let size = CELLS as f64 * CELL_SIZE;
+    let points = random_points(QUERIES, size);
+
+    // 50x50 grid of planes, steps and bumpy meshes
+    let start = Instant::now();
+    let elements: Vec<Vec<Box<dyn GridElement>>> = (0..CELLS)
+        .map(|row| {
+            (0..CELLS)
+                .map(|column| -> Box<dyn GridElement> {
+                    match (row + column) % 3 {
+                        0 => Box::new(Plane {
+                            size: [CELL_SIZE, CELL_SIZE],
+                            subdivisions: 1,
+                            height: 0.,
+                        }),
+                        1 => Box::new(Step {
+                            size: CELL_SIZE,
+                            height: 0.2,
+                            rotate: Rotate::Zero,
+                            mirror: Mirror::None,
+                        }),
+                        _ => Box::new(bumpy_mesh(8, CELL_SIZE)),
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let grid = GridTerrain::new(elements, [CELL_SIZE, CELL_SIZE]);
+    println!("{0}x{0} grid built in {1:?}", CELLS, start.elapsed());
+
+    time_queries("grid, one point at a time", &points, |points| {
+        points
+            .iter()
+            .filter(|point| grid.interference(**point).is_some())
+            .count()
+    });
+    time_queries("grid, batched", &points, |points| {
+        grid.interference_batch(points)
+            .iter()
+            .filter(|interference| interference.is_some())
+            .count()
+    });
+
+    // one element holding the whole terrain as a mesh
+    let start = Instant::now();
+    let mesh = bumpy_mesh(CELLS, size);
+    println!("{} triangle mesh built in {:?}", 2 * CELLS * CELLS, start.elapsed());
+    time_queries("mesh element", &points, |points| {
+        points
+            .iter()
+            .filter(|point| mesh.interference(**point).is_some())
+            .count()
+    });
+
+    // the wave example of the car environment, 3x3 cells of 20 m
+    let wave_points = random_points(QUERIES, 3. * 20.);
+    let direct = GridTerrain::new(wave(20., 0.3, 4.), [20., 20.]);
+    time_queries("wave, direct", &wave_points, |points| {
+        points
+            .iter()
+            .filter(|point| direct.interference(**point).is_some())
+            .count()
+    });
+    let start = Instant::now();
+    let cached = GridTerrain::new(cached_wave(20., 0.3, 4., 1e-3), [20., 20.]);
+    println!("wave cache (1 mm tolerance) built in {:?}", start.elapsed());
+    time_queries("wave, cached", &wave_points, |points| {
+        points
+            .iter()
+            .filter(|point| cached.interference(**point).is_some())
+            .count()
+    });
+}
+
+fn time_queries(name: &str, points: &[Vector], queries: impl Fn(&[Vector]) -> usize) {
+    let start = Instant::now();
+    let hits = queries(points);
+    let elapsed = start.elapsed();
+    println!(
+        "{}: {} points in {:?} ({:.0} ns per point, {} contacts)",
+        name,
+        points.len(),
+        elapsed,
+        elapsed.as_nanos() as f64 / points.len() as f64,
+        hits
+    );
+}
+
+// Points spread over the terrain, around the ground height (xorshift, so every run
+// queries the same points)
+fn random_points(count: usize, size: f64) -> Vec<Vector> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+    (0..count)
+        .map(|_| Vector::new(next() * size, next() * size, next() * 0.6 - 0.3))
+        .collect()
+}
+
+// Square mesh of `quads` x `quads` quads over `size` with gentle bumps
+fn bumpy_mesh(quads: usize, size: f64) -> MeshElement {
+    let spacing = size / quads as f64;
+    let mut vertices = Vec::with_capacity((quads + 1) * (quads + 1));
+    for y in 0..=quads {
+        for x in 0..=quads {
+            let (x, y) = (x as f64 * spacing, y as f64 * spacing);
+            vertices.push([x, y, 0.05 * (x.sin() + y.cos())]);
+        }
+    }
+    let mut triangles = Vec::with_capacity(2 * quads * quads);
+    for y in 0..quads {
+        for x in 0..quads {
+            let corner = y * (quads + 1) + x;
+            triangles.push([corner, corner + 1, corner + quads + 1]);
+            triangles.push([corner + quads + 2, corner + quads + 1, corner + 1]);
+        }
+    }
+    MeshElement::new(&vertices, &triangles)
+}
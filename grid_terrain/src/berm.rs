@@ -0,0 +1,92 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+use rigid_body::sva::Vector;
+
+use crate::{
+    rotate::{rotate_mesh, rotate_point},
+    GridElement, Interference, Rotate, RotationDirection,
+};
+
+// Defines a banked turn (berm) with given size, bank height and rotation
+// Unrotated, the car drives along y and the surface rises toward the outside of
+// the turn at +x, from 0 at x = 0 to bank_height at x = size.
+#[derive(Default)]
+pub struct Berm {
+    pub size: f64,        // Width/length of the berm base
+    pub bank_height: f64, // Height of the outer edge
+    pub rotate: Rotate,   // Rotation of the berm
+}
+
+impl GridElement for Berm {
+    fn interference(&self, mut point: Vector) -> Option<Interference> {
+        // Transform point into the berm's local space
+        rotate_point(&mut point, self.size, &self.rotate, RotationDirection::Reverse);
+        let size = self.size;
+        let bank_height = self.bank_height;
+
+        // Early exit checks
+        if point.z > bank_height {
+            return None;
+        }
+        if point.x < 0.0 || point.x > size || point.y < 0.0 || point.y > size {
+            return None;
+        }
+
+        // The surface is a plane tilted about the driving direction (y axis)
+        let bank_normal = Vector::new(-bank_height, 0., size).normalize();
+        let normal_interference = -bank_normal.dot(&point);
+
+        if normal_interference < 0.0 {
+            return None;
+        }
+        let mut interference = Interference {
+            magnitude: normal_interference,
+            position: point + normal_interference * bank_normal,
+            normal: bank_normal,
+        };
+        interference.rotate(size, &self.rotate, RotationDirection::Forward);
+        Some(interference)
+    }
+
+    // Creates a tilted quad for the banked surface
+    fn mesh(&self) -> Mesh {
+        let bank_normal = Vec3::new(-self.bank_height as f32, 0., self.size as f32)
+            .normalize()
+            .to_array();
+        let size = self.size as f32;
+        let bank_height = self.bank_height as f32;
+
+        // Define vertices (inner edge on the ground, outer edge raised)
+        let mut positions: Vec<[f32; 3]> = vec![
+            [0., 0., 0.],
+            [size, 0., bank_height],
+            [size, size, bank_height],
+            [0., size, 0.],
+        ];
+
+        let mut normals = vec![bank_normal; 4];
+        let mut uvs = vec![[0., 0.], [1., 0.], [1., 1.], [0., 1.]];
+
+        // Define triangles
+        let indices = vec![[0, 1, 3], [2, 3, 1]];
+
+        rotate_mesh(size, &mut positions, &mut normals, &mut uvs, &self.rotate);
+        let indices: Vec<u32> = indices.into_iter().flatten().collect();
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Berm: bank height {} m, rotate {:?}",
+            self.bank_height, self.rotate
+        )
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+use rigid_body::sva::Vector;
+
+use crate::{
+    rotate::{rotate_mesh, rotate_point},
+    GridElement, Interference, Rotate, RotationDirection,
+};
+
+// Number of strips across the road used to draw the crown
+const LATERAL_SUBDIVISIONS: u32 = 16;
+
+// Defines a crowned road with given size, crown height and rotation
+// Unrotated, the road runs along y and the surface is a parabola across x:
+// 0 at both edges and crown_height at the center line.
+#[derive(Default)]
+pub struct CrownedRoad {
+    pub size: f64,         // Width/length of the road piece
+    pub crown_height: f64, // Height of the center line above the edges
+    pub rotate: Rotate,    // Rotation of the road
+}
+
+impl CrownedRoad {
+    // Height of the surface and its slope (dz/dx) at lateral position x
+    fn height(&self, x: f64) -> (f64, f64) {
+        let u = 2. * x / self.size - 1.; // -1 at the left edge, 1 at the right edge
+        let height = self.crown_height * (1. - u * u);
+        let slope = -4. * self.crown_height * u / self.size;
+        (height, slope)
+    }
+}
+
+impl GridElement for CrownedRoad {
+    fn interference(&self, mut point: Vector) -> Option<Interference> {
+        // Transform point into the road's local space
+        rotate_point(&mut point, self.size, &self.rotate, RotationDirection::Reverse);
+        let size = self.size;
+
+        if point.x < 0.0 || point.x > size || point.y < 0.0 || point.y > size {
+            return None;
+        }
+
+        let (height, slope) = self.height(point.x);
+        if point.z > height {
+            return None;
+        }
+
+        let mut interference = Interference {
+            magnitude: height - point.z,
+            position: Vector::new(point.x, point.y, height),
+            normal: Vector::new(-slope, 0., 1.).normalize(),
+        };
+        interference.rotate(size, &self.rotate, RotationDirection::Forward);
+        Some(interference)
+    }
+
+    // Creates strips across the road so the crown is visible
+    fn mesh(&self) -> Mesh {
+        let size = self.size as f32;
+        let x_vertex_count = LATERAL_SUBDIVISIONS + 1;
+
+        let mut positions: Vec<[f32; 3]> = Vec::new();
+        let mut normals: Vec<[f32; 3]> = Vec::new();
+        let mut uvs: Vec<[f32; 2]> = Vec::new();
+        let mut indices: Vec<[u32; 3]> = Vec::new();
+
+        // Generate vertices, one pair (y = 0 and y = size) per lateral position
+        for x_vert in 0..x_vertex_count {
+            let tx = x_vert as f32 / (x_vertex_count - 1) as f32;
+            let (height, slope) = self.height((tx * size) as f64);
+            let normal = Vec3::new(-slope as f32, 0., 1.).normalize().to_array();
+            for ty in [0., 1.] {
+                positions.push([tx * size, ty * size, height as f32]);
+                normals.push(normal);
+                uvs.push([tx, 1.0 - ty]);
+            }
+        }
+
+        // Generate triangle indices
+        for x_vert in 0..x_vertex_count - 1 {
+            let quad = 2 * x_vert;
+            indices.push([quad, quad + 2, quad + 1]);
+            indices.push([quad + 3, quad + 1, quad + 2]);
+        }
+
+        rotate_mesh(size, &mut positions, &mut normals, &mut uvs, &self.rotate);
+        let indices: Vec<u32> = indices.into_iter().flatten().collect();
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Crowned road: crown height {} m, rotate {:?}",
+            self.crown_height, self.rotate
+        )
+    }
+}
@@ -1,7 +1,8 @@
 use std::f64::consts::PI as PI64;
 use crate::{
-    function::Function, mirror::Mirror, plane::Plane, rotate::Rotate, step::Step,
-    step_slope::StepSlope, GridElement,
+    berm::Berm, crowned_road::CrownedRoad, function::Function, mirror::Mirror, plane::Plane,
+    ramp::Ramp, rotate::Rotate, step::Step, step_slope::StepSlope, surface::Surface2D,
+    GridElement,
 };
 
 /// Creates a table-top pattern using steps and slopes arranged in a 2x3 grid
@@ -77,6 +78,7 @@ pub fn steps(size: f64, heights: Vec<f64>) -> Vec<Vec<Box<dyn GridElement + 'sta
             Box::new(Plane {
                 size: [size, size],
                 subdivisions: 1,
+                height: 0.,
             }),
         ]);
     }
@@ -90,6 +92,26 @@ const TAU64: f64 = 2. * PI64;  // Full circle in radians (2π)
 /// height: Amplitude of the wave
 /// wave_length: Length of one complete wave cycle
 pub fn wave(size: f64, height: f64, wave_length: f64) -> Vec<Vec<Box<dyn GridElement + 'static>>> {
+    wave_pieces(size, height, wave_length, None)
+}
+
+/// The wave pattern with every piece cached (see Function::with_cache)
+/// tolerance: Largest height and slope error of the cache
+pub fn cached_wave(
+    size: f64,
+    height: f64,
+    wave_length: f64,
+    tolerance: f64,
+) -> Vec<Vec<Box<dyn GridElement + 'static>>> {
+    wave_pieces(size, height, wave_length, Some(tolerance))
+}
+
+fn wave_pieces(
+    size: f64,
+    height: f64,
+    wave_length: f64,
+    cache_tolerance: Option<f64>,
+) -> Vec<Vec<Box<dyn GridElement + 'static>>> {
     // Parameter mapping functions for grid boundaries
     let x_start = Box::new(move |x: f64, _y: f64| x / size);        // Maps x to [0,1] at start
     let x_end = Box::new(move |x: f64, _y: f64| 1.0 - x / size);    // Maps x to [1,0] at end
@@ -112,64 +134,218 @@ pub fn wave(size: f64, height: f64, wave_length: f64) -> Vec<Vec<Box<dyn GridEle
     });
 
     let size = [size, size];
+    let piece = |function: Function| -> Box<dyn GridElement + 'static> {
+        match cache_tolerance {
+            Some(tolerance) => Box::new(function.with_cache(tolerance)),
+            None => Box::new(function),
+        }
+    };
 
     // Create 3x3 grid of wave functions with appropriate boundary conditions
     let grid_elements: Vec<Vec<Box<dyn GridElement + 'static>>> = vec![
         // Top row (y_start boundary)
         vec![
-            Box::new(Function {
+            piece(Function {
                 size,
                 functions: vec![z_fun.clone(), x_start.clone(), y_start.clone()],
                 derivatives: vec![z_der.clone(), dx_start.clone(), dy_start.clone()],
+                cache: None,
             }),
-            Box::new(Function {
+            piece(Function {
                 size,
                 functions: vec![z_fun.clone(), y_start.clone()],
                 derivatives: vec![z_der.clone(), dy_start.clone()],
+                cache: None,
             }),
-            Box::new(Function {
+            piece(Function {
                 size,
                 functions: vec![z_fun.clone(), x_end.clone(), y_start.clone()],
                 derivatives: vec![z_der.clone(), dx_end.clone(), dy_start.clone()],
+                cache: None,
             }),
         ],
         // Middle row
         vec![
-            Box::new(Function {
+            piece(Function {
                 size,
                 functions: vec![z_fun.clone(), x_start.clone()],
                 derivatives: vec![z_der.clone(), dx_start.clone()],
+                cache: None,
             }),
-            Box::new(Function {
+            piece(Function {
                 size,
                 functions: vec![z_fun.clone()],
                 derivatives: vec![z_der.clone()],
+                cache: None,
             }),
-            Box::new(Function {
+            piece(Function {
                 size,
                 functions: vec![z_fun.clone(), x_end.clone()],
                 derivatives: vec![z_der.clone(), dx_end.clone()],
+                cache: None,
             }),
         ],
         // Bottom row (y_end boundary)
         vec![
-            Box::new(Function {
+            piece(Function {
                 size,
                 functions: vec![z_fun.clone(), x_start.clone(), y_end.clone()],
                 derivatives: vec![z_der.clone(), dx_start.clone(), dy_end.clone()],
+                cache: None,
             }),
-            Box::new(Function {
+            piece(Function {
                 size,
                 functions: vec![z_fun.clone(), y_end.clone()],
                 derivatives: vec![z_der.clone(), dy_end.clone()],
+                cache: None,
             }),
-            Box::new(Function {
+            piece(Function {
                 size,
                 functions: vec![z_fun.clone(), x_end.clone(), y_end.clone()],
                 derivatives: vec![z_der.clone(), dx_end.clone(), dy_end.clone()],
+                cache: None,
             }),
         ],
     ];
 
     grid_elements
-}
\ No newline at end of file
+}
+/// Creates an oval track: flat straights along x joined by banked turns at both ends
+/// size: Dimension of each grid element
+/// bank_height: Height of the outer edge of the turns
+/// straight_length: Number of flat cells in each straight
+pub fn oval_track(
+    size: f64,
+    bank_height: f64,
+    straight_length: usize,
+) -> Vec<Vec<Box<dyn GridElement + 'static>>> {
+    let mut grid_elements: Vec<Vec<Box<dyn GridElement + 'static>>> = Vec::new();
+    for _ in 0..3 {
+        let mut row: Vec<Box<dyn GridElement + 'static>> = Vec::new();
+        // Left turn, banked up toward -x
+        row.push(Box::new(Berm {
+            size,
+            bank_height,
+            rotate: Rotate::OneEighty,  // Rotated 180 degrees
+        }));
+        // Straights on the outer rows, infield in the middle row
+        for _ in 0..straight_length {
+            row.push(Box::new(Plane {
+                size: [size, size],
+                subdivisions: 1,
+                height: 0.,
+            }));
+        }
+        // Right turn, banked up toward +x
+        row.push(Box::new(Berm {
+            size,
+            bank_height,
+            rotate: Rotate::Zero,
+        }));
+        grid_elements.push(row);
+    }
+    grid_elements
+}
+
+/// Creates a straight crowned road running along y, with flat ground on both sides
+/// size: Dimension of each grid element
+/// crown_height: Height of the road's center line above its edges
+/// length: Number of road cells
+pub fn crowned_road(
+    size: f64,
+    crown_height: f64,
+    length: usize,
+) -> Vec<Vec<Box<dyn GridElement + 'static>>> {
+    let mut grid_elements: Vec<Vec<Box<dyn GridElement + 'static>>> = Vec::new();
+    for _ in 0..length {
+        // For each row: plane -> road -> plane
+        grid_elements.push(vec![
+            Box::new(Plane {
+                size: [size, size],
+                subdivisions: 1,
+                height: 0.,
+            }),
+            Box::new(CrownedRoad {
+                size,
+                crown_height,
+                ..Default::default()
+            }),
+            Box::new(Plane {
+                size: [size, size],
+                subdivisions: 1,
+                height: 0.,
+            }),
+        ]);
+    }
+    grid_elements
+}
+
+/// Creates a jump: a ramp with a launch lip in the middle of a flat area with room to land
+/// size: Dimension of each grid element
+/// height: Height of the ramp's incline (the lip adds a quarter of that)
+pub fn jump(size: f64, height: f64) -> Vec<Vec<Box<dyn GridElement + 'static>>> {
+    let mut grid_elements: Vec<Vec<Box<dyn GridElement + 'static>>> = Vec::new();
+    // Run-up row, ramp row, then landing rows
+    for row in 0..5 {
+        let mut elements: Vec<Box<dyn GridElement + 'static>> = Vec::new();
+        for column in 0..3 {
+            if row == 1 && column == 1 {
+                elements.push(Box::new(Ramp {
+                    size,
+                    height,
+                    lip_height: height / 4.,
+                    ..Default::default()
+                }));
+            } else {
+                elements.push(Box::new(Plane {
+                    size: [size, size],
+                    subdivisions: 1,
+                    height: 0.,
+                }));
+            }
+        }
+        grid_elements.push(elements);
+    }
+    grid_elements
+}
+
+/// Creates a round bump in the middle of a 3x3 patch of flat ground
+/// size: Dimension of each grid element
+/// height: Height of the bump's top
+/// The bump is a gaussian with a standard deviation of a sixth of the cell, so it is
+/// about 1% of its height where it meets the flat cells
+pub fn gaussian_bump(size: f64, height: f64) -> Vec<Vec<Box<dyn GridElement + 'static>>> {
+    let center = size / 2.;
+    let sigma = size / 6.;
+    // Height of the bump and its gradient
+    let bump = move |x: f64, y: f64| {
+        let r2 = (x - center).powi(2) + (y - center).powi(2);
+        height * (-r2 / (2. * sigma * sigma)).exp()
+    };
+    let gradient = move |x: f64, y: f64| {
+        let z = bump(x, y);
+        (-z * (x - center) / (sigma * sigma), -z * (y - center) / (sigma * sigma))
+    };
+
+    let mut grid_elements: Vec<Vec<Box<dyn GridElement + 'static>>> = Vec::new();
+    for row in 0..3 {
+        let mut elements: Vec<Box<dyn GridElement + 'static>> = Vec::new();
+        for column in 0..3 {
+            if row == 1 && column == 1 {
+                elements.push(Box::new(Surface2D {
+                    size: [size, size],
+                    height: Box::new(bump),
+                    gradient: Box::new(gradient),
+                }));
+            } else {
+                elements.push(Box::new(Plane {
+                    size: [size, size],
+                    subdivisions: 1,
+                    height: 0.,
+                }));
+            }
+        }
+        grid_elements.push(elements);
+    }
+    grid_elements
+}
@@ -10,6 +10,8 @@ pub struct Function {
     pub size: [f64; 2],
     pub functions: Vec<Box<dyn Fn(f64, f64) -> f64>>,
     pub derivatives: Vec<Box<dyn Fn(f64, f64) -> (f64, f64)>>,
+    // Precomputed height and slope lattice; None evaluates the functions on every query
+    pub cache: Option<FunctionCache>,
 }
 
 impl Default for Function {
@@ -18,10 +20,124 @@ impl Default for Function {
             size: [10.0, 10.],
             functions: vec![Box::new(|x, _y| x.cos())],
             derivatives: vec![Box::new(|x, _y| (-x.sin(), 0.))],
+            cache: None,
         }
     }
 }
 
+impl Function {
+    // Caches the height and slope on a lattice fine enough that the interpolated
+    // height (m) and slopes stay within `tolerance` of the functions
+    pub fn with_cache(mut self, tolerance: f64) -> Self {
+        self.cache = Some(FunctionCache::new(&self, tolerance));
+        self
+    }
+
+    // Height and slope (height, dz/dx, dz/dy) at a point, from the cache if there is one
+    fn sample(&self, point: Vector) -> (f64, f64, f64) {
+        match &self.cache {
+            Some(cache) => cache.sample(point.x, point.y),
+            None => evaluate(&self.functions, &self.derivatives, point),
+        }
+    }
+}
+
+// Height and slope of a Function sampled on a regular lattice over the element,
+// bilinearly interpolated between the lattice nodes
+pub struct FunctionCache {
+    counts: [usize; 2], // lattice cells along x and y
+    spacing: [f64; 2],
+    values: Vec<(f64, f64, f64)>, // (height, dz/dx, dz/dy) per node, row by row
+}
+
+// Lattice cells per side to start from, and the finest lattice tried
+const CACHE_START_CELLS: usize = 16;
+const CACHE_MAX_CELLS: usize = 1024;
+
+impl FunctionCache {
+    // Doubles the lattice resolution until the interpolated height and slopes at every
+    // lattice cell center are within `tolerance` of the functions (or the finest lattice
+    // is reached)
+    pub fn new(function: &Function, tolerance: f64) -> Self {
+        let mut cells = CACHE_START_CELLS;
+        loop {
+            let cache = Self::build(function, [cells, cells]);
+            let error = cache.max_error(function);
+            if error <= tolerance || cells >= CACHE_MAX_CELLS {
+                if error > tolerance {
+                    println!(
+                        "Function cache error {:.2e} exceeds the tolerance {:.2e} at {} cells",
+                        error, tolerance, cells
+                    );
+                }
+                return cache;
+            }
+            cells *= 2;
+        }
+    }
+
+    fn build(function: &Function, counts: [usize; 2]) -> Self {
+        let spacing = [
+            function.size[0] / counts[0] as f64,
+            function.size[1] / counts[1] as f64,
+        ];
+        let mut values = Vec::with_capacity((counts[0] + 1) * (counts[1] + 1));
+        for j in 0..=counts[1] {
+            for i in 0..=counts[0] {
+                let point = Vector::new(i as f64 * spacing[0], j as f64 * spacing[1], 0.);
+                values.push(evaluate(&function.functions, &function.derivatives, point));
+            }
+        }
+        Self {
+            counts,
+            spacing,
+            values,
+        }
+    }
+
+    // Largest height or slope error at the lattice cell centers, where interpolation is worst
+    fn max_error(&self, function: &Function) -> f64 {
+        let mut max_error: f64 = 0.;
+        for j in 0..self.counts[1] {
+            for i in 0..self.counts[0] {
+                let x = (i as f64 + 0.5) * self.spacing[0];
+                let y = (j as f64 + 0.5) * self.spacing[1];
+                let point = Vector::new(x, y, 0.);
+                let exact = evaluate(&function.functions, &function.derivatives, point);
+                let cached = self.sample(x, y);
+                max_error = max_error
+                    .max((cached.0 - exact.0).abs())
+                    .max((cached.1 - exact.1).abs())
+                    .max((cached.2 - exact.2).abs());
+            }
+        }
+        max_error
+    }
+
+    fn node(&self, i: usize, j: usize) -> (f64, f64, f64) {
+        self.values[j * (self.counts[0] + 1) + i]
+    }
+
+    pub fn sample(&self, x: f64, y: f64) -> (f64, f64, f64) {
+        let u = (x / self.spacing[0]).clamp(0., self.counts[0] as f64);
+        let v = (y / self.spacing[1]).clamp(0., self.counts[1] as f64);
+        let i = (u as usize).min(self.counts[0] - 1);
+        let j = (v as usize).min(self.counts[1] - 1);
+        let (fu, fv) = (u - i as f64, v - j as f64);
+
+        let lerp = |a: (f64, f64, f64), b: (f64, f64, f64), t: f64| {
+            (
+                a.0 + (b.0 - a.0) * t,
+                a.1 + (b.1 - a.1) * t,
+                a.2 + (b.2 - a.2) * t,
+            )
+        };
+        let bottom = lerp(self.node(i, j), self.node(i + 1, j), fu);
+        let top = lerp(self.node(i, j + 1), self.node(i + 1, j + 1), fu);
+        lerp(bottom, top, fv)
+    }
+}
+
 fn evaluate(
     functions: &Vec<Box<dyn Fn(f64, f64) -> f64>>,
     derivatives: &Vec<Box<dyn Fn(f64, f64) -> (f64, f64)>>,
@@ -62,7 +178,7 @@ impl GridElement for Function {
             return None;
         }
 
-        let (height, dx, dy) = evaluate(&self.functions, &self.derivatives, point);
+        let (height, dx, dy) = self.sample(point);
 
         if point.z > height {
             // return immediately if point is above surface
@@ -142,4 +258,60 @@ impl GridElement for Function {
         mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
         mesh
     }
+
+    fn description(&self) -> String {
+        format!("Function: {} terms", self.functions.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::TAU;
+
+    // The corner piece of the wave example: a 0.3 m wave along x, faded in along x and y
+    fn wave_corner() -> Function {
+        let (size, height, wave_length) = (20., 0.3, 4.);
+        let k = TAU / wave_length;
+        Function {
+            size: [size, size],
+            functions: vec![
+                Box::new(move |x, _y| height * (k * x).cos()),
+                Box::new(move |x, _y| x / size),
+                Box::new(move |_x, y| y / size),
+            ],
+            derivatives: vec![
+                Box::new(move |x, _y| (-height * k * (k * x).sin(), 0.)),
+                Box::new(move |_x, _y| (1. / size, 0.)),
+                Box::new(move |_x, _y| (0., 1. / size)),
+            ],
+            cache: None,
+        }
+    }
+
+    #[test]
+    fn cache_stays_within_tolerance() {
+        let tolerance = 1e-3;
+        let function = wave_corner().with_cache(tolerance);
+        let mut worst: (f64, f64, f64) = (0., 0., 0.);
+        // a dense sample that doesn't line up with the lattice
+        for j in 0..=311 {
+            for i in 0..=311 {
+                let point = Vector::new(i as f64 * 20. / 311., j as f64 * 20. / 311., 0.);
+                let cached = function.sample(point);
+                let exact = evaluate(&function.functions, &function.derivatives, point);
+                worst.0 = worst.0.max((cached.0 - exact.0).abs());
+                worst.1 = worst.1.max((cached.1 - exact.1).abs());
+                worst.2 = worst.2.max((cached.2 - exact.2).abs());
+            }
+        }
+        assert!(worst.0 <= tolerance, "height off by {}", worst.0);
+        assert!(worst.1 <= tolerance && worst.2 <= tolerance, "slopes off by {:?}", worst);
+
+        // the contacts use the cached surface
+        let point = Vector::new(7.3, 12.9, -1.);
+        let contact = function.interference(point).expect("under the surface");
+        let exact = evaluate(&function.functions, &function.derivatives, point).0;
+        assert!((contact.magnitude - (exact + 1.)).abs() <= tolerance);
+    }
 }
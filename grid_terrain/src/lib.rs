@@ -1,15 +1,25 @@
 // Import different terrain elements we can use
+pub mod berm;
+pub mod crowned_road;
 pub mod examples;
 pub mod function;
+pub mod mesh_element;
 pub mod mirror;
 pub mod plane;
+pub mod ramp;
 pub mod rotate;
 pub mod slope;
+pub mod spatial;
 pub mod step;
 pub mod step_slope;
+pub mod surface;
+pub mod textured;
 
-use bevy::prelude::*;
+use bevy::{prelude::*, render::mesh::VertexAttributeValues};
 use mirror::Mirror;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
 use rigid_body::sva::Vector;
 use rotate::{Rotate, RotationDirection};
 
@@ -17,14 +27,48 @@ use rotate::{Rotate, RotationDirection};
 // - magnitude: how deep the collision is
 // - position: where the collision happened
 // - normal: direction to push back (like the floor pushing up)
+// This is what the terrain pieces return and may change along with them; code outside
+// the sim (FFI, Python bindings) should use GridTerrain::query and InterferenceData.
 pub struct Interference {
     pub magnitude: f64,
     pub position: Vector,
     pub normal: Vector,
 }
 
+// Plain copy of an Interference for code embedding the sim through a C ABI or bindings:
+// only f64 fields, laid out in this order (#[repr(C)]), see GridTerrain::query
+// - magnitude: how deep the point is in the terrain (m)
+// - position_*: contact point on the terrain surface (m)
+// - normal_*: unit surface normal at the contact point
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InterferenceData {
+    pub magnitude: f64,
+    pub position_x: f64,
+    pub position_y: f64,
+    pub position_z: f64,
+    pub normal_x: f64,
+    pub normal_y: f64,
+    pub normal_z: f64,
+}
+
+impl From<&Interference> for InterferenceData {
+    fn from(interference: &Interference) -> Self {
+        Self {
+            magnitude: interference.magnitude,
+            position_x: interference.position.x,
+            position_y: interference.position.y,
+            position_z: interference.position.z,
+            normal_x: interference.normal.x,
+            normal_y: interference.normal.y,
+            normal_z: interference.normal.z,
+        }
+    }
+}
+
 impl Interference {
-    // Handles mirroring the collision data when we flip terrain pieces
+    // Handles mirroring the collision data when we flip terrain pieces.
+    // Every mirror is its own inverse, mirroring twice gives back the original.
     fn mirror(&mut self, size: f64, mirror: &Mirror) {
         match mirror {
             Mirror::None => {}
@@ -38,10 +82,30 @@ impl Interference {
                 self.position.x = size - self.position.x;
                 self.normal.x = -self.normal.x;
             }
+            Mirror::Diagonal => {
+                // Swap X and Y
+                let x = self.position.x;
+                self.position.x = self.position.y;
+                self.position.y = x;
+                let x = self.normal.x;
+                self.normal.x = self.normal.y;
+                self.normal.y = x;
+            }
+            Mirror::AntiDiagonal => {
+                // Swap and flip X and Y
+                let x = self.position.x;
+                self.position.x = size - self.position.y;
+                self.position.y = size - x;
+                let x = self.normal.x;
+                self.normal.x = -self.normal.y;
+                self.normal.y = -x;
+            }
         }
     }
 
-    // Handles rotating collision data when we rotate terrain pieces
+    // Handles rotating collision data when we rotate terrain pieces.
+    // Reverse undoes Forward for the same Rotate (and matches rotate_point), so an
+    // element can rotate a point into its own frame and the contact back out.
     fn rotate(&mut self, size: f64, rotate: &Rotate, direction: RotationDirection) {
         match (rotate, direction) {
             (Rotate::Zero, _) => {}  // No rotation needed
@@ -86,16 +150,125 @@ impl Interference {
 // This trait defines what any terrain piece needs to implement:
 // - interference: handling collisions
 // - mesh: creating the 3D visual representation
+// - material_color: the base color of the piece (gray unless overridden)
+// - material_texture: a base color image for the piece, multiplied by material_color
+//   (none unless overridden, see textured::Textured)
+// - friction: grip of the surface relative to dry pavement (1 unless overridden)
+// - description: the kind of piece and its parameters, for display when it is picked
+// A custom piece only has to provide interference and mesh; the other methods
+// have defaults so it renders and drives like a plain paved piece.
 pub trait GridElement {
     fn interference(&self, point: Vector) -> Option<Interference>;
     fn mesh(&self) -> Mesh;
+    fn material_color(&self) -> Color {
+        Color::rgb_u8(100, 100, 100)
+    }
+    fn material_texture(&self) -> Option<Handle<Image>> {
+        None
+    }
+    fn friction(&self) -> f64 {
+        1.
+    }
+    fn description(&self) -> String {
+        "Custom piece".to_string()
+    }
+}
+
+// Marks the mesh entity of a grid cell built by GridTerrain::build_meshes
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TerrainCell {
+    pub column: usize,
+    pub row: usize,
+}
+
+// Problems with the layout of the terrain pieces passed to GridTerrain
+// - RaggedRow: a row has a different number of pieces than the first row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridError {
+    RaggedRow {
+        row: usize,
+        length: usize,
+        expected: usize,
+    },
+}
+
+impl std::fmt::Display for GridError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GridError::RaggedRow {
+                row,
+                length,
+                expected,
+            } => write!(
+                f,
+                "grid row {} has {} elements, expected {} like row 0",
+                row, length, expected
+            ),
+        }
+    }
 }
 
+impl std::error::Error for GridError {}
+
+// Points this far outside the grid (m) still count as on its edge
+const SEAM_TOLERANCE: f64 = 1e-6;
+
+// Bisection steps refining a raycast hit (halves the step each time)
+const RAYCAST_BISECTIONS: usize = 16;
+
+// slope_at casts a ray down from this height (m), in steps of SLOPE_PROBE_STEP (m), and
+// reads the normal this far (m) below the surface it hits
+const SLOPE_PROBE_HEIGHT: f64 = 20.;
+const SLOPE_PROBE_STEP: f64 = 0.25;
+const SLOPE_PROBE_DEPTH: f64 = 1e-3;
+
 // Main terrain class that manages a grid of different terrain pieces
 #[derive(Resource)]
 pub struct GridTerrain {
     elements: Vec<Vec<Box<dyn GridElement + 'static>>>,  // 2D grid of terrain pieces
     step: [f64; 2],  // Size of each grid cell [width, height]
+    normal_blend_margin: f64,  // Distance from a cell edge where normals are blended (0 = off)
+    ground: plane::Plane,  // Flat ground around (and in place of) the grid pieces
+    meshes: Option<TerrainMeshes>,  // What build_meshes spawned (None before it runs)
+}
+
+// Entities and materials spawned by GridTerrain::build_meshes
+struct TerrainMeshes {
+    parent: Entity,
+    cells: Vec<Vec<CellMesh>>,  // every cell, row by row
+    materials: HashMap<MaterialKey, Handle<StandardMaterial>>,  // one per distinct element look
+}
+
+// Element color and texture, pieces that share both share a material
+type MaterialKey = ([u8; 4], Option<Handle<Image>>);
+
+#[derive(Clone)]
+struct CellMesh {
+    entity: Entity,
+    mesh: Handle<Mesh>,
+}
+
+impl TerrainMeshes {
+    // Material for an element's color and texture, shared by all pieces that look the same
+    fn material(
+        &mut self,
+        element: &dyn GridElement,
+        materials: &mut ResMut<Assets<StandardMaterial>>,
+    ) -> Handle<StandardMaterial> {
+        let color = element.material_color();
+        let texture = element.material_texture();
+        self.materials
+            .entry((color.as_rgba_u8(), texture.clone()))
+            .or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color: color,
+                    base_color_texture: texture,
+                    perceptual_roughness: 1.0,
+                    ..default()
+                })
+            })
+            .clone()
+    }
 }
 
 // Tell Rust it's safe to share this between threads
@@ -103,68 +276,338 @@ unsafe impl Sync for GridTerrain {}
 unsafe impl Send for GridTerrain {}
 
 impl GridTerrain {
+    // Panics if the rows have different lengths, see try_new
     pub fn new(elements: Vec<Vec<Box<dyn GridElement>>>, step: [f64; 2]) -> Self {
-        Self { elements, step }
+        match Self::try_new(elements, step) {
+            Ok(grid) => grid,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    // Builds the grid, checking that every row has as many pieces as the first
+    // (meshes and lookups assume a rectangular grid)
+    pub fn try_new(
+        elements: Vec<Vec<Box<dyn GridElement>>>,
+        step: [f64; 2],
+    ) -> Result<Self, GridError> {
+        let expected = elements.first().map_or(0, |row| row.len());
+        if let Some((row, elements)) = elements
+            .iter()
+            .enumerate()
+            .find(|(_, row)| row.len() != expected)
+        {
+            return Err(GridError::RaggedRow {
+                row,
+                length: elements.len(),
+                expected,
+            });
+        }
+        Ok(Self {
+            elements,
+            step,
+            normal_blend_margin: 0.,
+            ground: plane::Plane {
+                size: [0., 0.],  // unbounded, the meshes are sized in build_meshes
+                subdivisions: 1,
+                height: 0.,
+            },
+            meshes: None,
+        })
+    }
+
+    // Blends the surface normal with the neighboring cell's normal within `margin`
+    // of a cell edge, so tires don't feel a jolt where two slopes meet.
+    // Off by default, each element's own normal is used as is.
+    pub fn with_normal_blending(mut self, margin: f64) -> Self {
+        self.normal_blend_margin = margin.max(0.);
+        self
+    }
+
+    // Puts the flat ground around the grid at `height` (0 by default), so collisions
+    // outside the grid happen where the ground is drawn (build_meshes uses the same height)
+    pub fn with_ground_height(mut self, height: f64) -> Self {
+        self.ground.height = height;
+        self
+    }
+
+    // Height of the flat ground around the grid
+    pub fn ground_height(&self) -> f64 {
+        self.ground.height
+    }
+
+    // Number of grid cells along x (columns) and y (rows)
+    pub fn cell_count(&self) -> (usize, usize) {
+        let rows = self.elements.len();
+        let columns = self.elements.first().map_or(0, |row| row.len());
+        (columns, rows)
+    }
+
+    // Size of a grid cell [width, height]
+    pub fn cell_size(&self) -> [f64; 2] {
+        self.step
+    }
+
+    // Min and max corners of the grid in the ground plane (z is 0 for both,
+    // element heights aren't known without building their meshes)
+    pub fn extents(&self) -> (Vector, Vector) {
+        let (columns, rows) = self.cell_count();
+        (
+            Vector::zeros(),
+            Vector::new(columns as f64 * self.step[0], rows as f64 * self.step[1], 0.),
+        )
+    }
+
+    // Base color of the piece in a cell (None outside the grid)
+    pub fn cell_color(&self, column: usize, row: usize) -> Option<Color> {
+        self.elements
+            .get(row)
+            .and_then(|elements| elements.get(column))
+            .map(|element| element.material_color())
+    }
+
+    // Terrain piece in a cell (None outside the grid)
+    pub fn element(&self, column: usize, row: usize) -> Option<&dyn GridElement> {
+        self.elements
+            .get(row)
+            .and_then(|elements| elements.get(column))
+            .map(|element| element.as_ref())
+    }
+
+    // Grid cell [column, row] under the point (None outside the grid)
+    pub fn cell_at(&self, point: &Vector) -> Option<[usize; 2]> {
+        self.cell_index(point)
     }
 
     // Check if a point interferes (collides) with any terrain piece
     pub fn interference(&self, point: Vector) -> Option<Interference> {
-        // Handle points beyond the left or bottom edge
-        if point.x < 0. || point.y < 0. {
-            if point.z < 0. {  // Below ground level
-                return Some(Interference {
-                    magnitude: -point.z,
-                    position: Vector::new(point.x, point.y, 0.),
-                    normal: Vector::z(),
-                });
+        let mut interference = self.cell_interference(point)?;
+        if self.normal_blend_margin > 0. {
+            self.blend_normal(&mut interference, &point);
+        }
+        Some(interference)
+    }
+
+    // Interference at (x, y, z) as plain data, for exposing the terrain through a thin
+    // FFI layer (None when the point is above the terrain)
+    pub fn query(&self, x: f64, y: f64, z: f64) -> Option<InterferenceData> {
+        self.interference(Vector::new(x, y, z))
+            .map(|interference| InterferenceData::from(&interference))
+    }
+
+    // Friction multiplier of the terrain piece under the point (1 outside the grid)
+    pub fn friction_at(&self, point: &Vector) -> f64 {
+        self.cell_index(point)
+            .and_then(|[x_index, y_index]| self.elements.get(y_index)?.get(x_index))
+            .map_or(1., |element| element.friction())
+    }
+
+    // Distance along the ray from `origin` in `direction` to the terrain surface, or None
+    // if it isn't reached within `max_distance`. The ray is marched in `step` increments
+    // and the first hit refined by bisection, so surfaces thinner than `step` can be
+    // missed. Some(0) if the origin is already inside the terrain.
+    pub fn raycast(
+        &self,
+        origin: Vector,
+        direction: Vector,
+        max_distance: f64,
+        step: f64,
+    ) -> Option<f64> {
+        let direction = direction.try_normalize(f64::EPSILON)?;
+        let inside = |distance: f64| self.interference(origin + direction * distance).is_some();
+        if inside(0.) {
+            return Some(0.);
+        }
+
+        let step = step.max(1e-3);
+        let mut outside = 0.;
+        while outside < max_distance {
+            let next = (outside + step).min(max_distance);
+            if inside(next) {
+                let mut hit = next;
+                for _ in 0..RAYCAST_BISECTIONS {
+                    let middle = 0.5 * (outside + hit);
+                    if inside(middle) {
+                        hit = middle;
+                    } else {
+                        outside = middle;
+                    }
+                }
+                return Some(outside);
             }
-            return None;
+            outside = next;
         }
+        None
+    }
 
-        // Figure out which grid cell we're in
-        let x_index = (point.x / self.step[0]) as usize;
-        let y_index = (point.y / self.step[1]) as usize;
+    // Slope of the surface at (x, y) as angles (rad) along x and along y, positive where
+    // the surface rises toward +x / +y. The grade along a heading psi is
+    // atan(cos(psi) tan(slope_x) + sin(psi) tan(slope_y)).
+    // The surface is the first one hit going down from SLOPE_PROBE_HEIGHT, None if there
+    // is none within twice that height.
+    pub fn slope_at(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let origin = Vector::new(x, y, SLOPE_PROBE_HEIGHT);
+        let down = -Vector::z();
+        let distance = self.raycast(origin, down, 2. * SLOPE_PROBE_HEIGHT, SLOPE_PROBE_STEP)?;
+        let normal = self
+            .interference(origin + down * (distance + SLOPE_PROBE_DEPTH))?
+            .normal;
+        Some(((-normal.x).atan2(normal.z), (-normal.y).atan2(normal.z)))
+    }
 
-        // Convert world coordinates to local grid cell coordinates
-        let local_offset = Vector::new(
+    // Grid cell [column, row] containing the point, or None outside the grid.
+    // A point on the edge between two cells belongs to the cell on its +x / +y side,
+    // except on the far edges of the grid where it belongs to the last cell. Points
+    // within SEAM_TOLERANCE outside the grid (rounding error) belong to the edge cell.
+    fn cell_index(&self, point: &Vector) -> Option<[usize; 2]> {
+        let (columns, rows) = self.cell_count();
+        let counts = [columns, rows];
+        let mut index = [0; 2];
+        for axis in 0..2 {
+            let cell = point[axis] / self.step[axis];
+            let tolerance = SEAM_TOLERANCE / self.step[axis];
+            if cell < -tolerance || cell > counts[axis] as f64 + tolerance {
+                return None;
+            }
+            index[axis] = (cell.max(0.).floor() as usize).min(counts[axis].saturating_sub(1));
+        }
+        Some(index)
+    }
+
+    // Offset of a cell's corner from the grid origin
+    fn cell_offset(&self, [x_index, y_index]: [usize; 2]) -> Vector {
+        Vector::new(
             x_index as f64 * self.step[0],
             y_index as f64 * self.step[1],
             0.,
-        );
-        let local_point = point - local_offset;
-
-        // Check for collision with the terrain piece in this cell
-        if let Some(y_elements) = self.elements.get(y_index) {
-            if let Some(element) = y_elements.get(x_index) {
-                if let Some(mut interference) = element.interference(local_point) {
-                    interference.position += local_offset;
-                    return Some(interference);
-                }
-                return None;
+        )
+    }
+
+    // Point in a cell's local coordinates, clamped into the cell so rounding at the
+    // seams can't push it just outside the element (which would skip the collision)
+    fn local_point(&self, point: &Vector, offset: &Vector) -> Vector {
+        let mut local = point - offset;
+        local.x = local.x.clamp(0., self.step[0]);
+        local.y = local.y.clamp(0., self.step[1]);
+        local
+    }
+
+    // Interference with the single terrain piece (or ground) under the point
+    fn cell_interference(&self, point: Vector) -> Option<Interference> {
+        // Points beyond the edges of the grid only collide with the ground
+        let Some(cell) = self.cell_index(&point) else {
+            return self.ground.interference(point);
+        };
+        let Some(element) = self.elements.get(cell[1]).and_then(|row| row.get(cell[0])) else {
+            return self.ground.interference(point);
+        };
+
+        // Check for collision with the terrain piece in this cell (in its local coordinates)
+        let local_offset = self.cell_offset(cell);
+        let mut interference = element.interference(self.local_point(&point, &local_offset))?;
+        interference.position += local_offset;
+        Some(interference)
+    }
+
+    // Same as calling `interference` for every point, but points are grouped by
+    // grid cell so each cell's element is looked up once (e.g. for multi-point tires).
+    // Results are returned in the same order as `points`.
+    pub fn interference_batch(&self, points: &[Vector]) -> Vec<Option<Interference>> {
+        let mut results: Vec<Option<Interference>> = Vec::with_capacity(points.len());
+        results.resize_with(points.len(), || None);
+
+        // Points beyond the edges of the grid only collide with the ground
+        let mut cells: Vec<([usize; 2], usize)> = Vec::with_capacity(points.len());
+        for (i, point) in points.iter().enumerate() {
+            match self.cell_index(point) {
+                Some(cell) => cells.push((cell, i)),
+                None => results[i] = self.ground.interference(*point),
             }
         }
+        cells.sort_unstable_by_key(|(cell, _)| *cell);
 
-        // If we're beyond the grid but below ground, treat as ground collision
-        if point.z < 0. {
-            return Some(Interference {
-                magnitude: -point.z,
-                position: Vector::new(point.x, point.y, 0.),
-                normal: Vector::z(),
-            });
+        let mut start = 0;
+        while start < cells.len() {
+            let cell = cells[start].0;
+            let mut end = start + 1;
+            while end < cells.len() && cells[end].0 == cells[start].0 {
+                end += 1;
+            }
+            let group = &cells[start..end];
+            start = end;
+
+            let local_offset = self.cell_offset(cell);
+            let element = self.elements.get(cell[1]).and_then(|row| row.get(cell[0]));
+            for &(_, i) in group {
+                let result = match element {
+                    Some(element) => element
+                        .interference(self.local_point(&points[i], &local_offset))
+                        .map(|mut interference| {
+                            interference.position += local_offset;
+                            interference
+                        }),
+                    None => self.ground.interference(points[i]),
+                };
+                results[i] = result.map(|mut interference| {
+                    if self.normal_blend_margin > 0. {
+                        self.blend_normal(&mut interference, &points[i]);
+                    }
+                    interference
+                });
+            }
+        }
+        results
+    }
+
+    // Near a cell edge, mixes in the surface normal of the neighboring cell (sampled just
+    // across the edge). The weight goes from 0 at `normal_blend_margin` to 1/2 on the edge,
+    // so both cells agree on the normal at the seam.
+    // Only upward facing normals are blended, walls of steps keep their own normal.
+    fn blend_normal(&self, interference: &mut Interference, point: &Vector) {
+        let margin = self.normal_blend_margin;
+        for axis in 0..2 {
+            if interference.normal.z <= 0. {
+                return;
+            }
+            let step = self.step[axis];
+            let local = point[axis] - (point[axis] / step).floor() * step;
+            let (distance, edge) = if local < margin {
+                (local, point[axis] - local - 1e-9)
+            } else if step - local < margin {
+                (step - local, point[axis] - local + step + 1e-9)
+            } else {
+                continue;
+            };
+
+            let mut neighbor_point = *point;
+            neighbor_point[axis] = edge;
+            let Some(neighbor) = self.cell_interference(neighbor_point) else {
+                continue;
+            };
+            if neighbor.normal.z <= 0. {
+                continue;
+            }
+
+            let weight = 0.5 * (1. - distance / margin);
+            interference.normal =
+                ((1. - weight) * interference.normal + weight * neighbor.normal).normalize();
         }
-        return None;
     }
 
     // Creates all the 3D meshes for visualization
+    // The cell entities and meshes are kept so cells can be re-meshed one at a time
+    // (rebuild_cell).
     pub fn build_meshes(
-        &self,
+        &mut self,
         commands: &mut Commands,
         meshes: &mut ResMut<Assets<Mesh>>,
         materials: &mut ResMut<Assets<StandardMaterial>>,
         parent: Entity,
     ) {
-        let x_grid_size = self.elements[0].len() as f64 * self.step[0];
-        let y_grid_size = self.elements.len() as f64 * self.step[1];
+        // an empty grid is all flat ground (drawn at the ground plane's height, where
+        // the car stands on it)
+        let (columns, rows) = self.cell_count();
+        let x_grid_size = columns as f64 * self.step[0];
+        let y_grid_size = rows as f64 * self.step[1];
         let extended_size = 500.;  // How far to extend the ground plane
 
         // Add flat ground planes around our terrain grid
@@ -179,6 +622,10 @@ impl GridTerrain {
                 if x_offsets[x_ind] == 0.0 && y_offsets[y_ind] == 0.0 {
                     continue;
                 }
+                // the pieces along an empty grid have no area
+                if x_sizes[x_ind] == 0.0 || y_sizes[y_ind] == 0.0 {
+                    continue;
+                }
                 let material = materials.add(StandardMaterial {
                     base_color: Color::rgb_u8(140, 120, 100),  // Brown-ish color
                     perceptual_roughness: 1.0,
@@ -189,6 +636,7 @@ impl GridTerrain {
                         plane::Plane {
                             size: [x_sizes[x_ind], y_sizes[y_ind]],
                             subdivisions: 1,
+                            height: self.ground.height,
                         }
                         .mesh(),
                     ),
@@ -205,29 +653,573 @@ impl GridTerrain {
         }
 
         // Create meshes for our actual terrain pieces
-        let material = materials.add(StandardMaterial {
-            base_color: Color::rgb_u8(100, 100, 100),  // Gray color
-            perceptual_roughness: 1.0,
-            ..default()
+        let mut spawned = TerrainMeshes {
+            parent,
+            cells: Vec::with_capacity(self.elements.len()),
+            materials: HashMap::new(),
+        };
+        for y_index in 0..self.elements.len() {
+            let row = (0..self.elements[y_index].len())
+                .map(|x_index| {
+                    self.spawn_cell([x_index, y_index], commands, meshes, materials, &mut spawned)
+                })
+                .collect();
+            spawned.cells.push(row);
+        }
+        self.meshes = Some(spawned);
+    }
+
+    // Spawns the mesh entity of one cell under the terrain parent
+    fn spawn_cell(
+        &self,
+        [x_index, y_index]: [usize; 2],
+        commands: &mut Commands,
+        meshes: &mut ResMut<Assets<Mesh>>,
+        materials: &mut ResMut<Assets<StandardMaterial>>,
+        spawned: &mut TerrainMeshes,
+    ) -> CellMesh {
+        let element = &self.elements[y_index][x_index];
+        let x_offset = x_index as f32 * self.step[0] as f32;
+        let y_offset = y_index as f32 * self.step[1] as f32;
+
+        let transform = Transform::from_translation(Vec3 {
+            x: x_offset,
+            y: y_offset,
+            z: 0.,
         });
+        let material = spawned.material(element.as_ref(), materials);
+        let mesh = meshes.add(element.mesh());
+        let mut entity = commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material,
+                transform,
+                ..default()
+            },
+            TerrainCell {
+                column: x_index,
+                row: y_index,
+            },
+        ));
+        entity.set_parent(spawned.parent);
+        CellMesh {
+            entity: entity.id(),
+            mesh,
+        }
+    }
+
+    // Regenerates the mesh of one cell from its piece (e.g. after replace_element) without
+    // touching the rest of the terrain. The cell keeps its entity: the mesh asset is
+    // replaced in place and the cell's material set again for the piece's color and texture.
+    // False if the cell has no mesh (outside the grid or before build_meshes).
+    pub fn rebuild_cell(
+        &mut self,
+        column: usize,
+        row: usize,
+        commands: &mut Commands,
+        meshes: &mut ResMut<Assets<Mesh>>,
+        materials: &mut ResMut<Assets<StandardMaterial>>,
+    ) -> bool {
+        let Some(element) = self.elements.get(row).and_then(|elements| elements.get(column))
+        else {
+            return false;
+        };
+        let Some(spawned) = self.meshes.as_mut() else {
+            return false;
+        };
+        let Some(cell) = spawned.cells.get(row).and_then(|cells| cells.get(column)).cloned()
+        else {
+            return false;
+        };
+        // the handle is kept, so the mesh asset is still there
+        if let Some(mesh) = meshes.get_mut(&cell.mesh) {
+            *mesh = element.mesh();
+        }
+        let material = spawned.material(element.as_ref(), materials);
+        commands.entity(cell.entity).insert(material);
+        true
+    }
+
+    // Puts `element` in a cell and returns the piece it replaces (None outside the grid,
+    // `element` is dropped then). After build_meshes the cell is re-meshed (rebuild_cell).
+    pub fn replace_element(
+        &mut self,
+        column: usize,
+        row: usize,
+        element: Box<dyn GridElement>,
+        commands: &mut Commands,
+        meshes: &mut ResMut<Assets<Mesh>>,
+        materials: &mut ResMut<Assets<StandardMaterial>>,
+    ) -> Option<Box<dyn GridElement>> {
+        let cell = self.elements.get_mut(row)?.get_mut(column)?;
+        let replaced = std::mem::replace(cell, element);
+        self.rebuild_cell(column, row, commands, meshes, materials);
+        Some(replaced)
+    }
+
+    // Mesh entity of a cell (None outside the grid or before build_meshes)
+    pub fn cell_entity(&self, column: usize, row: usize) -> Option<Entity> {
+        Some(self.meshes.as_ref()?.cells.get(row)?.get(column)?.entity)
+    }
+
+    // Writes the terrain pieces as one Wavefront OBJ file (positions, normals and
+    // triangles), each piece moved to its grid cell. The ground around the grid is
+    // not included.
+    pub fn export_obj(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_obj(&mut file)?;
+        file.flush()
+    }
+
+    // OBJ text of the terrain pieces, see export_obj
+    pub fn write_obj(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "# grid terrain")?;
+        // OBJ indices count from 1 across the whole file, so each piece's triangles
+        // are shifted by the number of vertices (and normals) written before it. Pieces
+        // without normals write none, so the two counts can differ.
+        let mut vertex_offset = 1;
+        let mut normal_offset = 1;
         for (y_index, y_elements) in self.elements.iter().enumerate() {
             for (x_index, element) in y_elements.iter().enumerate() {
-                let x_offset = x_index as f32 * self.step[0] as f32;
-                let y_offset = y_index as f32 * self.step[1] as f32;
+                let mesh = element.mesh();
+                let Some(VertexAttributeValues::Float32x3(positions)) =
+                    mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+                else {
+                    continue;
+                };
+                let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+                    Some(VertexAttributeValues::Float32x3(normals)) => Some(normals),
+                    _ => None,
+                };
 
-                let transform = Transform::from_translation(Vec3 {
-                    x: x_offset,
-                    y: y_offset,
-                    z: 0.,
-                });
-                let mut entity = commands.spawn(PbrBundle {
-                    mesh: meshes.add(element.mesh()),
-                    material: material.clone(),
-                    transform,
-                    ..default()
-                });
-                entity.set_parent(parent);
+                writeln!(writer, "o cell_{}_{}", x_index, y_index)?;
+                let offset = self.cell_offset([x_index, y_index]);
+                for [x, y, z] in positions {
+                    writeln!(
+                        writer,
+                        "v {} {} {}",
+                        *x as f64 + offset.x,
+                        *y as f64 + offset.y,
+                        z
+                    )?;
+                }
+                for [x, y, z] in normals.into_iter().flatten() {
+                    writeln!(writer, "vn {} {} {}", x, y, z)?;
+                }
+
+                // meshes without an index buffer list their triangles vertex by vertex
+                let indices: Vec<usize> = match mesh.indices() {
+                    Some(indices) => indices.iter().collect(),
+                    None => (0..positions.len()).collect(),
+                };
+                for triangle in indices.chunks_exact(3) {
+                    let [a, b, c] = [0, 1, 2].map(|i| triangle[i] + vertex_offset);
+                    if normals.is_some() {
+                        let [na, nb, nc] = [0, 1, 2].map(|i| triangle[i] + normal_offset);
+                        writeln!(writer, "f {a}//{na} {b}//{nb} {c}//{nc}")?;
+                    } else {
+                        writeln!(writer, "f {a} {b} {c}")?;
+                    }
+                }
+                vertex_offset += positions.len();
+                normal_offset += normals.map_or(0, |normals| normals.len());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::render::render_resource::PrimitiveTopology;
+
+    const EPSILON: f64 = 1e-9;
+
+    const ROTATIONS: [Rotate; 4] = [
+        Rotate::Zero,
+        Rotate::Ninety,
+        Rotate::OneEighty,
+        Rotate::TwoSeventy,
+    ];
+
+    const MIRRORS: [Mirror; 5] = [
+        Mirror::None,
+        Mirror::XZ,
+        Mirror::YZ,
+        Mirror::Diagonal,
+        Mirror::AntiDiagonal,
+    ];
+
+    fn sample_interference() -> Interference {
+        Interference {
+            magnitude: 0.1,
+            position: Vector::new(1.5, 3.25, 0.4),
+            normal: Vector::new(0.3, -0.2, 0.9).normalize(),
+        }
+    }
+
+    fn assert_close(actual: &Vector, expected: &Vector) {
+        assert!(
+            (actual - expected).norm() < EPSILON,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn rotate_forward_then_reverse_is_identity() {
+        let original = sample_interference();
+        for rotate in ROTATIONS {
+            let mut interference = sample_interference();
+            interference.rotate(4., &rotate, RotationDirection::Forward);
+            interference.rotate(4., &rotate, RotationDirection::Reverse);
+            assert_close(&interference.position, &original.position);
+            assert_close(&interference.normal, &original.normal);
+        }
+    }
+
+    #[test]
+    fn ground_contact_at_track_height() {
+        let track_height = -0.05;
+        let grid = GridTerrain::new(vec![], [1., 1.]).with_ground_height(track_height);
+        for (x, y) in [(0., 0.), (0.5, 0.5), (-30., 12.)] {
+            let below = grid
+                .interference(Vector::new(x, y, track_height - 0.01))
+                .expect("a point under the track touches the ground");
+            assert!((below.magnitude - 0.01).abs() < EPSILON);
+            assert!((below.position.z - track_height).abs() < EPSILON);
+            assert_close(&below.normal, &Vector::z());
+            // between the old z = 0 ground and the track there is no contact any more
+            assert!(grid.interference(Vector::new(x, y, track_height + 0.01)).is_none());
+        }
+
+        // a Plane grid piece at the track height matches the ground around it
+        let plane: Box<dyn GridElement> = Box::new(plane::Plane {
+            size: [10., 10.],
+            subdivisions: 1,
+            height: track_height,
+        });
+        let grid = GridTerrain::new(vec![vec![plane]], [10., 10.]).with_ground_height(track_height);
+        for x in [5., 15.] {
+            let contact = grid.interference(Vector::new(x, 5., track_height - 0.02)).unwrap();
+            assert!((contact.magnitude - 0.02).abs() < EPSILON);
+            assert!((contact.position.z - track_height).abs() < EPSILON);
+        }
+    }
+
+    // A flat triangle whose mesh has no normals
+    struct BareTriangle;
+
+    impl GridElement for BareTriangle {
+        fn interference(&self, _point: Vector) -> Option<Interference> {
+            None
+        }
+
+        fn mesh(&self) -> Mesh {
+            let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+            mesh.insert_attribute(
+                Mesh::ATTRIBUTE_POSITION,
+                vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]],
+            );
+            mesh
+        }
+    }
+
+    fn flat_plane(size: f64) -> Box<dyn GridElement> {
+        Box::new(plane::Plane {
+            size: [size, size],
+            subdivisions: 2,
+            height: 0.,
+        })
+    }
+
+    #[test]
+    fn obj_export_counts_and_normal_indices() {
+        let elements: Vec<Vec<Box<dyn GridElement>>> = vec![
+            vec![flat_plane(4.), Box::new(BareTriangle)],
+            vec![Box::new(BareTriangle), flat_plane(4.)],
+        ];
+        let grid = GridTerrain::new(elements, [4., 4.]);
+        let expected_vertices: usize = grid
+            .elements
+            .iter()
+            .flatten()
+            .map(|element| element.mesh().count_vertices())
+            .sum();
+
+        let mut obj = Vec::new();
+        grid.write_obj(&mut obj).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        let vertices = obj.lines().filter(|line| line.starts_with("v ")).count();
+        let normals = obj.lines().filter(|line| line.starts_with("vn ")).count();
+        assert_eq!(vertices, expected_vertices);
+
+        // every face corner points at a written vertex and normal, and the last piece's
+        // faces use the last normals (not indices shifted by the pieces without normals)
+        let mut largest_normal = 0;
+        for face in obj.lines().filter(|line| line.starts_with("f ")) {
+            for corner in face.split_whitespace().skip(1) {
+                let mut indices = corner.split("//");
+                let vertex: usize = indices.next().unwrap().parse().unwrap();
+                assert!((1..=vertices).contains(&vertex), "{}", face);
+                if let Some(normal) = indices.next() {
+                    let normal: usize = normal.parse().unwrap();
+                    assert!((1..=normals).contains(&normal), "{}", face);
+                    largest_normal = largest_normal.max(normal);
+                }
+            }
+        }
+        assert_eq!(largest_normal, normals);
+    }
+
+    #[test]
+    fn mirror_twice_is_identity() {
+        let original = sample_interference();
+        for mirror in MIRRORS {
+            let mut interference = sample_interference();
+            interference.mirror(4., &mirror);
+            interference.mirror(4., &mirror);
+            assert_close(&interference.position, &original.position);
+            assert_close(&interference.normal, &original.normal);
+        }
+    }
+
+    #[test]
+    fn try_new_rejects_ragged_rows() {
+        let ragged = vec![
+            vec![flat_plane(4.), flat_plane(4.)],
+            vec![flat_plane(4.)],
+        ];
+        assert_eq!(
+            GridTerrain::try_new(ragged, [4., 4.]).err(),
+            Some(GridError::RaggedRow {
+                row: 1,
+                length: 1,
+                expected: 2,
+            })
+        );
+
+        let rectangular = vec![
+            vec![flat_plane(4.), flat_plane(4.)],
+            vec![flat_plane(4.), flat_plane(4.)],
+        ];
+        assert!(GridTerrain::try_new(rectangular, [4., 4.]).is_ok());
+    }
+
+    // One row of three 4 m plane cells, each at its own height so contacts show the cell
+    fn stepped_row() -> GridTerrain {
+        let row = [0.1, 0.2, 0.3]
+            .iter()
+            .map(|&height| -> Box<dyn GridElement> {
+                Box::new(plane::Plane {
+                    size: [4., 4.],
+                    subdivisions: 1,
+                    height,
+                })
+            })
+            .collect();
+        GridTerrain::new(vec![row], [4., 4.])
+    }
+
+    #[test]
+    fn seam_points_resolve_to_one_cell() {
+        let grid = stepped_row();
+        // (x, expected column): seams go to the +x cell, the far edge to the last cell
+        // and tiny negative x to the first cell
+        for (x, column) in [(4., 1), (8., 2), (-1e-9, 0), (12., 2), (12. + 1e-9, 2)] {
+            let point = Vector::new(x, 2., 0.);
+            assert_eq!(grid.cell_at(&point), Some([column, 0]), "x = {}", x);
+            let contact = grid.interference(point).expect("the point is under the cell");
+            let height = 0.1 * (column + 1) as f64;
+            assert!((contact.magnitude - height).abs() < EPSILON, "x = {}", x);
+        }
+
+        // clearly outside the grid only the ground is hit
+        let outside = Vector::new(-0.01, 2., -0.05);
+        assert_eq!(grid.cell_at(&outside), None);
+        assert!((grid.interference(outside).unwrap().magnitude - 0.05).abs() < EPSILON);
+    }
+
+    // 2 x 2 grid of 4 m slopes facing different ways
+    fn slope_grid() -> GridTerrain {
+        let slope = |rotate| -> Box<dyn GridElement> {
+            Box::new(slope::Slope {
+                size: 4.,
+                height: 1.,
+                rotate,
+            })
+        };
+        GridTerrain::new(
+            vec![
+                vec![slope(Rotate::Zero), slope(Rotate::Ninety)],
+                vec![slope(Rotate::OneEighty), slope(Rotate::TwoSeventy)],
+            ],
+            [4., 4.],
+        )
+    }
+
+    fn assert_same(actual: &Option<Interference>, expected: &Option<Interference>) {
+        match (actual, expected) {
+            (Some(actual), Some(expected)) => {
+                assert!((actual.magnitude - expected.magnitude).abs() < EPSILON);
+                assert_close(&actual.position, &expected.position);
+                assert_close(&actual.normal, &expected.normal);
             }
+            (None, None) => {}
+            _ => panic!("contact {} != {}", actual.is_some(), expected.is_some()),
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn interference_batch_matches_interference() {
+        // inside each cell, on seams, above the surface and outside the grid (ground)
+        let points: Vec<Vector> = [
+            (1., 1., 0.1),
+            (6.5, 1.2, 0.2),
+            (3., 7., -0.1),
+            (5., 5., 0.3),
+            (4., 4., 0.05),
+            (4., 1., 0.),
+            (2., 2., 3.),
+            (-1., 2., -0.2),
+            (9., 9., 0.1),
+            (20., -3., -0.01),
+            (1.5, 1.5, 0.2),
+        ]
+        .iter()
+        .map(|&(x, y, z)| Vector::new(x, y, z))
+        .collect();
+
+        for grid in [slope_grid(), slope_grid().with_normal_blending(0.5)] {
+            let batch = grid.interference_batch(&points);
+            assert_eq!(batch.len(), points.len());
+            assert!(batch.iter().any(Option::is_some) && batch.iter().any(Option::is_none));
+            for (point, result) in points.iter().zip(&batch) {
+                assert_same(result, &grid.interference(*point));
+            }
+        }
+    }
+
+    #[test]
+    fn blended_normals_are_continuous_across_a_seam() {
+        // a flat cell next to a slope, both over the point at (4, 1, 0.2)
+        let grid = || {
+            let flat: Box<dyn GridElement> = Box::new(plane::Plane {
+                size: [4., 4.],
+                subdivisions: 1,
+                height: 1.,
+            });
+            let slope: Box<dyn GridElement> = Box::new(slope::Slope {
+                size: 4.,
+                height: 1.,
+                rotate: Rotate::Zero,
+            });
+            GridTerrain::new(vec![vec![flat, slope]], [4., 4.])
+        };
+        let normal = |grid: &GridTerrain, x: f64| {
+            grid.interference(Vector::new(x, 1., 0.2)).unwrap().normal
+        };
+        let slope_normal = Vector::new(0., 1., 4.).normalize();
+
+        // off: each side keeps its own normal
+        let sharp = grid();
+        assert_close(&normal(&sharp, 4. - 1e-6), &Vector::z());
+        assert_close(&normal(&sharp, 4. + 1e-6), &slope_normal);
+
+        // on: both sides of the seam agree, and far from the seam nothing changes
+        let blended = grid().with_normal_blending(0.5);
+        let left = normal(&blended, 4. - 1e-6);
+        let right = normal(&blended, 4. + 1e-6);
+        assert!((left - right).norm() < 1e-5, "{:?} != {:?}", left, right);
+        assert!((left - Vector::z()).norm() > 0.05);
+        assert_close(&normal(&blended, 2.), &Vector::z());
+        assert_close(&normal(&blended, 6.), &slope_normal);
+    }
+
+    #[test]
+    fn slope_at_reads_the_surface_angles() {
+        // a step slope (flat half at x < 2, face falling toward +y at x > 2) and a raised plane
+        let step_slope: Box<dyn GridElement> = Box::new(step_slope::StepSlope {
+            size: 4.,
+            height: 1.,
+            rotate: Rotate::Zero,
+            mirror: Mirror::None,
+        });
+        let flat: Box<dyn GridElement> = Box::new(plane::Plane {
+            size: [4., 4.],
+            subdivisions: 1,
+            height: 0.3,
+        });
+        let grid =
+            GridTerrain::new(vec![vec![step_slope, flat]], [4., 4.]).with_ground_height(-30.);
+        let assert_angles = |x: f64, y: f64, expected: (f64, f64)| {
+            let (slope_x, slope_y) = grid.slope_at(x, y).expect("there is a surface below");
+            assert!((slope_x - expected.0).abs() < 1e-6, "slope_x {} at ({}, {})", slope_x, x, y);
+            assert!((slope_y - expected.1).abs() < 1e-6, "slope_y {} at ({}, {})", slope_y, x, y);
+        };
+
+        assert_angles(1., 2., (0., 0.));
+        assert_angles(3., 2., (0., -(0.25_f64).atan()));
+        assert_angles(6., 1., (0., 0.));
+
+        // off the grid the ground is further down than the probe reaches
+        assert_eq!(grid.slope_at(-10., 2.), None);
+    }
+
+    // A custom piece as a user would write one: a constant slope rising `grade` m per m
+    // along x, with only the required methods
+    struct Wall {
+        size: f64,
+        grade: f64,
+    }
+
+    impl GridElement for Wall {
+        fn interference(&self, point: Vector) -> Option<Interference> {
+            let normal = Vector::new(-self.grade, 0., 1.).normalize();
+            let depth = (self.grade * point.x - point.z) * normal.z;
+            (depth >= 0.).then(|| Interference {
+                magnitude: depth,
+                position: point + depth * normal,
+                normal,
+            })
+        }
+
+        fn mesh(&self) -> Mesh {
+            let (size, height) = (self.size as f32, (self.grade * self.size) as f32);
+            let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+            mesh.insert_attribute(
+                Mesh::ATTRIBUTE_POSITION,
+                vec![[0., 0., 0.], [size, 0., height], [0., size, 0.]],
+            );
+            mesh
+        }
+    }
+
+    #[test]
+    fn custom_element_gets_the_defaults() {
+        let wall = Wall {
+            size: 4.,
+            grade: 0.5,
+        };
+        assert_eq!(wall.friction(), 1.);
+        assert_eq!(wall.material_color(), Color::rgb_u8(100, 100, 100));
+        assert!(wall.material_texture().is_none());
+        assert_eq!(wall.description(), "Custom piece");
+
+        // and works in a grid like the built-in pieces
+        let grid = GridTerrain::new(vec![vec![flat_plane(4.), Box::new(wall)]], [4., 4.]);
+        let point = Vector::new(6., 2., 0.5);
+        let contact = grid.interference(point).expect("under the wall");
+        let normal = Vector::new(-0.5, 0., 1.).normalize();
+        assert!((contact.magnitude - 0.5 * normal.z).abs() < EPSILON);
+        assert_close(&contact.normal, &normal);
+        assert_eq!(grid.friction_at(&point), 1.);
+        assert!(grid.interference(Vector::new(6., 2., 1.5)).is_none());
+
+        let mut obj = Vec::new();
+        grid.write_obj(&mut obj).unwrap();
+        assert!(!obj.is_empty());
+    }
+}
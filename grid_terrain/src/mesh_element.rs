@@ -0,0 +1,258 @@
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, VertexAttributeValues},
+        render_resource::PrimitiveTopology,
+    },
+};
+use rigid_body::sva::Vector;
+
+use crate::{
+    spatial::{Aabb, SpatialGrid},
+    GridElement, Interference,
+};
+
+// A terrain piece made from an arbitrary triangle mesh (e.g. a scanned track section),
+// in the element's local coordinates like the other pieces (0..size in x and y).
+// Triangles wind counterclockwise seen from above, their normals point out of the ground.
+//
+// A point collides when it is behind the closest triangle (on the side its normal points
+// away from) and no more than max_depth from it. Compared to the parametric pieces:
+// - precision: the surface is exactly the triangles, so contacts are only as smooth as
+//   the mesh; normals are per triangle and jump at the edges (GridTerrain normal
+//   blending only smooths the cell seams)
+// - speed: triangles are binned on an x-y grid, so a query only checks the triangles
+//   in the bins within max_depth of the point. That is close to constant time for
+//   meshes of evenly sized triangles, but a large max_depth or a few huge triangles
+//   spanning many bins make every query check more triangles
+// - thin walls thinner than max_depth can be pushed through from behind; keep max_depth
+//   below the thickness of the thinnest part of the mesh
+pub struct MeshElement {
+    triangles: Vec<[Vector; 3]>,
+    normals: Vec<Vector>, // unit normal of each triangle
+    bins: SpatialGrid, // triangles by their bounding boxes
+    pub max_depth: f64, // deepest interference reported (m)
+    pub color: Color,
+}
+
+const DEFAULT_MAX_DEPTH: f64 = 0.5;
+
+impl MeshElement {
+    // Builds the element from vertex positions and triangles (indices into `vertices`).
+    // Degenerate triangles (zero area) are dropped.
+    pub fn new(vertices: &[[f64; 3]], triangles: &[[usize; 3]]) -> Self {
+        let mut corners = Vec::with_capacity(triangles.len());
+        let mut normals = Vec::with_capacity(triangles.len());
+        for triangle in triangles {
+            let [a, b, c] = triangle.map(|index| {
+                let [x, y, z] = vertices[index];
+                Vector::new(x, y, z)
+            });
+            let normal = (b - a).cross(&(c - a));
+            if normal.norm() <= f64::EPSILON {
+                continue;
+            }
+            corners.push([a, b, c]);
+            normals.push(normal.normalize());
+        }
+        let boxes: Vec<Aabb> = corners.iter().map(Aabb::from_points).collect();
+        let bins = SpatialGrid::new(&boxes);
+        Self {
+            triangles: corners,
+            normals,
+            bins,
+            max_depth: DEFAULT_MAX_DEPTH,
+            color: Color::rgb_u8(100, 100, 100),
+        }
+    }
+
+    // Builds the element from a triangle list mesh (e.g. loaded from a file).
+    // None if the mesh is not a triangle list or has no positions.
+    pub fn from_mesh(mesh: &Mesh) -> Option<Self> {
+        if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+            return None;
+        }
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return None;
+        };
+        let vertices: Vec<[f64; 3]> = positions
+            .iter()
+            .map(|position| position.map(|x| x as f64))
+            .collect();
+        let indices: Vec<usize> = match mesh.indices() {
+            Some(indices) => indices.iter().collect(),
+            None => (0..vertices.len()).collect(),
+        };
+        let triangles: Vec<[usize; 3]> = indices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+            .collect();
+        Some(Self::new(&vertices, &triangles))
+    }
+
+    pub fn with_max_depth(mut self, max_depth: f64) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+// Closest point to p on the triangle abc (Ericson, Real-Time Collision Detection 5.1.5)
+fn closest_point_on_triangle(p: &Vector, [a, b, c]: &[Vector; 3]) -> Vector {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+    let d1 = ab.dot(&ap);
+    let d2 = ac.dot(&ap);
+    if d1 <= 0. && d2 <= 0. {
+        return *a;
+    }
+
+    let bp = p - b;
+    let d3 = ab.dot(&bp);
+    let d4 = ac.dot(&bp);
+    if d3 >= 0. && d4 <= d3 {
+        return *b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0. && d1 >= 0. && d3 <= 0. {
+        return a + ab * (d1 / (d1 - d3));
+    }
+
+    let cp = p - c;
+    let d5 = ab.dot(&cp);
+    let d6 = ac.dot(&cp);
+    if d6 >= 0. && d5 <= d6 {
+        return *c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0. && d2 >= 0. && d6 <= 0. {
+        return a + ac * (d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0. && d4 - d3 >= 0. && d5 - d6 >= 0. {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+
+    let denom = 1. / (va + vb + vc);
+    a + ab * (vb * denom) + ac * (vc * denom)
+}
+
+impl GridElement for MeshElement {
+    fn interference(&self, point: Vector) -> Option<Interference> {
+        // closest triangle; on a shared edge or corner (equal distances) prefer a
+        // triangle the point is behind, so points under a ridge still collide
+        let mut closest: Option<(f64, bool, Vector, usize)> = None;
+        for index in self.bins.query(&Aabb::around(&point, self.max_depth)) {
+            let surface = closest_point_on_triangle(&point, &self.triangles[index]);
+            let distance = (point - surface).norm();
+            if distance > self.max_depth {
+                continue;
+            }
+            let behind = (point - surface).dot(&self.normals[index]) < 0.;
+            let better = match closest {
+                None => true,
+                Some((best, best_behind, _, _)) => {
+                    distance < best - 1e-9 || (distance <= best + 1e-9 && behind && !best_behind)
+                }
+            };
+            if better {
+                closest = Some((distance, behind, surface, index));
+            }
+        }
+
+        let (distance, behind, surface, index) = closest?;
+        if !behind {
+            return None;
+        }
+        Some(Interference {
+            magnitude: distance,
+            position: surface,
+            normal: self.normals[index],
+        })
+    }
+
+    // Flat shaded: every triangle gets its own vertices with the triangle normal
+    fn mesh(&self) -> Mesh {
+        let mut positions: Vec<[f32; 3]> = Vec::with_capacity(3 * self.triangles.len());
+        let mut normals: Vec<[f32; 3]> = Vec::with_capacity(3 * self.triangles.len());
+        let mut uvs: Vec<[f32; 2]> = Vec::with_capacity(3 * self.triangles.len());
+        for (triangle, normal) in self.triangles.iter().zip(self.normals.iter()) {
+            for corner in triangle {
+                positions.push([corner.x as f32, corner.y as f32, corner.z as f32]);
+                normals.push([normal.x as f32, normal.y as f32, normal.z as f32]);
+                uvs.push([corner.x as f32, corner.y as f32]);
+            }
+        }
+        let indices: Vec<u32> = (0..positions.len() as u32).collect();
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh
+    }
+
+    fn material_color(&self) -> Color {
+        self.color
+    }
+
+    fn description(&self) -> String {
+        format!("Mesh: {} triangles", self.triangles.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 4 m square at z = 0.2, split along its diagonal
+    fn flat_quad() -> MeshElement {
+        let vertices = [[0., 0., 0.2], [4., 0., 0.2], [4., 4., 0.2], [0., 4., 0.2]];
+        MeshElement::new(&vertices, &[[0, 1, 2], [0, 2, 3]])
+    }
+
+    // (within f32 precision, for elements rebuilt from a render mesh)
+    fn assert_flat_contacts(element: &MeshElement) {
+        // under either triangle and on the shared diagonal
+        for (x, y) in [(3., 1.), (1., 3.), (2., 2.), (0.5, 0.5)] {
+            let point = Vector::new(x, y, 0.05);
+            let contact = element.interference(point).expect("under the quad");
+            assert!((contact.magnitude - 0.15).abs() < 1e-6, "({}, {})", x, y);
+            assert!((contact.normal - Vector::z()).norm() < 1e-6);
+            assert!((contact.position - Vector::new(x, y, 0.2)).norm() < 1e-6);
+        }
+        // above it, deeper than max_depth, or off to the side
+        assert!(element.interference(Vector::new(2., 2., 0.3)).is_none());
+        assert!(element.interference(Vector::new(2., 2., 0.2 - 0.6)).is_none());
+        assert!(element.interference(Vector::new(5., 2., 0.1)).is_none());
+    }
+
+    #[test]
+    fn flat_quad_collides_like_a_plane() {
+        let quad = flat_quad();
+        assert_eq!(quad.description(), "Mesh: 2 triangles");
+        assert_flat_contacts(&quad);
+
+        // rebuilt from its own render mesh it collides the same
+        let rebuilt = MeshElement::from_mesh(&quad.mesh()).expect("a triangle list");
+        assert_flat_contacts(&rebuilt);
+    }
+
+    #[test]
+    fn degenerate_triangles_are_dropped() {
+        let vertices = [[0., 0., 0.], [4., 0., 0.], [4., 4., 0.], [8., 0., 0.]];
+        let element = MeshElement::new(&vertices, &[[0, 1, 2], [0, 1, 3]]);
+        assert_eq!(element.description(), "Mesh: 1 triangles");
+    }
+}
@@ -1,12 +1,27 @@
 use rigid_body::sva::Vector;
 
 // Defines possible mirror transformations for terrain pieces
-#[derive(Default)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mirror {
     #[default]
     None,
     XZ,  // Mirror across XZ plane
     YZ,  // Mirror across YZ plane
+    Diagonal,      // Mirror across the x = y diagonal
+    AntiDiagonal,  // Mirror across the x + y = size diagonal
+}
+
+impl Mirror {
+    // Next mirror in the order above, back to None after AntiDiagonal
+    pub fn next(&self) -> Self {
+        match self {
+            Mirror::None => Mirror::XZ,
+            Mirror::XZ => Mirror::YZ,
+            Mirror::YZ => Mirror::Diagonal,
+            Mirror::Diagonal => Mirror::AntiDiagonal,
+            Mirror::AntiDiagonal => Mirror::None,
+        }
+    }
 }
 
 // Mirrors a mesh's geometry based on specified mirror type
@@ -48,6 +63,38 @@ pub fn mirror_mesh(
                 indices[i][2] = ind1;
             }
         }
+        Mirror::Diagonal => {
+            // Swap X and Y coordinates and normals
+            for i in 0..positions.len() {
+                positions[i].swap(0, 1);
+                normals[i].swap(0, 1);
+            }
+            // Fix triangle winding order
+            for i in 0..indices.len() {
+                let ind1 = indices[i][1];
+                let ind2 = indices[i][2];
+                indices[i][1] = ind2;
+                indices[i][2] = ind1;
+            }
+        }
+        Mirror::AntiDiagonal => {
+            // Swap and flip X and Y coordinates and normals
+            for i in 0..positions.len() {
+                let x = positions[i][0];
+                positions[i][0] = -positions[i][1] + size;
+                positions[i][1] = -x + size;
+                let nx = normals[i][0];
+                normals[i][0] = -normals[i][1];
+                normals[i][1] = -nx;
+            }
+            // Fix triangle winding order
+            for i in 0..indices.len() {
+                let ind1 = indices[i][1];
+                let ind2 = indices[i][2];
+                indices[i][1] = ind2;
+                indices[i][2] = ind1;
+            }
+        }
     }
 }
 
@@ -61,5 +108,15 @@ pub fn mirror_point(point: &mut Vector, size: f64, mirror: &Mirror) {
         Mirror::YZ => {
             point.x = size - point.x;
         }
+        Mirror::Diagonal => {
+            let x = point.x;
+            point.x = point.y;
+            point.y = x;
+        }
+        Mirror::AntiDiagonal => {
+            let x = point.x;
+            point.x = size - point.y;
+            point.y = size - x;
+        }
     }
 }
\ No newline at end of file
@@ -9,15 +9,16 @@ use crate::{GridElement, Interference};
 pub struct Plane {
     pub size: [f64; 2],           // Width and height
     pub subdivisions: u32,        // Level of mesh detail
+    pub height: f64,              // z of the surface (0 for the ground level)
 }
 
 impl GridElement for Plane {
-    // Returns collision data if point is below plane (z < 0)
+    // Returns collision data if point is below plane (z < height)
     fn interference(&self, point: Vector) -> Option<Interference> {
-        if point.z < 0. {
+        if point.z < self.height {
             return Some(Interference {
-                magnitude: -point.z,
-                position: Vector::new(point.x, point.y, 0.),
+                magnitude: self.height - point.z,
+                position: Vector::new(point.x, point.y, self.height),
                 normal: Vector::z(),
             });
         } else {
@@ -43,7 +44,11 @@ impl GridElement for Plane {
             for x in 0..x_vertex_count {
                 let tx = x as f32 / (x_vertex_count - 1) as f32;
                 let ty = y as f32 / (y_vertex_count - 1) as f32;
-                positions.push([tx * self.size[0] as f32, ty * self.size[1] as f32, 0.0]);
+                positions.push([
+                    tx * self.size[0] as f32,
+                    ty * self.size[1] as f32,
+                    self.height as f32,
+                ]);
                 normals.push(up);
                 uvs.push([tx, 1.0 - ty]);
             }
@@ -69,4 +74,12 @@ impl GridElement for Plane {
         mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
         mesh
     }
+
+    fn description(&self) -> String {
+        if self.height == 0. {
+            "Plane".to_string()
+        } else {
+            format!("Plane: z = {:.2} m", self.height)
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,180 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+use rigid_body::sva::Vector;
+
+use crate::{
+    rotate::{rotate_mesh, rotate_point},
+    GridElement, Interference, Rotate, RotationDirection,
+};
+
+// Fraction of the ramp length taken up by the lip
+const LIP_FRACTION: f64 = 0.1;
+
+// Defines a jump ramp with given size, height, lip height and rotation
+// Unrotated, the car drives along +y: the incline rises from the ground at y = 0
+// to `height`, then a short, steeper lip adds `lip_height` before the ramp ends in a
+// vertical drop at y = size. Nothing past the drop belongs to the ramp, so a car
+// going fast enough leaves the ground there.
+#[derive(Default)]
+pub struct Ramp {
+    pub size: f64,       // Width/length of the ramp base
+    pub height: f64,     // Height at the top of the incline
+    pub lip_height: f64, // Extra height gained over the lip
+    pub rotate: Rotate,  // Rotation of the ramp
+}
+
+impl Ramp {
+    // Length of the incline (the lip takes up the rest of the ramp)
+    fn incline_length(&self) -> f64 {
+        self.size * (1. - LIP_FRACTION)
+    }
+
+    // Surface height and (unnormalized) surface normal at distance y along the ramp
+    fn surface(&self, y: f64) -> (f64, Vector) {
+        let incline_length = self.incline_length();
+        let lip_length = self.size - incline_length;
+        if y <= incline_length {
+            (
+                self.height * y / incline_length,
+                Vector::new(0., -self.height, incline_length),
+            )
+        } else {
+            (
+                self.height + self.lip_height * (y - incline_length) / lip_length,
+                Vector::new(0., -self.lip_height, lip_length),
+            )
+        }
+    }
+}
+
+impl GridElement for Ramp {
+    fn interference(&self, mut point: Vector) -> Option<Interference> {
+        // Transform point into the ramp's local space
+        rotate_point(&mut point, self.size, &self.rotate, RotationDirection::Reverse);
+        let size = self.size;
+
+        // Past the lip (or beside the ramp) there is no contact
+        if point.x < 0.0 || point.x > size || point.y < 0.0 || point.y > size {
+            return None;
+        }
+
+        let (height, normal) = self.surface(point.y);
+        if point.z > height {
+            return None;
+        }
+
+        // Penetration measured along the normal of the incline or lip
+        let normal = normal.normalize();
+        let normal_interference = (height - point.z) * normal.z;
+
+        // If the point is closer to the vertical drop at the back than to the top surface
+        // (e.g. reversing into the ramp from behind)
+        let back_interference = size - point.y;
+        if point.z > 0.0 && back_interference < normal_interference {
+            let mut interference = Interference {
+                magnitude: back_interference,
+                position: point + back_interference * Vector::y(),
+                normal: Vector::y(),
+            };
+            interference.rotate(size, &self.rotate, RotationDirection::Forward);
+            return Some(interference);
+        }
+
+        let mut interference = Interference {
+            magnitude: normal_interference,
+            position: point + normal_interference * normal,
+            normal,
+        };
+        interference.rotate(size, &self.rotate, RotationDirection::Forward);
+        Some(interference)
+    }
+
+    // Creates the incline, the lip, the vertical drop and both sides
+    fn mesh(&self) -> Mesh {
+        let size = self.size as f32;
+        let height = self.height as f32;
+        let top = (self.height + self.lip_height) as f32;
+        let incline_length = self.incline_length() as f32;
+        let lip_length = size - incline_length;
+        let incline_normal = Vec3::new(0., -height, incline_length).normalize().to_array();
+        let lip_normal = Vec3::new(0., -(top - height), lip_length).normalize().to_array();
+
+        let mut positions: Vec<[f32; 3]> = Vec::new();
+        let mut normals: Vec<[f32; 3]> = Vec::new();
+        let mut uvs: Vec<[f32; 2]> = Vec::new();
+        let mut indices: Vec<[u32; 3]> = Vec::new();
+
+        // Adds a face from its vertices and triangles (indices local to the face)
+        let mut add_face = |vertices: &[[f32; 3]], normal: [f32; 3], triangles: &[[u32; 3]]| {
+            let offset = positions.len() as u32;
+            for vertex in vertices {
+                positions.push(*vertex);
+                normals.push(normal);
+                uvs.push([vertex[0] / size, 1.0 - vertex[1] / size]);
+            }
+            for triangle in triangles {
+                indices.push([triangle[0] + offset, triangle[1] + offset, triangle[2] + offset]);
+            }
+        };
+
+        // Incline
+        add_face(
+            &[
+                [0., 0., 0.],
+                [size, 0., 0.],
+                [size, incline_length, height],
+                [0., incline_length, height],
+            ],
+            incline_normal,
+            &[[0, 1, 3], [2, 3, 1]],
+        );
+        // Lip
+        add_face(
+            &[
+                [0., incline_length, height],
+                [size, incline_length, height],
+                [size, size, top],
+                [0., size, top],
+            ],
+            lip_normal,
+            &[[0, 1, 3], [2, 3, 1]],
+        );
+        // Vertical drop at the back
+        add_face(
+            &[[0., size, 0.], [size, size, 0.], [size, size, top], [0., size, top]],
+            [0., 1., 0.],
+            &[[0, 2, 1], [0, 3, 2]],
+        );
+        // Left side
+        add_face(
+            &[[0., 0., 0.], [0., incline_length, height], [0., size, top], [0., size, 0.]],
+            [-1., 0., 0.],
+            &[[0, 1, 3], [1, 2, 3]],
+        );
+        // Right side
+        add_face(
+            &[[size, 0., 0.], [size, incline_length, height], [size, size, top], [size, size, 0.]],
+            [1., 0., 0.],
+            &[[0, 3, 1], [1, 3, 2]],
+        );
+
+        rotate_mesh(size, &mut positions, &mut normals, &mut uvs, &self.rotate);
+        let indices: Vec<u32> = indices.into_iter().flatten().collect();
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Ramp: height {} m, lip height {} m, rotate {:?}",
+            self.height, self.lip_height, self.rotate
+        )
+    }
+}
@@ -7,7 +7,7 @@ pub enum RotationDirection {
 }
 
 // Defines rotation amounts in degrees
-#[derive(Default)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Rotate {
    #[default]
    Zero,
@@ -16,6 +16,18 @@ pub enum Rotate {
    TwoSeventy,  // 270 degrees
 }
 
+impl Rotate {
+   // Next quarter turn, back to Zero after TwoSeventy
+   pub fn next(&self) -> Self {
+       match self {
+           Rotate::Zero => Rotate::Ninety,
+           Rotate::Ninety => Rotate::OneEighty,
+           Rotate::OneEighty => Rotate::TwoSeventy,
+           Rotate::TwoSeventy => Rotate::Zero,
+       }
+   }
+}
+
 // Rotates mesh data (positions, normals, UVs) around origin
 pub fn rotate_mesh(
    size: f32,
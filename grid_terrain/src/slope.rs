@@ -43,10 +43,20 @@ use bevy::{
  
         if normal_interference < 0.0 {
             return None;
+        } else if point.z < 0.0 && -point.z < normal_interference {
+            // Below the ground plane near the low edge the ground is the closer
+            // surface, push the point straight up like the flat part of a step
+            let mut interference = Interference {
+                magnitude: -point.z,
+                position: point - point.z * Vector::z(),
+                normal: Vector::z(),
+            };
+            interference.rotate(size, &self.rotate, RotationDirection::Forward);
+            return Some(interference);
         } else {
             let mut interference = Interference {
                 magnitude: normal_interference,
-                position: point - normal_interference * top_normal,
+                position: point + normal_interference * top_normal,
                 normal: top_normal,
             };
             interference.rotate(size, &self.rotate, RotationDirection::Forward);
@@ -91,4 +101,63 @@ use bevy::{
         mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
         mesh
     }
- }
\ No newline at end of file
+
+    fn description(&self) -> String {
+        format!("Slope: height {} m, rotate {:?}", self.height, self.rotate)
+    }
+ }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROTATIONS: [Rotate; 4] = [
+        Rotate::Zero,
+        Rotate::Ninety,
+        Rotate::OneEighty,
+        Rotate::TwoSeventy,
+    ];
+
+    fn slope(rotate: Rotate) -> Slope {
+        Slope {
+            size: 4.,
+            height: 1.,
+            rotate,
+        }
+    }
+
+    // A point given in the unrotated slope's coordinates, moved onto the rotated slope
+    fn rotated(mut point: Vector, rotate: &Rotate) -> Vector {
+        rotate_point(&mut point, 4., rotate, RotationDirection::Forward);
+        point
+    }
+
+    #[test]
+    fn points_below_the_low_edge_touch_the_ground() {
+        for rotate in ROTATIONS {
+            // the low edge is at y = size before rotating
+            for local in [Vector::new(2., 3.9, -0.05), Vector::new(0.5, 3.5, -0.02)] {
+                let point = rotated(local, &rotate);
+                let contact = slope(rotate).interference(point).expect("under the base");
+                assert!((contact.magnitude + local.z).abs() < 1e-9, "{:?} {:?}", rotate, local);
+                assert!((contact.normal - Vector::z()).norm() < 1e-9);
+                assert!((contact.position - (point - local.z * Vector::z())).norm() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn points_under_the_face_touch_the_face() {
+        let depth = 2. / 17f64.sqrt();
+        let face_normal = Vector::new(0., 1., 4.).normalize();
+        for rotate in ROTATIONS {
+            // (2, 2, 0) is under the middle of the face, where the surface is at z = 0.5
+            let point = rotated(Vector::new(2., 2., 0.), &rotate);
+            let contact = slope(rotate).interference(point).expect("under the face");
+            let normal = rotated(face_normal, &rotate) - rotated(Vector::zeros(), &rotate);
+            assert!((contact.magnitude - depth).abs() < 1e-9, "{:?}", rotate);
+            assert!((contact.normal - normal).norm() < 1e-9, "{:?}", rotate);
+            assert!(contact.normal.z > 0.);
+            assert!((contact.position - (point + depth * normal)).norm() < 1e-9);
+        }
+    }
+}
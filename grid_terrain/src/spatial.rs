@@ -0,0 +1,134 @@
+use rigid_body::sva::Vector;
+
+// Broadphase for custom terrain pieces with many parts (triangles, boxes, ...):
+// GridTerrain already finds the cell under a point directly, this narrows down which
+// parts of the piece in that cell are worth an exact test.
+
+// Axis aligned bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vector,
+    pub max: Vector,
+}
+
+impl Aabb {
+    // Smallest box around the points (an inverted, empty box for no points)
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vector>) -> Self {
+        let mut aabb = Self {
+            min: Vector::repeat(f64::INFINITY),
+            max: Vector::repeat(f64::NEG_INFINITY),
+        };
+        for point in points {
+            aabb.min = aabb.min.inf(point);
+            aabb.max = aabb.max.sup(point);
+        }
+        aabb
+    }
+
+    // Box around a point, `radius` out in every direction
+    pub fn around(point: &Vector, radius: f64) -> Self {
+        Self {
+            min: point - Vector::repeat(radius),
+            max: point + Vector::repeat(radius),
+        }
+    }
+
+    pub fn union(&self, other: &Aabb) -> Self {
+        Self {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+}
+
+// Uniform grid hash over the x-y plane: every bin lists the items whose bounding box
+// overlaps it. Suited to terrain, where items are spread over the ground plane and a
+// query only needs the few bins around a point. z is ignored.
+// - bin_size: side of a square bin (m)
+pub struct SpatialGrid {
+    min: [f64; 2],
+    bin_size: f64,
+    counts: [usize; 2],
+    bins: Vec<Vec<usize>>, // item indices, row by row
+}
+
+impl SpatialGrid {
+    // Grid for items with the given bounding boxes (the index of a box is the item id),
+    // with about one item per bin when the items are spread evenly
+    pub fn new(boxes: &[Aabb]) -> Self {
+        let bounds = Self::bounds(boxes);
+        let extent = (bounds.max.x - bounds.min.x)
+            .max(bounds.max.y - bounds.min.y)
+            .max(f64::EPSILON);
+        let per_side = (boxes.len() as f64).sqrt().ceil().max(1.);
+        Self::with_bin_size(boxes, extent / per_side)
+    }
+
+    pub fn with_bin_size(boxes: &[Aabb], bin_size: f64) -> Self {
+        if boxes.is_empty() {
+            return Self {
+                min: [0., 0.],
+                bin_size,
+                counts: [0, 0],
+                bins: Vec::new(),
+            };
+        }
+        let bounds = Self::bounds(boxes);
+        let min = [bounds.min.x, bounds.min.y];
+        let counts = [0, 1].map(|axis| {
+            ((bounds.max[axis] - bounds.min[axis]) / bin_size).floor() as usize + 1
+        });
+
+        let mut grid = Self {
+            min,
+            bin_size,
+            counts,
+            bins: vec![Vec::new(); counts[0] * counts[1]],
+        };
+        for (index, aabb) in boxes.iter().enumerate() {
+            let (x_range, y_range) = grid.bin_range(aabb);
+            for y in y_range {
+                for x in x_range.clone() {
+                    grid.bins[y * counts[0] + x].push(index);
+                }
+            }
+        }
+        grid
+    }
+
+    fn bounds(boxes: &[Aabb]) -> Aabb {
+        boxes
+            .iter()
+            .fold(Aabb::from_points([]), |bounds, aabb| bounds.union(aabb))
+    }
+
+    // Bins overlapping the box in x-y (clamped to the grid)
+    fn bin_range(&self, aabb: &Aabb) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        let [x, y] = [0, 1].map(|axis| {
+            let first = ((aabb.min[axis] - self.min[axis]) / self.bin_size)
+                .floor()
+                .max(0.) as usize;
+            let last = ((aabb.max[axis] - self.min[axis]) / self.bin_size).floor();
+            if last < 0. || first >= self.counts[axis] {
+                return 0..0;
+            }
+            first..(last as usize).min(self.counts[axis] - 1) + 1
+        });
+        (x, y)
+    }
+
+    // Items whose bins overlap the box in x-y, each listed once in ascending order.
+    // This can include items that don't overlap the box themselves.
+    pub fn query(&self, aabb: &Aabb) -> Vec<usize> {
+        let (x_range, y_range) = self.bin_range(aabb);
+        let mut items = Vec::new();
+        for y in y_range {
+            for x in x_range.clone() {
+                items.extend_from_slice(&self.bins[y * self.counts[0] + x]);
+            }
+        }
+        items.sort_unstable();
+        items.dedup();
+        items
+    }
+}
@@ -205,4 +205,11 @@ impl GridElement for Step {
         mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
         mesh
     }
+
+    fn description(&self) -> String {
+        format!(
+            "Step: height {} m, rotate {:?}, mirror {:?}",
+            self.height, self.rotate, self.mirror
+        )
+    }
 }
\ No newline at end of file
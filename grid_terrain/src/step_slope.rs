@@ -167,4 +167,87 @@ impl GridElement for StepSlope {
 
         mesh
     }
-}
\ No newline at end of file
+
+    fn description(&self) -> String {
+        format!(
+            "Step slope: height {} m, rotate {:?}, mirror {:?}",
+            self.height, self.rotate, self.mirror
+        )
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::render::mesh::VertexAttributeValues;
+
+    fn mirrored(mirror: Mirror) -> StepSlope {
+        StepSlope {
+            size: 4.,
+            height: 1.,
+            rotate: Rotate::Zero,
+            mirror,
+        }
+    }
+
+    fn assert_contact(step_slope: &StepSlope, point: Vector, magnitude: f64, normal: Vector) {
+        let interference = step_slope
+            .interference(point)
+            .expect("the point is under the step");
+        assert!((interference.magnitude - magnitude).abs() < 1e-9, "{:?}", point);
+        assert!((interference.normal - normal).norm() < 1e-9, "{:?}", interference.normal);
+        assert!(interference.normal.z > 0.);
+        // the contact is on the surface, magnitude away from the point along the normal
+        let surface = point + magnitude * normal;
+        assert!((interference.position - surface).norm() < 1e-9);
+    }
+
+    #[test]
+    fn diagonal_mirrors_swap_the_step_across_the_diagonal() {
+        // unmirrored, (3, 2, 0) is 2/sqrt(17) under the sloped face with normal (0, 1, 4)/sqrt(17)
+        let depth = 2. / 17f64.sqrt();
+        let slope_normal = Vector::new(0., 1., 4.).normalize();
+        assert_contact(&mirrored(Mirror::None), Vector::new(3., 2., 0.), depth, slope_normal);
+
+        // x = y diagonal: the face is at y > 2 and faces +x
+        let diagonal = mirrored(Mirror::Diagonal);
+        let facing_x = Vector::new(1., 0., 4.).normalize();
+        assert_contact(&diagonal, Vector::new(2., 3., 0.), depth, facing_x);
+        assert_contact(&diagonal, Vector::new(3., 1., -0.1), 0.1, Vector::z());
+
+        // x + y = size diagonal: (x, y) -> (size - y, size - x), the face is at y < 2 and faces -x
+        let anti_diagonal = mirrored(Mirror::AntiDiagonal);
+        let facing_minus_x = Vector::new(-1., 0., 4.).normalize();
+        assert_contact(&anti_diagonal, Vector::new(2., 1., 0.), depth, facing_minus_x);
+        assert_contact(&anti_diagonal, Vector::new(1., 3., -0.1), 0.1, Vector::z());
+    }
+
+    #[test]
+    fn diagonal_mirrored_meshes_keep_winding_and_normals_together() {
+        for mirror in [Mirror::Diagonal, Mirror::AntiDiagonal] {
+            let mesh = mirrored(mirror).mesh();
+            let Some(VertexAttributeValues::Float32x3(positions)) =
+                mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+            else {
+                panic!("no positions");
+            };
+            let Some(VertexAttributeValues::Float32x3(normals)) =
+                mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+            else {
+                panic!("no normals");
+            };
+            let Some(Indices::U32(indices)) = mesh.indices() else {
+                panic!("no indices");
+            };
+
+            // the ground and sloped faces still point up
+            assert!(normals.iter().all(|normal| normal[2] >= 0.));
+            // counter-clockwise triangles (seen from outside) face the way their normals do
+            for triangle in indices.chunks(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(positions[triangle[i] as usize]));
+                let face = (b - a).cross(c - a);
+                let normal = Vec3::from(normals[triangle[0] as usize]);
+                assert!(face.dot(normal) > 0., "{:?} triangle {:?}", mirror, triangle);
+            }
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+use rigid_body::sva::Vector;
+
+use crate::{GridElement, Interference};
+
+// Vertices along each side of the mesh
+const MESH_VERTICES: u32 = 100;
+
+// Steps moving the contact point from straight below the query point to the closest
+// point of the surface (each projects onto the tangent plane and back onto the surface)
+const PROJECTION_STEPS: usize = 2;
+
+// A height field z = height(x, y) over the element, in local coordinates (0..size)
+// - height: surface height (m)
+// - gradient: (dz/dx, dz/dy) of the height, the surface normal is built from it
+// Unlike Function, which multiplies terms that mostly vary along one axis, any 2D
+// surface can be given directly. The height should match its neighbors at the cell
+// edges, the element doesn't blend into them.
+pub struct Surface2D {
+    pub size: [f64; 2],
+    pub height: Box<dyn Fn(f64, f64) -> f64>,
+    pub gradient: Box<dyn Fn(f64, f64) -> (f64, f64)>,
+}
+
+impl Default for Surface2D {
+    fn default() -> Self {
+        Self {
+            size: [10., 10.],
+            height: Box::new(|x, y| 0.1 * x.cos() * y.cos()),
+            gradient: Box::new(|x, y| (-0.1 * x.sin() * y.cos(), -0.1 * x.cos() * y.sin())),
+        }
+    }
+}
+
+impl Surface2D {
+    // Surface point and unit normal above (x, y), kept on the element
+    fn surface_point(&self, x: f64, y: f64) -> (Vector, Vector) {
+        let x = x.clamp(0., self.size[0]);
+        let y = y.clamp(0., self.size[1]);
+        let (dx, dy) = (self.gradient)(x, y);
+        let point = Vector::new(x, y, (self.height)(x, y));
+        (point, Vector::new(-dx, -dy, 1.).normalize())
+    }
+}
+
+impl GridElement for Surface2D {
+    fn interference(&self, point: Vector) -> Option<Interference> {
+        if point.x < 0.0 || point.x > self.size[0] || point.y < 0.0 || point.y > self.size[1] {
+            return None;
+        }
+
+        let (mut contact_point, mut normal) = self.surface_point(point.x, point.y);
+        if point.z > contact_point.z {
+            return None;
+        }
+
+        // The penetration is measured along the normal, not straight up, so it matches the
+        // true surface on slopes. Each step moves the contact point to where the tangent
+        // plane is closest to the query point.
+        for _ in 0..PROJECTION_STEPS {
+            let depth = (contact_point - point).dot(&normal);
+            let projected = point + normal * depth;
+            (contact_point, normal) = self.surface_point(projected.x, projected.y);
+        }
+        let magnitude = (contact_point - point).dot(&normal);
+        if magnitude <= 0. {
+            // can only happen on a strongly curved surface, fall back to the vertical contact
+            let (contact_point, normal) = self.surface_point(point.x, point.y);
+            return Some(Interference {
+                magnitude: (contact_point.z - point.z) * normal.z,
+                position: contact_point,
+                normal,
+            });
+        }
+
+        Some(Interference {
+            magnitude,
+            position: contact_point,
+            normal,
+        })
+    }
+
+    fn mesh(&self) -> Mesh {
+        let size = [self.size[0] as f32, self.size[1] as f32];
+        let count = MESH_VERTICES;
+
+        let num_vertices = (count * count) as usize;
+        let num_indices = ((count - 1) * (count - 1) * 6) as usize;
+
+        let mut positions: Vec<[f32; 3]> = Vec::with_capacity(num_vertices);
+        let mut normals: Vec<[f32; 3]> = Vec::with_capacity(num_vertices);
+        let mut uvs: Vec<[f32; 2]> = Vec::with_capacity(num_vertices);
+        let mut indices: Vec<u32> = Vec::with_capacity(num_indices);
+
+        for y_vert in 0..count {
+            for x_vert in 0..count {
+                let x_normalized = x_vert as f32 / (count - 1) as f32;
+                let y_normalized = y_vert as f32 / (count - 1) as f32;
+
+                let x = (x_normalized * size[0]) as f64;
+                let y = (y_normalized * size[1]) as f64;
+                let (point, normal) = self.surface_point(x, y);
+
+                positions.push([point.x as f32, point.y as f32, point.z as f32]);
+                normals.push([normal.x as f32, normal.y as f32, normal.z as f32]);
+                uvs.push([x_normalized, 1. - y_normalized]);
+            }
+        }
+
+        for y in 0..count - 1 {
+            for x in 0..count - 1 {
+                let quad = y * count + x;
+                indices.push(quad);
+                indices.push(quad + 1);
+                indices.push(quad + count);
+                indices.push(quad + count + 1);
+                indices.push(quad + count);
+                indices.push(quad + 1);
+            }
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh
+    }
+
+    fn description(&self) -> String {
+        format!("Surface: {:.1} x {:.1} m height field", self.size[0], self.size[1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::render::mesh::VertexAttributeValues;
+
+    // A 1 m gaussian bump in the middle of a 10 m cell
+    fn bump() -> Surface2D {
+        let height = |x: f64, y: f64| (-((x - 5.).powi(2) + (y - 5.).powi(2)) / 5.).exp();
+        Surface2D {
+            size: [10., 10.],
+            height: Box::new(height),
+            gradient: Box::new(move |x, y| {
+                let z = height(x, y);
+                (-0.4 * z * (x - 5.), -0.4 * z * (y - 5.))
+            }),
+        }
+    }
+
+    // Unit normal from central differences of the height
+    fn finite_difference_normal(surface: &Surface2D, x: f64, y: f64) -> Vector {
+        let h = 1e-5;
+        let dx = ((surface.height)(x + h, y) - (surface.height)(x - h, y)) / (2. * h);
+        let dy = ((surface.height)(x, y + h) - (surface.height)(x, y - h)) / (2. * h);
+        Vector::new(-dx, -dy, 1.).normalize()
+    }
+
+    #[test]
+    fn contact_normal_matches_finite_difference() {
+        let surface = bump();
+        for (x, y) in [(5., 5.), (4., 5.), (6.5, 3.), (3., 7.), (5.5, 5.2), (8., 8.)] {
+            // just under the surface, so the contact stays next to (x, y)
+            let point = Vector::new(x, y, (surface.height)(x, y) - 1e-4);
+            let contact = surface.interference(point).expect("under the surface");
+            let expected = finite_difference_normal(&surface, x, y);
+            assert!((contact.normal - expected).norm() < 1e-3, "({}, {})", x, y);
+            assert!(contact.magnitude > 0. && contact.magnitude <= 1e-4 + 1e-9);
+        }
+        assert!(surface.interference(Vector::new(5., 5., 1.01)).is_none());
+    }
+
+    #[test]
+    fn mesh_normals_match_finite_difference() {
+        let surface = bump();
+        let mesh = surface.mesh();
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("no positions");
+        };
+        let Some(VertexAttributeValues::Float32x3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+        else {
+            panic!("no normals");
+        };
+
+        for (position, normal) in positions.iter().zip(normals) {
+            let (x, y) = (position[0] as f64, position[1] as f64);
+            let expected = finite_difference_normal(&surface, x, y);
+            let normal = Vector::new(normal[0] as f64, normal[1] as f64, normal[2] as f64);
+            assert!((normal - expected).norm() < 1e-4, "({}, {})", x, y);
+            assert!((position[2] as f64 - (surface.height)(x, y)).abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+use rigid_body::sva::Vector;
+
+use crate::{GridElement, Interference};
+
+// Any terrain piece with an image on it, e.g. cells cut from a picture of a track so the
+// parametric terrain looks like the track and still has all its collision detail.
+// The piece collides, meshes and grips exactly like `element`; only its material changes.
+// - element: the piece being textured
+// - texture: base color image, mapped with the piece's mesh UVs (most pieces stretch
+//   the image once over the cell)
+// - tint: multiplies the image colors, white shows the image as it is
+pub struct Textured {
+    pub element: Box<dyn GridElement>,
+    pub texture: Handle<Image>,
+    pub tint: Color,
+}
+
+impl Textured {
+    pub fn new(element: Box<dyn GridElement>, texture: Handle<Image>) -> Self {
+        Self {
+            element,
+            texture,
+            tint: Color::WHITE,
+        }
+    }
+}
+
+impl GridElement for Textured {
+    fn interference(&self, point: Vector) -> Option<Interference> {
+        self.element.interference(point)
+    }
+
+    fn mesh(&self) -> Mesh {
+        self.element.mesh()
+    }
+
+    fn material_color(&self) -> Color {
+        self.tint
+    }
+
+    fn material_texture(&self) -> Option<Handle<Image>> {
+        Some(self.texture.clone())
+    }
+
+    fn friction(&self) -> f64 {
+        self.element.friction()
+    }
+
+    fn description(&self) -> String {
+        format!("{} (textured)", self.element.description())
+    }
+}
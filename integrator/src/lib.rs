@@ -30,6 +30,14 @@ enum SolverSet {
     Post,
 }
 
+// Pauses the integrator: while paused, time and state don't advance, except for
+// one step each time `step_requested` is set (cleared once the step is taken)
+#[derive(Resource, Default, Debug, Clone)]
+pub struct SimControl {
+    pub paused: bool,
+    pub step_requested: bool,
+}
+
 pub struct StateMap<T: Stateful>(pub HashMap<Entity, T::State>);
 
 #[derive(Resource, Clone)]
@@ -134,6 +142,16 @@ fn evaluate_state<T: Stateful>(world: &mut World, state: &StateMap<T>, _t: f64)
 }
 
 pub fn integrator_schedule<T: Stateful>(world: &mut World) {
+    // skip the step while paused, unless a single step was requested
+    if let Some(mut control) = world.get_resource_mut::<SimControl>() {
+        if control.paused {
+            if !control.step_requested {
+                return;
+            }
+            control.step_requested = false;
+        }
+    }
+
     // get the initial state
     let state_0 = world
         .get_resource::<PhysicsState<T>>()
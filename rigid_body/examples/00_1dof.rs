@@ -21,6 +21,7 @@ fn main() {
             simulation_setup: vec![],
             environment_setup: vec![camera_setup],
             name: "example 00_1dof".to_string(),
+            headless: false,
         })
         .add_systems(
             PhysicsSchedule,
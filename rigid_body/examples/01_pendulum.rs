@@ -21,6 +21,7 @@ fn main() {
             simulation_setup: vec![],
             environment_setup: vec![camera_setup],
             name: "example 01_pendulum".to_string(),
+            headless: false,
         })
         .add_systems(Startup, startup_system)
         .add_systems(Startup, environment_startup_system)
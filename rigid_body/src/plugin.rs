@@ -5,13 +5,21 @@ use crate::{
     rendering::startup_rendering,
     structure::{apply_external_forces, loop_1, loop_23},
 };
-use bevy::{app::AppExit, prelude::*};
+use bevy::{
+    app::AppExit, hierarchy::HierarchyPlugin, input::InputPlugin, prelude::*,
+    time::TimeUpdateStrategy, transform::TransformPlugin,
+};
 use bevy_integrator::{
-    initialize_state, integrator_schedule, ExitEvent, PhysicsSchedule, PhysicsScheduleExt, SimTime,
-    Solver,
+    initialize_state, integrator_schedule, ExitEvent, PhysicsSchedule, PhysicsScheduleExt,
+    SimControl, SimTime, Solver,
 };
 use bevy_obj::ObjPlugin;
+use std::time::Duration;
 
+// - headless: run without a window or renderer (e.g. on a server). Only the minimal,
+//   transform, hierarchy and input plugins are added, joints get no meshes, and time
+//   advances exactly one physics step per update, so the simulation runs as fast as
+//   the physics can be computed. Setups must not use render assets or cameras.
 #[derive(Clone)]
 pub struct RigidBodyPlugin {
     pub time: SimTime,
@@ -19,6 +27,7 @@ pub struct RigidBodyPlugin {
     pub environment_setup: Vec<fn(&mut App)>,
     pub solver: Solver,
     pub name: String,
+    pub headless: bool,
 }
 
 impl RigidBodyPlugin {
@@ -28,6 +37,7 @@ impl RigidBodyPlugin {
             .insert_resource(self.time.clone())
             .insert_resource(self.solver)
             .insert_resource(FixedTime::new_from_secs(self.time.dt as f32))
+            .init_resource::<SimControl>()
             .add_systems(FixedUpdate, integrator_schedule::<Joint>);
     }
 }
@@ -49,20 +59,28 @@ impl Plugin for RigidBodyPlugin {
             setup(app);
         }
 
-        app.add_plugins((
-            DefaultPlugins.build().set(WindowPlugin {
-                primary_window: Some(Window {
-                    resolution: (1920., 1080.).into(),
-                    title: self.name.clone(),
-                    resizable: true,
+        if self.headless {
+            app.add_plugins((MinimalPlugins, TransformPlugin, HierarchyPlugin, InputPlugin))
+                .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+                    self.time.dt,
+                )))
+                .add_systems(PostStartup, startup_headless);
+        } else {
+            app.add_plugins((
+                DefaultPlugins.build().set(WindowPlugin {
+                    primary_window: Some(Window {
+                        resolution: (1920., 1080.).into(),
+                        title: self.name.clone(),
+                        resizable: true,
+                        ..default()
+                    }),
                     ..default()
                 }),
-                ..default()
-            }),
-            ObjPlugin,
-        ));
-        app.add_systems(PostStartup, startup_rendering)
-            .add_systems(Update, bevy_joint_positions);
+                ObjPlugin,
+            ));
+            app.add_systems(PostStartup, startup_rendering);
+        }
+        app.add_systems(Update, bevy_joint_positions);
 
         app.add_systems(PostStartup, initialize_state::<Joint>);
     }
@@ -76,6 +94,13 @@ fn create_physics_schedule() -> Schedule {
     physics_schedule
 }
 
+// Headless counterpart of startup_rendering: joints only get a transform, no meshes
+fn startup_headless(mut commands: Commands, joints: Query<Entity, With<Joint>>) {
+    for entity in joints.iter() {
+        commands.entity(entity).insert(SpatialBundle::default());
+    }
+}
+
 fn time_exit_system(time: Res<SimTime>, mut exit: EventWriter<ExitEvent>) {
     if time.is_complete() {
         exit.send(ExitEvent);